@@ -1,16 +1,18 @@
 use core::panic;
-use std::collections::HashMap;
 
 use http::StatusCode;
 use proc_macro2::{self, TokenStream};
 use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    parenthesized, parse::ParseBuffer, punctuated::Punctuated, spanned::Spanned, Expr, ExprAssign,
-    Field, Fields, Ident, Item, ItemEnum, ItemStruct, Lit, LitInt, LitStr, Token, Variant,
+    meta::ParseNestedMeta, parenthesized, parse::ParseBuffer, punctuated::Punctuated,
+    spanned::Spanned, Expr, ExprAssign, ExprLit, Field, Fields, Ident, Item, ItemEnum, ItemStruct,
+    Lit, LitInt, LitStr, Path, Token, Variant,
 };
 
 const FORMAT_FIELD_PREFIX: &str = "__f_";
 
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
 macro_rules! format_field_ident {
     ($fmt:expr) => {
         format_ident!("{FORMAT_FIELD_PREFIX}{}", $fmt)
@@ -36,25 +38,36 @@ fn expand_struct(item: ItemStruct) -> syn::Result<TokenStream> {
 }
 
 fn expand_enum(item: ItemEnum) -> syn::Result<TokenStream> {
+    let enum_args = EnumArg::parse_from_enum(&item)?;
     let variant_args = item
         .variants
         .iter()
         .map(|variant| {
-            let args = Arg::parse_from_variant(variant)?;
+            let mut args = Arg::parse_from_variant(variant)?;
+            if let Some(prefix) = &enum_args.code_prefix {
+                args.insert_code(prefix, variant);
+            }
             Ok((variant, args))
         })
         .collect::<syn::Result<Vec<_>>>()?;
 
     let impl_display_block = impl_display(&item.ident, &variant_args)?;
-    let impl_from_http_error_block = impl_from_http_error(&item.ident, &variant_args)?;
-    let impl_from_anyhow_error_block = impl_from_anyhow_error(&item.ident);
+    let impl_from_http_error_block = impl_from_http_error(&item.ident, &enum_args, &variant_args)?;
+    let impl_from_anyhow_error_block =
+        impl_from_anyhow_error(&item.ident, &enum_args, &variant_args);
     let impl_from_source_block = impl_from_source(&item.ident, &variant_args)?;
+    let impl_into_response_block = impl_into_response(&item.ident, &enum_args);
+    let impl_self_data_block = impl_self_data(&item.ident, &variant_args);
+    let impl_catalog_block = impl_catalog(&item.ident, &enum_args, &variant_args)?;
 
     let output = quote! {
         #impl_display_block
+        #impl_self_data_block
         #impl_from_http_error_block
         #impl_from_anyhow_error_block
         #impl_from_source_block
+        #impl_into_response_block
+        #impl_catalog_block
     };
 
     Ok(output)
@@ -82,15 +95,21 @@ fn impl_display(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Result<Tok
                     (Arg::Explicit { status_code, .. }, _) => {
                         quote_spanned! {span=>::core::write!(f, "http error {}: {}", #status_code, #ident)}
                     },
-                    (Arg::Transparent, Some(VariantAttribute::From { ident: sident, .. } | VariantAttribute::Source { ident: sident, .. })) => {
+                    (Arg::Transparent(_), Some(VariantAttribute::From { ident: sident, .. } | VariantAttribute::Source { ident: sident, .. })) => {
                         quote_spanned! {span=>#sident.fmt(f)}
                     },
-                    (Arg::Transparent, None) => {
+                    (Arg::Transparent(_), None) => {
                         return Err(spanned_err!(
                             variant,
                             "`transparent` requires either `#[from]` or `#[source]`"
                         ));
                     }
+                    (Arg::Skip, Some(VariantAttribute::From { ident: sident, .. } | VariantAttribute::Source { ident: sident, .. })) => {
+                        quote_spanned! {span=>::core::write!(f, "{}: {}", #ident, #sident)}
+                    },
+                    (Arg::Skip, None) => {
+                        quote_spanned! {span=>::core::write!(f, "{}", #ident)}
+                    },
                 };
                 Ok(quote_spanned! {span=>#lhs => #rhs,})
             },
@@ -137,11 +156,34 @@ fn quote_match_variant_lhs(ty: &Ident, variant: &Variant) -> TokenStream {
     }
 }
 
-fn impl_http_error_builder_arg(arg: &Arg) -> TokenStream {
+fn quote_variant_field_refs(variant: &Variant) -> Vec<TokenStream> {
+    match &variant.fields {
+        Fields::Named(f) => f
+            .named
+            .iter()
+            .filter_map(|f| {
+                let ident = format_field_ident!(f.ident.as_ref()?);
+                Some(quote! {&#ident})
+            })
+            .collect(),
+        Fields::Unnamed(f) => (0..f.unnamed.len())
+            .map(|i| {
+                let ident = format_field_ident!(i);
+                quote! {&#ident}
+            })
+            .collect(),
+        Fields::Unit => vec![],
+    }
+}
+
+fn impl_http_error_builder_arg(ty: &Ident, variant: &Variant, arg: &Arg) -> TokenStream {
     let Arg::Explicit {
         status_code,
         reason,
+        public_message,
         data,
+        headers,
+        ..
     } = &arg
     else {
         panic!(
@@ -149,23 +191,56 @@ fn impl_http_error_builder_arg(arg: &Arg) -> TokenStream {
         );
     };
 
-    let with_reason = reason
-        .as_ref()
-        .map(|r| quote! { .with_reason(::std::format!(#r)) });
-    let with_data: Option<TokenStream> = data.as_ref().map(|d| {
+    let with_reason = reason.as_ref().map(|r| match r {
+        ReasonArg::Format(r) => {
+            let r = quote_format(r);
+            quote! { .with_reason(#r) }
+        }
+        ReasonArg::Fn(path) => {
+            let fields = quote_variant_field_refs(variant);
+            quote! { .with_reason(#path(#(#fields),*)) }
+        }
+    });
+    let with_public_message = public_message.as_ref().map(|m| {
+        let m = quote_format(m);
+        quote! { .with_public_message(#m) }
+    });
+    // data that references `self` is added together with all other entries, see
+    // `quote_with_self_data`
+    let with_data: Option<TokenStream> = data.as_ref().filter(|_| !arg.has_self_data()).map(|d| {
         d.iter()
             .map(|(k, v)| quote! { .with_key_value(#k, #v) })
             .collect()
     });
+    let with_headers: Option<TokenStream> = headers.as_ref().map(|h| {
+        h.iter()
+            .map(|(k, v)| quote! { .with_header(#k, #v) })
+            .collect()
+    });
+
+    // literals are validated while parsing, while expressions and fields are only known at
+    // runtime and fall back to `500` if invalid
+    let status_code = quote! {
+        ::anyhow_http::http::StatusCode::try_from(#status_code)
+            .unwrap_or(::anyhow_http::http::StatusCode::INTERNAL_SERVER_ERROR)
+    };
+    let origin = format!("{ty}::{}", variant.ident);
 
     quote! {
-        .with_status_code(#status_code.try_into().unwrap())
+        .with_status_code(#status_code)
+        .with_origin(#origin)
         #with_reason
+        #with_public_message
         #with_data
+        #with_headers
     }
 }
 
-fn impl_from_http_error(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Result<TokenStream> {
+fn impl_from_http_error(
+    ty: &Ident,
+    enum_args: &EnumArg,
+    variant_args: &[(&Variant, Arg)],
+) -> syn::Result<TokenStream> {
     let variants = variant_args
         .iter()
         .map(|(variant, arg)| {
@@ -180,22 +255,38 @@ fn impl_from_http_error(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Re
                         | VariantAttribute::Source { ident: sident, .. },
                     ),
                 ) => {
-                    let builder_args = impl_http_error_builder_arg(args);
-                    quote_spanned! {span=>
-                        ::anyhow_http::HttpError::default()
-                            #builder_args
-                            .with_source_err(#sident)
-                    }
+                    let builder_args = impl_http_error_builder_arg(ty, variant, args);
+                    quote_log_http_error(
+                        ty,
+                        variant,
+                        args,
+                        quote_with_self_data(
+                            args,
+                            quote_spanned! {span=>
+                                ::anyhow_http::HttpError::default()
+                                    #builder_args
+                                    .with_source_err(#sident)
+                            },
+                        ),
+                    )
                 }
                 (args @ Arg::Explicit { .. }, None) => {
-                    let builder_args = impl_http_error_builder_arg(args);
-                    quote_spanned! {span=>
-                        ::anyhow_http::HttpError::default()
-                            #builder_args
-                    }
+                    let builder_args = impl_http_error_builder_arg(ty, variant, args);
+                    quote_log_http_error(
+                        ty,
+                        variant,
+                        args,
+                        quote_with_self_data(
+                            args,
+                            quote_spanned! {span=>
+                                ::anyhow_http::HttpError::default()
+                                    #builder_args
+                            },
+                        ),
+                    )
                 }
                 (
-                    Arg::Transparent,
+                    Arg::Transparent(TransparentMode::Anyhow),
                     Some(
                         VariantAttribute::From { ident: sident, .. }
                         | VariantAttribute::Source { ident: sident, .. },
@@ -205,21 +296,71 @@ fn impl_from_http_error(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Re
                         ::anyhow_http::HttpError::from_err(#sident)
                     }
                 }
-                (Arg::Transparent, None) => {
+                (
+                    Arg::Transparent(TransparentMode::HttpError),
+                    Some(
+                        VariantAttribute::From { ident: sident, .. }
+                        | VariantAttribute::Source { ident: sident, .. },
+                    ),
+                ) => {
+                    quote_spanned! {span=>{
+                        let http_error: ::anyhow_http::HttpError = #sident;
+                        http_error
+                    }}
+                }
+                (Arg::Transparent(_), None) => {
                     return Err(spanned_err!(
                         variant,
                         "`transparent` requires either `#[from]` or `#[source]`"
                     ));
                 }
+                (Arg::Skip, _) => {
+                    let message = format!(
+                        "`{ty}::{}` is marked `#[http_error(skip)]` and can not be converted into `HttpError`",
+                        variant.ident
+                    );
+                    quote_spanned! {span=>::core::unreachable!(#message)}
+                }
             };
             Ok(quote_spanned! {span=>#lhs => #rhs,})
         })
         .collect::<syn::Result<Vec<_>>>()?;
 
+    // data computed from `self` borrows the value, so it is evaluated before `e` is destructured
+    let self_data = variant_args
+        .iter()
+        .any(|(_, arg)| arg.has_self_data())
+        .then(|| quote! { let __self_data = e.__anyhow_http_self_data(); });
+
+    if enum_args.try_from {
+        // skipped variants are returned before `e` is destructured
+        let return_skipped = quote_skipped_variants(ty, variant_args).map(|skipped| {
+            quote! {
+                if ::core::matches!(e, #skipped) {
+                    return ::core::result::Result::Err(e);
+                }
+            }
+        });
+        return Ok(quote! {
+            impl ::std::convert::TryFrom<#ty> for ::anyhow_http::HttpError {
+                type Error = #ty;
+
+                fn try_from(e: #ty) -> ::core::result::Result<Self, #ty> {
+                    #return_skipped
+                    #self_data
+                    ::core::result::Result::Ok(match e {
+                        #(#variants)*
+                    })
+                }
+            }
+        });
+    }
+
     Ok(quote! {
-        #[allow(fallible_impl_from, clippy::useless_format)]
+        #[allow(fallible_impl_from)]
         impl ::std::convert::From<#ty> for ::anyhow_http::HttpError {
             fn from(e: #ty) -> Self {
+                #self_data
                 match e {
                     #(#variants)*
                 }
@@ -228,16 +369,244 @@ fn impl_from_http_error(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Re
     })
 }
 
-fn impl_from_anyhow_error(ty: &Ident) -> TokenStream {
+/// Generates a hidden method that evaluates the `data(..)` entries that reference `self`, e.g.
+/// `data(code = self.code())`, in the order they are declared. Entries of other variants are
+/// empty.
+fn impl_self_data(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> Option<TokenStream> {
+    if !variant_args.iter().any(|(_, arg)| arg.has_self_data()) {
+        return None;
+    }
+    let arms = variant_args.iter().filter_map(|(variant, arg)| {
+        let Arg::Explicit {
+            data: Some(data), ..
+        } = arg
+        else {
+            return None;
+        };
+        let entries: Vec<_> = data
+            .iter()
+            .filter_map(|(_, v)| match v {
+                DataArg::SelfExpr(expr) => Some(quote! {
+                    data.push(::anyhow_http::macros::to_json_value(&(#expr)));
+                }),
+                _ => None,
+            })
+            .collect();
+        if entries.is_empty() {
+            return None;
+        }
+        let ident = &variant.ident;
+        Some(quote! {
+            #ty::#ident { .. } => {
+                #(#entries)*
+            }
+        })
+    });
+
+    Some(quote! {
+        impl #ty {
+            #[doc(hidden)]
+            fn __anyhow_http_self_data(
+                &self,
+            ) -> ::std::vec::Vec<::core::option::Option<::anyhow_http::macros::JsonValue>> {
+                let mut data = ::std::vec::Vec::new();
+                #[allow(unreachable_patterns)]
+                match self {
+                    #(#arms)*
+                    _ => {}
+                }
+                data
+            }
+        }
+    })
+}
+
+/// Adds the `data(..)` entries of variants with entries that reference `self` in the order they
+/// are declared, taking the values computed by `__anyhow_http_self_data`.
+fn quote_with_self_data(arg: &Arg, http_error: TokenStream) -> TokenStream {
+    let Arg::Explicit {
+        data: Some(data), ..
+    } = arg
+    else {
+        return http_error;
+    };
+    if !arg.has_self_data() {
+        return http_error;
+    }
+    let entries = data.iter().map(|(k, v)| match v {
+        DataArg::SelfExpr(_) => quote! {
+            (#k, __self_data.next().flatten())
+        },
+        v => quote! {
+            (#k, ::anyhow_http::macros::to_json_value(&(#v)))
+        },
+    });
+    quote! {{
+        let mut __self_data = __self_data.into_iter();
+        ::anyhow_http::macros::with_self_data(#http_error, [#(#entries),*])
+    }}
+}
+
+/// Wraps the conversion of a variant with `#[http_error(log(..))]` so that the resulting
+/// `HttpError` is logged.
+fn quote_log_http_error(
+    ty: &Ident,
+    variant: &Variant,
+    arg: &Arg,
+    http_error: TokenStream,
+) -> TokenStream {
+    let Arg::Explicit {
+        log_level: Some(level),
+        ..
+    } = arg
+    else {
+        return http_error;
+    };
+    let name = format!("{ty}::{}", variant.ident);
+    quote! {{
+        let http_error = #http_error;
+        ::anyhow_http::macros::log_http_error(
+            #level,
+            #name,
+            &http_error,
+        );
+        http_error
+    }}
+}
+
+/// Returns the pattern that matches all variants marked `#[http_error(skip)]`, if any.
+fn quote_skipped_variants(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> Option<TokenStream> {
+    let skipped: Vec<_> = variant_args
+        .iter()
+        .filter(|(_, arg)| matches!(arg, Arg::Skip))
+        .map(|(variant, _)| {
+            let ident = &variant.ident;
+            quote! { #ty::#ident { .. } }
+        })
+        .collect();
+    (!skipped.is_empty()).then(|| quote! { #(#skipped)|* })
+}
+
+/// Skipped variants are wrapped as is, so they can be downcast from the `anyhow::Error` again
+/// instead of reaching the conversion into `HttpError`.
+fn impl_from_anyhow_error(
+    ty: &Ident,
+    enum_args: &EnumArg,
+    variant_args: &[(&Variant, Arg)],
+) -> TokenStream {
+    if enum_args.try_from {
+        return quote! {
+            impl ::std::convert::From<#ty> for ::anyhow::Error {
+                fn from(e: #ty) -> Self {
+                    match ::anyhow_http::HttpError::try_from(e) {
+                        ::core::result::Result::Ok(http_error) => http_error.into(),
+                        ::core::result::Result::Err(e) => ::anyhow::Error::msg(e),
+                    }
+                }
+            }
+        };
+    }
+    let return_skipped = quote_skipped_variants(ty, variant_args).map(|skipped| {
+        quote! {
+            if ::core::matches!(e, #skipped) {
+                return ::anyhow::Error::msg(e);
+            }
+        }
+    });
     quote! {
         impl ::std::convert::From<#ty> for ::anyhow::Error {
             fn from(e: #ty) -> Self {
+                #return_skipped
                 ::anyhow_http::HttpError::from(e).into()
             }
         }
     }
 }
 
+fn impl_into_response(ty: &Ident, enum_args: &EnumArg) -> Option<TokenStream> {
+    let formatter = enum_args.response.as_ref()?;
+    Some(quote! {
+        ::anyhow_http::__impl_into_response!(#ty, #formatter);
+    })
+}
+
+/// Implements `catalog()` for `#[http_error(catalog)]`, which lists an `ErrorDescriptor` for
+/// every explicit variant. Transparent and skipped variants don't declare an error of their own.
+fn impl_catalog(
+    ty: &Ident,
+    enum_args: &EnumArg,
+    variant_args: &[(&Variant, Arg)],
+) -> syn::Result<Option<TokenStream>> {
+    if !enum_args.catalog {
+        return Ok(None);
+    }
+    let mut descriptors = Vec::new();
+    for (variant, arg) in variant_args {
+        let Arg::Explicit {
+            status_code,
+            reason,
+            data,
+            ..
+        } = arg
+        else {
+            continue;
+        };
+        if Arg::parse_status_field(variant)?.is_some() {
+            return Err(spanned_err!(
+                variant,
+                "`catalog` requires a `status(..)`, not a `#[status]` field"
+            ));
+        }
+
+        let name = format!("{ty}::{}", variant.ident);
+        let reason = match reason {
+            Some(ReasonArg::Format(r)) => {
+                let r = unparse_format_string(r);
+                quote! { ::core::option::Option::Some(#r) }
+            }
+            Some(ReasonArg::Fn(_)) | None => quote! { ::core::option::Option::None },
+        };
+        let data = data.as_deref().unwrap_or_default();
+        let code = match data.iter().find(|(k, _)| k == "code").map(|(_, v)| v) {
+            Some(DataArg::Lit(Lit::Int(code))) => {
+                quote! { ::core::option::Option::Some(::anyhow_http::ErrorCode::Numeric(#code)) }
+            }
+            Some(DataArg::Lit(Lit::Str(code))) => {
+                quote! { ::core::option::Option::Some(::anyhow_http::ErrorCode::Symbolic(#code)) }
+            }
+            Some(DataArg::Format(code)) => {
+                let code = unparse_format_string(code);
+                quote! { ::core::option::Option::Some(::anyhow_http::ErrorCode::Symbolic(#code)) }
+            }
+            _ => quote! { ::core::option::Option::None },
+        };
+        let data_keys = data.iter().map(|(k, _)| k);
+
+        descriptors.push(quote! {
+            ::anyhow_http::ErrorDescriptor {
+                name: #name,
+                status_code: ::anyhow_http::http::StatusCode::try_from(#status_code)
+                    .unwrap_or(::anyhow_http::http::StatusCode::INTERNAL_SERVER_ERROR),
+                reason: #reason,
+                code: #code,
+                data_keys: &[#(#data_keys),*],
+            }
+        });
+    }
+
+    // status codes can't be created in a const context, so the catalog is built once
+    Ok(Some(quote! {
+        impl #ty {
+            /// Returns the errors declared by the variants of the enum.
+            pub fn catalog() -> &'static [::anyhow_http::ErrorDescriptor] {
+                static CATALOG: ::std::sync::OnceLock<::std::vec::Vec<::anyhow_http::ErrorDescriptor>> =
+                    ::std::sync::OnceLock::new();
+                CATALOG.get_or_init(|| ::std::vec![#(#descriptors),*])
+            }
+        }
+    }))
+}
+
 fn impl_from_source(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Result<TokenStream> {
     let mut from_impls = quote! {};
     for (variant, _) in variant_args {
@@ -248,11 +617,33 @@ fn impl_from_source(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Result
         };
         let sty = field.ty;
         let ident = &variant.ident;
+        let init = |f: &Field| {
+            if f.attrs.iter().any(|a| a.path().is_ident("from")) {
+                quote! { s }
+            } else {
+                quote! { ::core::default::Default::default() }
+            }
+        };
+        let construct = match &variant.fields {
+            Fields::Named(f) => {
+                let inits = f.named.iter().map(|f| {
+                    let name = &f.ident;
+                    let init = init(f);
+                    quote! { #name: #init }
+                });
+                quote! { Self::#ident { #(#inits,)* } }
+            }
+            Fields::Unnamed(f) => {
+                let inits = f.unnamed.iter().map(init);
+                quote! { Self::#ident(#(#inits,)*) }
+            }
+            Fields::Unit => unreachable!(),
+        };
 
         let from_source = quote! {
             impl ::std::convert::From<#sty> for #ty {
                 fn from(s: #sty) -> Self {
-                    Self::#ident(s)
+                    #construct
                 }
             }
         };
@@ -266,89 +657,360 @@ fn impl_from_source(ty: &Ident, variant_args: &[(&Variant, Arg)]) -> syn::Result
     Ok(from_impls)
 }
 
+#[derive(Debug, Default)]
+struct EnumArg {
+    response: Option<Path>,
+    /// Whether `TryFrom` is implemented instead of `From`, failing for skipped variants.
+    try_from: bool,
+    /// The prefix of the `code` data derived from the variant names.
+    code_prefix: Option<String>,
+    /// Whether an associated `catalog()` of the declared errors is generated.
+    catalog: bool,
+}
+
+impl EnumArg {
+    fn parse_from_enum(item: &ItemEnum) -> syn::Result<Self> {
+        let mut args = Self::default();
+        for attr in item
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("http_error"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("response") {
+                    let content;
+                    parenthesized!(content in meta.input);
+                    args.response = Some(content.parse()?);
+                    return Ok(());
+                }
+
+                if meta.path.is_ident("try_from") {
+                    args.try_from = true;
+                    return Ok(());
+                }
+
+                if meta.path.is_ident("catalog") {
+                    args.catalog = true;
+                    return Ok(());
+                }
+
+                if meta.path.is_ident("code_prefix") {
+                    if args.code_prefix.is_some() {
+                        return Err(meta.error("duplicate `code_prefix`"));
+                    }
+                    let prefix: LitStr = meta.value()?.parse()?;
+                    args.code_prefix = Some(prefix.value());
+                    return Ok(());
+                }
+
+                Err(meta.error("unrecognized argument to enum-level `#[http_error(..)]`"))
+            })?;
+        }
+        Ok(args)
+    }
+}
+
 #[derive(Debug)]
 enum Arg {
     Explicit {
-        status_code: LitInt,
-        reason: Option<String>,
-        data: Option<HashMap<String, DataArg>>,
+        status_code: Box<Expr>,
+        reason: Option<ReasonArg>,
+        public_message: Option<String>,
+        data: Option<Vec<(String, DataArg)>>,
+        headers: Option<Vec<(String, String)>>,
+        log_level: Option<String>,
     },
-    Transparent,
+    Transparent(TransparentMode),
+    /// The variant is not convertible into `HttpError`.
+    Skip,
+}
+
+/// Controls how `#[http_error(transparent)]` forwards the source error.
+#[derive(Debug)]
+enum TransparentMode {
+    /// Forwards any error through `HttpError::from_err`. Errors that are not a `HttpError`
+    /// become a bare `500`. This is the default.
+    Anyhow,
+    /// Forwards the source as-is and requires it to be of type `HttpError`.
+    HttpError,
 }
 
 impl Arg {
+    /// Whether any `data(..)` entry is computed from `self`.
+    fn has_self_data(&self) -> bool {
+        matches!(
+            self,
+            Arg::Explicit { data: Some(data), .. }
+                if data.iter().any(|(_, v)| matches!(v, DataArg::SelfExpr(_)))
+        )
+    }
+
+    /// Adds the `code` data of the enum-level `code_prefix`, i.e. the prefix followed by the
+    /// variant name in SCREAMING_SNAKE_CASE, unless the variant declares its own `code`.
+    fn insert_code(&mut self, prefix: &str, variant: &Variant) {
+        let Arg::Explicit { data, .. } = self else {
+            return;
+        };
+        let data = data.get_or_insert_with(Vec::new);
+        if data.iter().any(|(k, _)| k == "code") {
+            return;
+        }
+        let code = format!(
+            "{prefix}{}",
+            screaming_snake_case(&variant.ident.to_string())
+        );
+        data.push((
+            "code".to_owned(),
+            DataArg::Lit(Lit::Str(LitStr::new(&code, variant.ident.span()))),
+        ));
+    }
+
     fn parse_from_variant(variant: &Variant) -> syn::Result<Self> {
         let mut status_code = None;
         let mut reason = None;
+        let mut public_message = None;
         let mut data = None;
-        let mut transparent = false;
-        let attr = variant
+        let mut headers = None;
+        let mut log_level = None;
+        let mut transparent = None;
+        let mut skip = false;
+        let attrs: Vec<_> = variant
             .attrs
             .iter()
-            .find(|attr| attr.path().is_ident("http_error"))
-            .ok_or_else(|| spanned_err!(variant, "missing `http_error` attribute"))?;
+            .filter(|attr| attr.path().is_ident("http_error"))
+            .collect();
+        if attrs.is_empty() && Self::parse_status_field(variant)?.is_none() {
+            return Err(spanned_err!(variant, "missing `http_error` attribute"));
+        }
 
-        attr.parse_nested_meta(|meta| {
+        let mut parse_meta = |meta: ParseNestedMeta| {
             if meta.path.is_ident("status") {
+                if status_code.is_some() {
+                    return Err(meta.error("duplicate `status`"));
+                }
                 let content;
                 parenthesized!(content in meta.input);
-                status_code = Some(Self::parse_and_validate_status_code(variant, &content)?);
+                status_code = Some(Self::parse_and_validate_status_code(&content)?);
                 return Ok(());
             }
 
             if meta.path.is_ident("reason") {
+                match reason {
+                    Some(ReasonArg::Format(_)) => return Err(meta.error("duplicate `reason`")),
+                    Some(ReasonArg::Fn(_)) => {
+                        return Err(meta.error("`reason` may not be combined with `reason_fn`"))
+                    }
+                    None => {}
+                }
+                let content;
+                parenthesized!(content in meta.input);
+                reason = Some(ReasonArg::Format(Self::parse_reason(&content)?));
+                return Ok(());
+            }
+
+            if meta.path.is_ident("reason_fn") {
+                match reason {
+                    Some(ReasonArg::Fn(_)) => return Err(meta.error("duplicate `reason_fn`")),
+                    Some(ReasonArg::Format(_)) => {
+                        return Err(meta.error("`reason_fn` may not be combined with `reason`"))
+                    }
+                    None => {}
+                }
                 let content;
                 parenthesized!(content in meta.input);
-                reason = Some(Self::parse_reason(&content)?);
+                reason = Some(ReasonArg::Fn(content.parse()?));
+                return Ok(());
+            }
+
+            if meta.path.is_ident("public_message") {
+                if public_message.is_some() {
+                    return Err(meta.error("duplicate `public_message`"));
+                }
+                let content;
+                parenthesized!(content in meta.input);
+                public_message = Some(Self::parse_reason(&content)?);
                 return Ok(());
             }
 
             if meta.path.is_ident("data") {
                 let content;
                 parenthesized!(content in meta.input);
-                data = Some(Self::parse_data(&content)?);
+                Self::parse_data(&content, data.get_or_insert_with(Vec::new))?;
+                return Ok(());
+            }
+
+            if meta.path.is_ident("headers") {
+                let content;
+                parenthesized!(content in meta.input);
+                headers
+                    .get_or_insert_with(Vec::new)
+                    .extend(Self::parse_headers(&content)?);
+                return Ok(());
+            }
+
+            if meta.path.is_ident("log") {
+                if log_level.is_some() {
+                    return Err(meta.error("duplicate `log`"));
+                }
+                meta.parse_nested_meta(|meta| {
+                    if !meta.path.is_ident("level") {
+                        return Err(meta.error("expected `log(level = \"..\")`"));
+                    }
+                    let level: LitStr = meta.value()?.parse()?;
+                    if !LOG_LEVELS.contains(&level.value().as_str()) {
+                        return Err(syn::Error::new_spanned(
+                            level,
+                            "`#[derive(HttpError)]`: expected one of `trace`, `debug`, `info`, `warn` or `error`",
+                        ));
+                    }
+                    log_level = Some(level.value());
+                    Ok(())
+                })?;
+                if log_level.is_none() {
+                    return Err(meta.error("expected `log(level = \"..\")`"));
+                }
                 return Ok(());
             }
 
             if meta.path.is_ident("transparent") {
-                transparent = true;
+                if transparent.is_some() {
+                    return Err(meta.error("duplicate `transparent`"));
+                }
+                if !meta.input.peek(syn::token::Paren) {
+                    transparent = Some(TransparentMode::Anyhow);
+                    return Ok(());
+                }
+                let content;
+                parenthesized!(content in meta.input);
+                let mode: Ident = content.parse()?;
+                transparent = match mode.to_string().as_str() {
+                    "anyhow" => Some(TransparentMode::Anyhow),
+                    "http_error" => Some(TransparentMode::HttpError),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            mode,
+                            "`#[derive(HttpError)]`: expected `transparent(anyhow)` or `transparent(http_error)`",
+                        ))
+                    }
+                };
+                return Ok(());
+            }
+
+            if meta.path.is_ident("skip") {
+                if skip {
+                    return Err(meta.error("duplicate `skip`"));
+                }
+                skip = true;
                 return Ok(());
             }
 
             Err(meta.error("unrecognized argument to `#[http_error(..)]`"))
-        })?;
+        };
+        // Arguments may be split across several `#[http_error(..)]` attributes and are merged.
+        for attr in attrs {
+            attr.parse_nested_meta(&mut parse_meta)?;
+        }
 
-        if transparent {
-            if status_code.is_some() || reason.is_some() || data.is_some() {
+        if skip {
+            if status_code.is_some()
+                || reason.is_some()
+                || public_message.is_some()
+                || data.is_some()
+                || headers.is_some()
+                || log_level.is_some()
+                || transparent.is_some()
+            {
                 return Err(spanned_err!(
                     variant,
-                    "`#[http_error(transparent)]` may not use `status`, `reason` or `data`"
+                    "`#[http_error(skip)]` may not be combined with other arguments"
                 ));
             }
 
-            return Ok(Self::Transparent);
+            return Ok(Self::Skip);
         }
 
-        let Some(status_code) = status_code else {
-            return Err(spanned_err!(
-                variant,
-                "missing `#[http_error(status(..))]` attribute"
-            ));
+        if let Some(mode) = transparent {
+            if status_code.is_some()
+                || reason.is_some()
+                || public_message.is_some()
+                || data.is_some()
+                || headers.is_some()
+                || log_level.is_some()
+            {
+                return Err(spanned_err!(
+                    variant,
+                    "`#[http_error(transparent)]` may not use `status`, `reason`, `reason_fn`, `public_message`, `data`, `headers` or `log`"
+                ));
+            }
+
+            return Ok(Self::Transparent(mode));
+        }
+
+        let status_field = Self::parse_status_field(variant)?;
+        let status_code = match (status_code, status_field) {
+            (Some(_), Some(_)) => {
+                return Err(spanned_err!(
+                    variant,
+                    "`status(..)` may not be combined with a `#[status]` field"
+                ))
+            }
+            (Some(status_code), None) | (None, Some(status_code)) => status_code,
+            (None, None) => {
+                return Err(spanned_err!(
+                    variant,
+                    "missing `#[http_error(status(..))]` attribute or `#[status]` field"
+                ))
+            }
         };
 
         Ok(Self::Explicit {
-            status_code,
+            status_code: Box::new(status_code),
             reason,
+            public_message,
             data,
+            headers,
+            log_level,
         })
     }
 
-    fn parse_and_validate_status_code(variant: &Variant, buf: &ParseBuffer) -> syn::Result<LitInt> {
+    /// Returns the binding of the field annotated with `#[status]` if any.
+    fn parse_status_field(variant: &Variant) -> syn::Result<Option<Expr>> {
+        let mut fields = variant
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.attrs.iter().any(|a| a.path().is_ident("status")));
+        let Some((pos, field)) = fields.next() else {
+            return Ok(None);
+        };
+        if let Some((_, field)) = fields.next() {
+            return Err(spanned_err!(
+                field,
+                "only one field may be marked `#[status]`"
+            ));
+        }
+        let ident = match &field.ident {
+            Some(ident) => format_field_ident!(ident),
+            None => format_field_ident!(pos),
+        };
+        Ok(Some(syn::parse_quote!(#ident)))
+    }
+
+    fn parse_and_validate_status_code(buf: &ParseBuffer) -> syn::Result<Expr> {
+        if !buf.peek(LitInt) {
+            return buf.parse();
+        }
         let lit: LitInt = buf.parse()?;
-        let status_code: u16 = lit.base10_parse()?;
-        StatusCode::try_from(status_code)
-            .map_err(|_| spanned_err!(variant, "invalid status code"))?;
-        Ok(lit)
+        lit.base10_parse::<u16>()
+            .ok()
+            .and_then(|status_code| StatusCode::try_from(status_code).ok())
+            .ok_or_else(|| {
+                syn::Error::new(lit.span(), "`#[derive(HttpError)]`: invalid status code")
+            })?;
+        Ok(Expr::Lit(ExprLit {
+            attrs: vec![],
+            lit: Lit::Int(lit),
+        }))
     }
 
     fn parse_reason(buf: &ParseBuffer) -> syn::Result<String> {
@@ -356,22 +1018,79 @@ impl Arg {
         Ok(parse_format_string(&reason))
     }
 
-    fn parse_data(buf: &ParseBuffer) -> syn::Result<HashMap<String, DataArg>> {
-        let mut data: HashMap<String, DataArg> = Default::default();
+    /// Parses `key = value` pairs into `data`, which may hold the entries of previous `data(..)`
+    /// arguments. Keys must be unique across all of them.
+    fn parse_data(buf: &ParseBuffer, data: &mut Vec<(String, DataArg)>) -> syn::Result<()> {
         let args: Punctuated<ExprAssign, Token![,]> = Punctuated::parse_terminated(buf)?;
 
         for arg in &args {
-            match (arg.left.as_ref(), arg.right.as_ref()) {
-                (Expr::Path(p), Expr::Lit(l)) => {
-                    let ident = p.path.get_ident().unwrap();
-                    data.insert(ident.to_string(), DataArg::parse_from_lit(&l.lit));
+            let (Expr::Path(p), value) = (arg.left.as_ref(), arg.right.as_ref()) else {
+                return Err(spanned_err!(arg, "invalid data argument"));
+            };
+            let Some(ident) = p.path.get_ident() else {
+                return Err(spanned_err!(p, "invalid data key"));
+            };
+            let value = match value {
+                Expr::Lit(l) => DataArg::parse_from_lit(&l.lit),
+                expr if references_self(expr.to_token_stream()) => {
+                    DataArg::SelfExpr(Box::new(expr.clone()))
                 }
-                _ => Err(spanned_err!(arg, "invalid data argument"))?,
+                _ => return Err(spanned_err!(arg, "invalid data argument")),
+            };
+            let key = ident.to_string();
+            if data.iter().any(|(k, _)| *k == key) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    format!("`#[derive(HttpError)]`: duplicate data key `{key}`"),
+                ));
             }
+            data.push((key, value));
         }
 
-        Ok(data)
+        Ok(())
     }
+
+    /// Parses `"name" = "value"` pairs. Names and values are validated at expansion time, so
+    /// invalid headers are reported at the offending literal.
+    fn parse_headers(buf: &ParseBuffer) -> syn::Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        while !buf.is_empty() {
+            let name: LitStr = buf.parse()?;
+            buf.parse::<Token![=]>()?;
+            let value: LitStr = buf.parse()?;
+            if http::HeaderName::from_bytes(name.value().as_bytes()).is_err() {
+                return Err(spanned_err!(name, "invalid header name"));
+            }
+            if http::HeaderValue::from_str(&value.value()).is_err() {
+                return Err(spanned_err!(value, "invalid header value"));
+            }
+            headers.push((name.value(), value.value()));
+            if buf.is_empty() {
+                break;
+            }
+            buf.parse::<Token![,]>()?;
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Converts an identifier in CamelCase to SCREAMING_SNAKE_CASE, e.g. `HTTPTimeout` to
+/// `HTTP_TIMEOUT`.
+fn screaming_snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut snake = String::with_capacity(ident.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 && chars[i - 1] != '_' {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if !prev.is_uppercase() || next_lower {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_uppercase());
+    }
+    snake
 }
 
 fn parse_format_string(lit: &LitStr) -> String {
@@ -385,10 +1104,42 @@ fn parse_format_string(lit: &LitStr) -> String {
     format
 }
 
+/// Restores the format string as declared from one parsed by [`parse_format_string`].
+fn unparse_format_string(format: &str) -> String {
+    format.replace(&format!("{{{FORMAT_FIELD_PREFIX}"), "{")
+}
+
+/// Quotes a format string parsed by [`parse_format_string`]. Strings without placeholders are
+/// emitted as `&'static str` so that they are not allocated.
+fn quote_format(format: &str) -> TokenStream {
+    if format.contains(['{', '}']) {
+        quote! { ::std::format!(#format) }
+    } else {
+        quote! { #format }
+    }
+}
+
+#[derive(Debug)]
+enum ReasonArg {
+    Format(String),
+    Fn(Path),
+}
+
+/// Whether the tokens contain `self`, e.g. `self.code()`.
+fn references_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "self",
+        proc_macro2::TokenTree::Group(group) => references_self(group.stream()),
+        _ => false,
+    })
+}
+
 #[derive(Debug)]
 enum DataArg {
     Format(String),
     Lit(Lit),
+    /// An expression that is evaluated on a reference to the enum value.
+    SelfExpr(Box<Expr>),
 }
 
 impl DataArg {
@@ -406,15 +1157,16 @@ impl DataArg {
 impl ToTokens for DataArg {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         match self {
-            DataArg::Format(f) => quote! {::std::format!(#f)}.to_tokens(tokens),
+            DataArg::Format(f) => quote_format(f).to_tokens(tokens),
             DataArg::Lit(l) => l.to_tokens(tokens),
+            DataArg::SelfExpr(e) => e.to_tokens(tokens),
         }
     }
 }
 
 #[derive(Debug)]
 enum VariantAttribute {
-    From { ident: Ident, field: Field },
+    From { ident: Ident, field: Box<Field> },
     Source { ident: Ident },
 }
 
@@ -451,17 +1203,18 @@ impl VariantAttribute {
         }
     }
 
+    /// Fields of the variant other than the `#[from]` field are initialized with
+    /// [`Default::default`] in the generated [`From`] implementation.
     fn parse_from_attr(variant: &Variant, field: Field) -> syn::Result<Option<Self>> {
-        match &variant.fields {
-            Fields::Unnamed(f) if f.unnamed.len() == 1 => Ok(Some(Self::From {
-                ident: format_field_ident!("0"),
-                field,
-            })),
-            _ => Err(spanned_err!(
-                variant,
-                "`#[from]` is only supported on single unnamed fields"
-            )),
-        }
+        let ident = match &variant.fields {
+            Fields::Named(_) => format_field_ident!(field.ident.as_ref().unwrap()),
+            Fields::Unnamed(_) => field.ident.clone().unwrap(),
+            Fields::Unit => unreachable!(),
+        };
+        Ok(Some(Self::From {
+            ident,
+            field: Box::new(field),
+        }))
     }
 
     fn parse_source_attr(variant: &Variant, field: Field) -> syn::Result<Option<Self>> {
@@ -65,6 +65,21 @@ mod http_error;
 /// }
 /// ```
 ///
+/// `#[from]` may also be set on a named field. All other fields of the variant must implement
+/// [`Default`] and are initialized with their default value by the [`From`] implementation.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(502), reason("request failed after {retries} retries"))]
+///     RequestFailed {
+///         #[from]
+///         source: std::io::Error,
+///         retries: u32,
+///     },
+/// }
+/// ```
+///
 /// Formatting on the `reason(..)` and `data(..)` attribute is supported on both named and unnamed
 /// variants.
 /// ```
@@ -78,6 +93,137 @@ mod http_error;
 /// }
 /// ```
 ///
+/// Arguments can be split across several `#[http_error(..)]` attributes on the same variant,
+/// which are merged. Entries of multiple `data(..)` arguments are combined and added in the order
+/// they are declared.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(409), reason("order {0} already exists"))]
+///     #[http_error(data(code = 1001, docs = "https://example.com/errors/1001"))]
+///     #[http_error(data(order = "{0}"))]
+///     OrderExists(u64),
+/// }
+/// ```
+///
+/// Besides integer literals, `status(..)` accepts any expression that converts into a
+/// `StatusCode`, such as `StatusCode` constants or shared `u16` constants. Invalid literals are
+/// rejected at compile time, while expressions are only validated at runtime and fall back to
+/// `500` if invalid.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// # use http::StatusCode;
+/// const UPSTREAM_STATUS: u16 = 502;
+///
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(StatusCode::BAD_GATEWAY))]
+///     RequestFailed,
+///     #[http_error(status(UPSTREAM_STATUS))]
+///     UpstreamFailed,
+/// }
+/// ```
+///
+/// Reasons that require more logic than a format string can be computed with `reason_fn(..)`.
+/// The function receives references to all fields of the variant in declaration order and
+/// returns anything that converts into `Cow<'static, str>`. `reason_fn` and `reason` are
+/// mutually exclusive.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// fn items_reason(count: &usize) -> String {
+///     match count {
+///         1 => "1 item missing".to_string(),
+///         n => format!("{n} items missing"),
+///     }
+/// }
+///
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(400), reason_fn(items_reason))]
+///     MissingItems(usize),
+/// }
+/// ```
+///
+/// A field marked with `#[status]` provides the status code at runtime instead of `status(..)`,
+/// e.g. to forward the status of an upstream service. The field must be of type `StatusCode` or
+/// `u16`. Invalid status codes fall back to `500`.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// # use http::StatusCode;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(reason("upstream failed: {body}"))]
+///     Upstream {
+///         #[status]
+///         status: StatusCode,
+///         body: String,
+///     },
+///     UpstreamStatus(#[status] u16),
+/// }
+/// ```
+///
+/// `data(..)` values may also be expressions on `self`, e.g. to call helper methods that compute
+/// data from several fields. Such expressions are evaluated on a reference to the enum value
+/// before it is converted, so fields must be accessed through `self` as well. The value must
+/// implement `Serialize` but may borrow from `self`.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(409), data(code = self.code(), order = "{order}"))]
+///     OrderConflict { order: u64, items: Vec<u64> },
+/// }
+///
+/// impl CustomError {
+///     fn code(&self) -> &str {
+///         match self {
+///             Self::OrderConflict { items, .. } if items.is_empty() => "order_conflict",
+///             Self::OrderConflict { .. } => "item_conflict",
+///         }
+///     }
+/// }
+/// ```
+///
+/// `public_message(..)` sets the message that is shown to clients, while `reason(..)` is kept
+/// for logs. It supports the same formatting as `reason(..)`.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(
+///         status(503),
+///         reason("replica {0} is lagging behind"),
+///         public_message("service unavailable, retry in {1}s")
+///     )]
+///     ReplicaLag(String, u64),
+/// }
+/// ```
+///
+/// `headers(..)` adds headers to the error response. Header names and values are literals that
+/// are validated at compile time.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(503), headers("retry-after" = "120", "x-error-code" = "E42"))]
+///     Unavailable,
+/// }
+/// ```
+///
+/// `log(level = "..")` emits a `tracing` event at the given level whenever the variant is
+/// converted into a [`HttpError`]. The event records the variant name, status, reason and data.
+/// Supported levels are `trace`, `debug`, `info`, `warn` and `error`. Events are only emitted if
+/// the `tracing` feature of `anyhow-http` is enabled.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(429), reason("client {0} rate limited"), log(level = "warn"))]
+///     RateLimited(String),
+/// }
+/// ```
+///
 /// `transparent` allows to forward the source error as-is. It required either `#[source]` or
 /// `#[from]`.
 /// ```
@@ -89,10 +235,134 @@ mod http_error;
 /// }
 /// ```
 ///
+/// By default `transparent` converts the source with `HttpError::from_err`, which only
+/// preserves status, reason and data if the source is (or wraps) a [`HttpError`]. Any other error
+/// becomes a bare `500`. `transparent(anyhow)` states this behavior explicitly, while
+/// `transparent(http_error)` requires the source to be of type [`HttpError`] and fails to compile
+/// otherwise.
+/// ```
+/// # use anyhow_http::HttpError;
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// enum CustomError {
+///     #[http_error(transparent(anyhow))]
+///     Any(#[source] anyhow::Error),
+///     #[http_error(transparent(http_error))]
+///     Http(#[source] HttpError),
+/// }
+/// ```
+///
+/// `skip` marks variants that are purely internal and must never reach a client. The generated
+/// conversion into [`HttpError`] panics for skipped variants, so they have to be handled before,
+/// unless `try_from` is set (see below). Converted into `anyhow::Error`, e.g. with `?`, skipped
+/// variants are wrapped as is and can be downcast again, which requires the enum to implement
+/// `Debug`. `skip` may not be combined with other arguments.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(Debug, FromHttpError)]
+/// enum CustomError {
+///     #[http_error(status(503))]
+///     Unavailable,
+///     #[http_error(skip)]
+///     RetryInternally(u32),
+/// }
+/// ```
+///
+/// With the enum-level `#[http_error(try_from)]` attribute, `TryFrom` is implemented instead of
+/// `From`, which returns skipped variants as error. This suits enums where only a subset of the
+/// variants maps to HTTP. Converted into `anyhow::Error`, skipped variants are wrapped as is.
+/// ```
+/// # use anyhow_http::HttpError;
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(Debug, FromHttpError)]
+/// #[http_error(try_from)]
+/// enum CustomError {
+///     #[http_error(status(503))]
+///     Unavailable,
+///     #[http_error(skip)]
+///     RetryInternally(u32),
+/// }
+///
+/// assert!(HttpError::try_from(CustomError::Unavailable).is_ok());
+/// assert!(HttpError::try_from(CustomError::RetryInternally(1)).is_err());
+/// ```
+///
+/// The enum-level `#[http_error(code_prefix = "..")]` attribute gives every variant a stable
+/// machine-readable `code` data, the prefix followed by the variant name in SCREAMING_SNAKE_CASE.
+/// Variants that declare their own `code` data and `transparent` variants keep theirs.
+/// ```
+/// # use anyhow_http::HttpError;
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// #[http_error(code_prefix = "BILLING_")]
+/// enum BillingError {
+///     #[http_error(status(402))]
+///     PaymentRequired,
+/// }
+///
+/// let err = HttpError::from(BillingError::PaymentRequired);
+/// assert_eq!(err.get("code"), Some("BILLING_PAYMENT_REQUIRED".to_string()));
+/// ```
+///
+/// The enum-level `#[http_error(catalog)]` attribute generates an associated `catalog()` that
+/// lists an [`ErrorDescriptor`](anyhow_http::ErrorDescriptor) with status, reason template, code
+/// and data keys for every variant that isn't `transparent` or skipped, e.g. to document all
+/// declared errors or for contract tests. Variants must declare their status with `status(..)`.
+/// ```
+/// # use anyhow_http::ErrorCode;
+/// # use anyhow_http_derive::FromHttpError;
+/// #[derive(FromHttpError)]
+/// #[http_error(catalog)]
+/// enum OrderError {
+///     #[http_error(status(404), reason("order {0} not found"), data(code = 1001))]
+///     NotFound(u64),
+/// }
+///
+/// let [not_found] = OrderError::catalog() else { unreachable!() };
+/// assert_eq!(not_found.name, "OrderError::NotFound");
+/// assert_eq!(not_found.reason, Some("order {0} not found"));
+/// assert_eq!(not_found.code, Some(ErrorCode::Numeric(1001)));
+/// ```
+///
+/// Every converted variant records its name as [`origin`](anyhow_http::HttpError::origin) of the
+/// `HttpError`, e.g. `BillingError::PaymentRequired`, so logs, metrics labels and tests can tell
+/// which declared error produced a response. `transparent` variants keep the origin of the
+/// wrapped error.
+///
+/// The enum-level `#[http_error(response(..))]` attribute implements axum's `IntoResponse` for
+/// the enum with the given formatter, if the `axum` feature of `anyhow-http` is enabled. This
+/// allows the enum to be returned from handlers directly. A conversion into `HttpErrorResponse`
+/// is already provided through `anyhow::Error`.
+/// ```
+/// # use anyhow_http_derive::FromHttpError;
+/// use anyhow_http::response::Json;
+///
+/// #[derive(FromHttpError)]
+/// #[http_error(response(Json))]
+/// enum CustomError {
+///     #[http_error(status(404), reason("not found"))]
+///     NotFound,
+/// }
+///
+/// async fn handler() -> Result<(), CustomError> {
+///     Err(CustomError::NotFound)
+/// }
+/// ```
+///
+/// # Compile errors
+///
+/// Invalid attributes are rejected at compile time, e.g. a variant without `status(..)`, a
+/// status code literal outside of `100..=999`, `transparent` or `skip` combined with other
+/// arguments, or duplicate `data(..)` keys. These misuses are part of the macro's contract; the
+/// diagnostics are covered by the compile-fail tests in `tests/ui`.
+///
+/// Downstream crates can assert the rendering of their derived errors with the
+/// `derive-test-utils` feature of `anyhow-http`, see `anyhow_http::test_utils`.
+///
 /// [`From`]: std::convert::From
 /// [`HttpError`]: https://docs.rs/anyhow-http/latest/anyhow_http/struct.HttpError.html
 /// [`thiserror`]: https://docs.rs/thiserror/latest/thiserror/#derives
-#[proc_macro_derive(FromHttpError, attributes(http_error, from, source, data))]
+#[proc_macro_derive(FromHttpError, attributes(http_error, from, source, data, status))]
 pub fn derive_from_http_error(input: TokenStream) -> TokenStream {
     syn::parse(input)
         .and_then(http_error::expand_http_error)
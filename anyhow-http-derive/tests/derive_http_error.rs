@@ -1,5 +1,24 @@
-use anyhow_http::{http_error, HttpError};
+use anyhow_http::{
+    http_error,
+    response::{HttpJsonResult, Json},
+    test_utils::render,
+    HttpError,
+};
 use anyhow_http_derive::FromHttpError;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tracing_core::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Level, Metadata, Subscriber,
+};
+
+const DEFAULT_STATUS: u16 = 503;
+const INVALID_STATUS: u16 = 1000;
 
 #[derive(Debug, FromHttpError)]
 enum CustomError {
@@ -19,6 +38,86 @@ enum CustomError {
     UnamedWithSource(u64, #[source] anyhow::Error),
     #[http_error(transparent)]
     Transparent(#[source] HttpError),
+    #[http_error(transparent(anyhow))]
+    TransparentAnyhow(#[source] anyhow::Error),
+    #[http_error(transparent(http_error))]
+    TransparentHttpError(#[source] HttpError),
+    #[http_error(status(http::StatusCode::BAD_GATEWAY), reason("bad gateway"))]
+    StatusPath,
+    #[http_error(status(DEFAULT_STATUS))]
+    StatusConst,
+    #[http_error(status(INVALID_STATUS))]
+    StatusConstInvalid,
+    #[http_error(status(400), reason_fn(pluralize_reason))]
+    ReasonFn {
+        count: u64,
+        item: &'static str,
+    },
+    #[http_error(status(502), reason("{attempt}: {source}"))]
+    NamedFrom {
+        #[from]
+        source: std::io::Error,
+        attempt: u32,
+    },
+    #[http_error(status(504))]
+    UnnamedFromWithDefault(Option<String>, #[from] std::fmt::Error),
+    #[http_error(
+        status(503),
+        reason("replica {replica} lagging"),
+        public_message("retry in {retry}s")
+    )]
+    PublicMessage {
+        replica: String,
+        retry: u64,
+    },
+    #[http_error(status(409), reason("order {0} exists"))]
+    #[http_error(data(code = 1001))]
+    #[http_error(data(order = "{0}"))]
+    MultipleAttrs(u64),
+    #[http_error(status(429), reason("client {0} rate limited"), log(level = "warn"))]
+    Logged(u64),
+    #[http_error(status(503), headers("retry-after" = "120", "x-error-code" = "E42"))]
+    #[http_error(headers("Vary" = "accept"))]
+    WithHeaders,
+    #[http_error(reason("upstream failed: {body}"))]
+    Upstream {
+        #[status]
+        status: anyhow_http::http::StatusCode,
+        body: String,
+    },
+    UpstreamCode(#[status] u16),
+    #[http_error(status(409), data(code = self.code(), conflicts = self.conflicts(), kind = "order"))]
+    OrderConflict {
+        order: u64,
+        items: Vec<u64>,
+    },
+    #[http_error(skip)]
+    Internal(#[source] anyhow::Error),
+}
+
+impl CustomError {
+    fn code(&self) -> &str {
+        match self {
+            Self::OrderConflict { items, .. } if items.is_empty() => "order_conflict",
+            _ => "item_conflict",
+        }
+    }
+
+    fn conflicts(&self) -> Vec<String> {
+        match self {
+            Self::OrderConflict { order, items } => {
+                items.iter().map(|item| format!("{order}/{item}")).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+fn pluralize_reason(count: &u64, item: &&'static str) -> String {
+    match count {
+        1 => format!("1 {item} missing"),
+        n => format!("{n} {item}s missing"),
+    }
 }
 
 #[test]
@@ -34,6 +133,206 @@ fn derive_enum_from() {
     assert_eq!(err.source().map(ToString::to_string), Some("source".into()));
 }
 
+#[test]
+fn derive_enum_from_with_default_fields() {
+    let err: CustomError = std::io::Error::other("io").into();
+    assert!(matches!(err, CustomError::NamedFrom { attempt: 0, .. }));
+    let err: HttpError = err.into();
+    assert_eq!(err.status_code(), 502);
+    assert_eq!(err.reason(), Some("0: io".into()));
+    assert_eq!(err.source().map(ToString::to_string), Some("io".into()));
+
+    let err: CustomError = std::fmt::Error.into();
+    assert!(matches!(err, CustomError::UnnamedFromWithDefault(None, _)));
+    let err: HttpError = err.into();
+    assert_eq!(err.status_code(), 504);
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn derive_enum_public_message() {
+    let err: HttpError = CustomError::PublicMessage {
+        replica: "db-2".into(),
+        retry: 30,
+    }
+    .into();
+    assert_eq!(err.status_code(), 503);
+    assert_eq!(err.reason(), Some("replica db-2 lagging".into()));
+    assert_eq!(err.public_message(), Some("retry in 30s"));
+}
+
+#[test]
+fn derive_enum_multiple_attrs() {
+    let err: HttpError = CustomError::MultipleAttrs(7).into();
+    assert_eq!(err.status_code(), 409);
+    assert_eq!(err.reason(), Some("order 7 exists".into()));
+    assert_eq!(err.get("code"), Some(1001));
+    assert_eq!(err.get("order"), Some("7".to_string()));
+}
+
+/// A subscriber that records the level and fields of all events.
+#[derive(Default)]
+struct EventRecorder {
+    events: Mutex<Vec<(Level, BTreeMap<String, String>)>>,
+}
+
+struct FieldRecorder<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+impl Subscriber for EventRecorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = BTreeMap::new();
+        event.record(&mut FieldRecorder(&mut fields));
+        self.events
+            .lock()
+            .unwrap()
+            .push((*event.metadata().level(), fields));
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn derive_enum_log() {
+    let recorder = Arc::new(EventRecorder::default());
+    let err: HttpError =
+        tracing::subscriber::with_default(Arc::clone(&recorder), || CustomError::Logged(7).into());
+    assert_eq!(err.status_code(), 429);
+    assert_eq!(err.reason(), Some("client 7 rate limited".into()));
+
+    let events = recorder.events.lock().unwrap();
+    let [(level, fields)] = events.as_slice() else {
+        panic!("expected one event, got {}", events.len());
+    };
+    assert_eq!(*level, Level::WARN);
+    assert_eq!(
+        fields,
+        &BTreeMap::from(
+            [
+                ("message", "http error"),
+                ("variant", "CustomError::Logged"),
+                ("status", "429"),
+                ("reason", "client 7 rate limited"),
+            ]
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        )
+    );
+    drop(events);
+
+    // variants without `log` emit no events
+    let _: HttpError = tracing::subscriber::with_default(Arc::clone(&recorder), || {
+        CustomError::MultipleAttrs(7).into()
+    });
+    assert_eq!(recorder.events.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn derive_enum_headers() {
+    let err: HttpError = CustomError::WithHeaders.into();
+    assert_eq!(err.status_code(), 503);
+    let headers = err.headers().unwrap();
+    assert_eq!(headers["retry-after"], "120");
+    assert_eq!(headers["x-error-code"], "E42");
+    assert_eq!(headers["vary"], "accept");
+}
+
+#[test]
+fn derive_enum_status_field() {
+    let err: HttpError = CustomError::Upstream {
+        status: anyhow_http::http::StatusCode::BAD_GATEWAY,
+        body: "timeout".into(),
+    }
+    .into();
+    assert_eq!(err.status_code(), 502);
+    assert_eq!(err.reason(), Some("upstream failed: timeout".into()));
+
+    let err: HttpError = CustomError::UpstreamCode(429).into();
+    assert_eq!(err.status_code(), 429);
+    assert!(CustomError::UpstreamCode(429)
+        .to_string()
+        .starts_with("http error 429: "));
+
+    let err: HttpError = CustomError::UpstreamCode(1000).into();
+    assert_eq!(err.status_code(), 500);
+}
+
+#[test]
+fn derive_enum_self_data() {
+    let err: HttpError = CustomError::OrderConflict {
+        order: 7,
+        items: vec![1, 2],
+    }
+    .into();
+    assert_eq!(err.status_code(), 409);
+    assert_eq!(err.get::<String>("code").as_deref(), Some("item_conflict"));
+    assert_eq!(
+        err.get::<Vec<String>>("conflicts"),
+        Some(vec!["7/1".to_owned(), "7/2".to_owned()])
+    );
+    assert_eq!(err.get::<String>("kind").as_deref(), Some("order"));
+
+    let err: HttpError = CustomError::OrderConflict {
+        order: 7,
+        items: vec![],
+    }
+    .into();
+    assert_eq!(err.get::<String>("code").as_deref(), Some("order_conflict"));
+}
+
+#[test]
+fn derive_enum_skip_display() {
+    let err = CustomError::Internal(anyhow::anyhow!("retry"));
+    assert!(err.to_string().ends_with("Internal: retry"));
+}
+
+#[test]
+#[should_panic(expected = "is marked `#[http_error(skip)]`")]
+fn derive_enum_skip() {
+    let _: HttpError = CustomError::Internal(anyhow::anyhow!("retry")).into();
+}
+
+#[test]
+fn derive_enum_skip_anyhow() {
+    let err = anyhow::Error::from(CustomError::Internal(anyhow::anyhow!("retry")));
+    assert!(matches!(
+        err.downcast_ref::<CustomError>(),
+        Some(CustomError::Internal(_))
+    ));
+
+    fn handler() -> HttpJsonResult<()> {
+        Err(CustomError::Internal(anyhow::anyhow!("retry")))?
+    }
+    let resp = handler().unwrap_err();
+    assert_eq!(resp.http_error.status_code(), 500);
+}
+
+#[test]
+fn derive_enum_render() {
+    let rendered = render::<Json, _>(CustomError::WithHeaders);
+    assert_eq!(rendered.status, 503);
+    assert_eq!(rendered.headers["retry-after"], "120");
+    assert_eq!(
+        rendered.body_json()["error"]["message"],
+        "Service Unavailable"
+    );
+}
+
 #[test]
 fn derive_enum_named_with_source() {
     let err: HttpError = CustomError::NamedWithSource {
@@ -66,3 +365,206 @@ fn derive_enum_transparent() {
     assert_eq!(err.reason(), Some("bad request".into()));
     assert!(err.source().is_none());
 }
+
+#[test]
+fn derive_enum_reason_fn() {
+    let err: HttpError = CustomError::ReasonFn {
+        count: 1,
+        item: "field",
+    }
+    .into();
+    assert_eq!(err.status_code(), 400);
+    assert_eq!(err.reason(), Some("1 field missing".into()));
+
+    let err: HttpError = CustomError::ReasonFn {
+        count: 3,
+        item: "field",
+    }
+    .into();
+    assert_eq!(err.reason(), Some("3 fields missing".into()));
+}
+
+#[test]
+fn derive_enum_transparent_modes() {
+    let err: HttpError = CustomError::TransparentAnyhow(anyhow::anyhow!("source")).into();
+    assert_eq!(err.status_code(), 500);
+    assert_eq!(err.source().map(ToString::to_string), Some("source".into()));
+
+    let err: HttpError = CustomError::TransparentAnyhow(http_error!(NOT_FOUND).into()).into();
+    assert_eq!(err.status_code(), 404);
+
+    let err: HttpError =
+        CustomError::TransparentHttpError(http_error!(BAD_REQUEST, "bad request")).into();
+    assert_eq!(err.status_code(), 400);
+    assert_eq!(err.reason(), Some("bad request".into()));
+}
+
+#[test]
+fn derive_enum_status_expr() {
+    let err: HttpError = CustomError::StatusPath.into();
+    assert_eq!(err.status_code(), 502);
+    assert_eq!(err.reason(), Some("bad gateway".into()));
+
+    let err: HttpError = CustomError::StatusConst.into();
+    assert_eq!(err.status_code(), 503);
+
+    let err: HttpError = CustomError::StatusConstInvalid.into();
+    assert_eq!(err.status_code(), 500);
+}
+
+#[test]
+fn derive_enum_static_reason_borrowed() {
+    let err: HttpError = CustomError::StatusPath.into();
+    assert!(matches!(err.reason(), Some(Cow::Borrowed("bad gateway"))));
+
+    let err: HttpError = CustomError::MultipleAttrs(7).into();
+    assert!(matches!(err.reason(), Some(Cow::Owned(_))));
+}
+
+#[derive(Debug, FromHttpError)]
+#[http_error(response(anyhow_http::response::Json))]
+enum ResponseError {
+    #[http_error(status(404), reason("not found"))]
+    NotFound,
+}
+
+#[test]
+fn derive_enum_response() {
+    use anyhow_http::response::HttpJsonErrorResponse;
+    use axum::response::IntoResponse;
+
+    let resp = ResponseError::NotFound.into_response();
+    assert_eq!(resp.status(), 404);
+
+    let resp: HttpJsonErrorResponse = ResponseError::NotFound.into();
+    assert_eq!(resp.http_error.reason(), Some("not found".into()));
+}
+
+#[derive(Debug, FromHttpError)]
+#[http_error(try_from)]
+enum PartialError {
+    #[http_error(status(404), reason("user {0} not found"))]
+    NotFound(u64),
+    #[http_error(skip)]
+    Retry { attempt: u32 },
+}
+
+#[test]
+fn derive_enum_try_from() {
+    let err = HttpError::try_from(PartialError::NotFound(7)).unwrap();
+    assert_eq!(err.status_code(), 404);
+    assert_eq!(err.reason(), Some("user 7 not found".into()));
+
+    let err = HttpError::try_from(PartialError::Retry { attempt: 2 }).unwrap_err();
+    assert!(matches!(err, PartialError::Retry { attempt: 2 }));
+
+    let err = anyhow::Error::from(PartialError::Retry { attempt: 2 });
+    assert!(matches!(
+        err.downcast_ref::<PartialError>(),
+        Some(PartialError::Retry { attempt: 2 })
+    ));
+    let err = HttpError::from(err);
+    assert_eq!(err.status_code(), 500);
+    let err = HttpError::from(anyhow::Error::from(PartialError::NotFound(7)));
+    assert_eq!(err.status_code(), 404);
+}
+
+#[derive(Debug, FromHttpError)]
+#[http_error(code_prefix = "BILLING_", catalog)]
+enum BillingError {
+    #[http_error(status(402))]
+    PaymentRequired,
+    #[http_error(status(504), reason("gateway {0} timed out"))]
+    HTTPGatewayTimeout(String),
+    #[http_error(status(409), data(code = "DUPLICATE_INVOICE", invoice = "{0}"))]
+    Conflict(u64),
+    #[http_error(status(http::StatusCode::TOO_MANY_REQUESTS), data(code = 4290))]
+    RateLimited,
+    #[http_error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[test]
+fn derive_enum_code_prefix() {
+    let err: HttpError = BillingError::PaymentRequired.into();
+    assert_eq!(
+        err.get("code"),
+        Some("BILLING_PAYMENT_REQUIRED".to_string())
+    );
+
+    let err: HttpError = BillingError::HTTPGatewayTimeout("eu-1".into()).into();
+    assert_eq!(
+        err.get("code"),
+        Some("BILLING_HTTP_GATEWAY_TIMEOUT".to_string())
+    );
+    assert_eq!(err.reason(), Some("gateway eu-1 timed out".into()));
+
+    // a declared `code` takes precedence
+    let err: HttpError = BillingError::Conflict(7).into();
+    assert_eq!(err.get("code"), Some("DUPLICATE_INVOICE".to_string()));
+    assert_eq!(err.get("invoice"), Some("7".to_string()));
+    let err: HttpError = BillingError::RateLimited.into();
+    assert_eq!(err.get("code"), Some(4290));
+
+    let err: HttpError = BillingError::Other(anyhow::anyhow!("error")).into();
+    assert!(err.get::<String>("code").is_none());
+}
+
+#[test]
+fn derive_enum_catalog() {
+    use anyhow_http::{ErrorCode, ErrorDescriptor};
+    use http::StatusCode;
+
+    assert_eq!(
+        BillingError::catalog(),
+        [
+            ErrorDescriptor {
+                name: "BillingError::PaymentRequired",
+                status_code: StatusCode::PAYMENT_REQUIRED,
+                reason: None,
+                code: Some(ErrorCode::Symbolic("BILLING_PAYMENT_REQUIRED")),
+                data_keys: &["code"],
+            },
+            ErrorDescriptor {
+                name: "BillingError::HTTPGatewayTimeout",
+                status_code: StatusCode::GATEWAY_TIMEOUT,
+                reason: Some("gateway {0} timed out"),
+                code: Some(ErrorCode::Symbolic("BILLING_HTTP_GATEWAY_TIMEOUT")),
+                data_keys: &["code"],
+            },
+            ErrorDescriptor {
+                name: "BillingError::Conflict",
+                status_code: StatusCode::CONFLICT,
+                reason: None,
+                code: Some(ErrorCode::Symbolic("DUPLICATE_INVOICE")),
+                data_keys: &["code", "invoice"],
+            },
+            ErrorDescriptor {
+                name: "BillingError::RateLimited",
+                status_code: StatusCode::TOO_MANY_REQUESTS,
+                reason: None,
+                code: Some(ErrorCode::Numeric(4290)),
+                data_keys: &["code"],
+            },
+        ]
+    );
+    assert!(std::ptr::eq(
+        BillingError::catalog(),
+        BillingError::catalog()
+    ));
+}
+
+#[test]
+fn derive_origin() {
+    let err: HttpError = BillingError::PaymentRequired.into();
+    assert_eq!(err.origin(), Some("BillingError::PaymentRequired"));
+
+    let err: HttpError = CustomError::StatusPath.into();
+    assert_eq!(err.origin(), Some("CustomError::StatusPath"));
+
+    // transparent variants keep the origin of the wrapped error
+    let err: HttpError = CustomError::TransparentHttpError(BillingError::Conflict(7).into()).into();
+    assert_eq!(err.origin(), Some("BillingError::Conflict"));
+    let err: HttpError = BillingError::Other(anyhow::anyhow!("error")).into();
+    assert_eq!(err.origin(), None);
+}
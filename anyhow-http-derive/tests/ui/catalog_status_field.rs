@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+#[http_error(catalog)]
+enum CustomError {
+    Upstream(#[status] u16),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(status(409), data(code = 1001))]
+    #[http_error(data(code = 1002))]
+    Conflict,
+}
+
+fn main() {}
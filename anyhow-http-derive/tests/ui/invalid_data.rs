@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(status(400), data(code = 1 + 2))]
+    InvalidData,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(status(503), headers("retry after" = "120"))]
+    Unavailable,
+}
+
+fn main() {}
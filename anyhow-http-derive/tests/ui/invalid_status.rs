@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(status(1000))]
+    InvalidStatus,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(transparent(http_error))]
+    Inner(#[source] anyhow::Error),
+}
+
+fn main() {}
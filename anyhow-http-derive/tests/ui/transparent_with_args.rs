@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(transparent, status(500))]
+    Inner(#[source] anyhow::Error),
+}
+
+fn main() {}
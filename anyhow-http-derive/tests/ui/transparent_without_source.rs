@@ -0,0 +1,9 @@
+use anyhow_http_derive::FromHttpError;
+
+#[derive(FromHttpError)]
+enum CustomError {
+    #[http_error(transparent)]
+    Inner(anyhow::Error),
+}
+
+fn main() {}
@@ -0,0 +1,120 @@
+//! Measures the cost of moving `Result<T, HttpError>` through a call stack, which depends on the
+//! size of [`HttpError`]. [`Unboxed`] replicates the layout before the rarely set parts were
+//! boxed and is measured as baseline. Run with `cargo bench --bench result_size`.
+use anyhow_http::{http_error, DataMap, HttpError, Violation};
+use http::{HeaderMap, HeaderValue, StatusCode};
+use std::{borrow::Cow, hint::black_box, sync::Arc, time::Instant};
+
+const ITERATIONS: u32 = 1_000_000;
+
+/// The previous layout of [`HttpError`], with all parts stored inline.
+#[allow(dead_code)]
+struct Unboxed {
+    status_code: StatusCode,
+    reason: Option<Cow<'static, str>>,
+    public_message: Option<Cow<'static, str>>,
+    source: Option<Arc<anyhow::Error>>,
+    data: Option<Box<DataMap>>,
+    #[allow(clippy::box_collection)]
+    violations: Option<Box<Vec<Violation>>>,
+    headers: Option<Box<HeaderMap>>,
+    allow_non_error_status: bool,
+    source_chain_limit: Option<u16>,
+    origin: Option<&'static str>,
+}
+
+impl Unboxed {
+    fn new(status_code: StatusCode, reason: &'static str) -> Self {
+        Self {
+            status_code,
+            reason: Some(Cow::Borrowed(reason)),
+            public_message: None,
+            source: None,
+            data: None,
+            violations: None,
+            headers: None,
+            allow_non_error_status: false,
+            source_chain_limit: None,
+            origin: None,
+        }
+    }
+}
+
+/// A call stack that propagates the result of the innermost call with `?`.
+macro_rules! call_stack {
+    ($($name:ident => $next:ident),* $(,)?) => {
+        $(
+            #[inline(never)]
+            fn $name<E>(n: u64, fail: fn(u64) -> Option<E>) -> Result<u64, E> {
+                Ok(black_box($next(n, fail)?) + 1)
+            }
+        )*
+    };
+}
+
+call_stack!(
+    depth_8 => depth_7,
+    depth_7 => depth_6,
+    depth_6 => depth_5,
+    depth_5 => depth_4,
+    depth_4 => depth_3,
+    depth_3 => depth_2,
+    depth_2 => depth_1,
+);
+
+#[inline(never)]
+fn depth_1<E>(n: u64, fail: fn(u64) -> Option<E>) -> Result<u64, E> {
+    match fail(n) {
+        Some(err) => Err(err),
+        None => Ok(n),
+    }
+}
+
+fn bench<E>(name: &str, fail: fn(u64) -> Option<E>) {
+    let start = Instant::now();
+    for n in 0..ITERATIONS {
+        let _ = black_box(depth_8(black_box(u64::from(n)), fail));
+    }
+    let per_iter = start.elapsed().as_secs_f64() * 1e9 / f64::from(ITERATIONS);
+    println!("{name:<24} {per_iter:>8.1} ns/iter");
+}
+
+fn main() {
+    println!(
+        "size_of::<HttpError>() = {}, size_of::<Result<u64, HttpError>>() = {}",
+        size_of::<HttpError>(),
+        size_of::<Result<u64, HttpError>>()
+    );
+    println!(
+        "size_of::<Unboxed>() = {}, size_of::<Result<u64, Unboxed>>() = {}",
+        size_of::<Unboxed>(),
+        size_of::<Result<u64, Unboxed>>()
+    );
+
+    bench::<HttpError>("ok", |_| None);
+    bench("err", |_| Some(http_error!(NOT_FOUND, "user not found")));
+    bench("err_with_data", |n| {
+        Some(
+            http_error!(NOT_FOUND, "user not found")
+                .with_key_value("id", n)
+                .with_header("x-error-code", "E404"),
+        )
+    });
+
+    bench::<Unboxed>("ok (unboxed)", |_| None);
+    bench("err (unboxed)", |_| {
+        Some(Unboxed::new(StatusCode::NOT_FOUND, "user not found"))
+    });
+    bench("err_with_data (unboxed)", |n| {
+        let mut err = Unboxed::new(StatusCode::NOT_FOUND, "user not found");
+        let mut data = DataMap::new();
+        if let Ok(id) = serde_json::to_value(n) {
+            data.insert("id", id);
+        }
+        err.data = Some(Box::new(data));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-error-code", HeaderValue::from_static("E404"));
+        err.headers = Some(Box::new(headers));
+        Some(err)
+    });
+}
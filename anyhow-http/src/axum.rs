@@ -0,0 +1,1992 @@
+//! Integrations for [`axum`](::axum).
+#[cfg(feature = "multipart")]
+use ::axum::extract::multipart::{MultipartError, MultipartRejection};
+use ::axum::{
+    async_trait,
+    extract::{
+        rejection::{ExtensionRejection, JsonRejection, PathRejection, QueryRejection},
+        ConnectInfo, FromRef, FromRequestParts, MatchedPath, State,
+    },
+    middleware::Next,
+    response::sse::Event,
+};
+use futures_core::Stream;
+use http::{
+    header::{ACCEPT, ALLOW, USER_AGENT},
+    request::Parts,
+    Extensions, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri,
+};
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tokio::task::futures::TaskLocalFuture;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{
+    response::{FormatResponse, HttpErrorResponse},
+    HttpError, ELAPSED_DATA_KEY,
+};
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
+/// An extractor that captures request information relevant for error telemetry.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{axum::ErrorContext, http_error, response::HttpJsonResult};
+///
+/// async fn handler(ctx: ErrorContext) -> HttpJsonResult<()> {
+///     Err(http_error!(NOT_FOUND).with_request_context(&ctx))?
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    method: Method,
+    path: String,
+    matched_route: Option<String>,
+}
+
+impl ErrorContext {
+    /// Returns the request method.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Returns the request path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the matched route if any.
+    pub fn matched_route(&self) -> Option<&str> {
+        self.matched_route.as_deref()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ErrorContext
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            method: parts.method.clone(),
+            path: parts.uri.path().to_owned(),
+            matched_route: parts
+                .extensions
+                .get::<MatchedPath>()
+                .map(|p| p.as_str().to_owned()),
+        })
+    }
+}
+
+impl HttpError {
+    /// Records the request method, path and matched route of an [`ErrorContext`] as `request`
+    /// data.
+    #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+    pub fn with_request_context(self, ctx: &ErrorContext) -> Self {
+        self.with_key_value(
+            "request",
+            serde_json::json!({
+                "method": ctx.method.as_str(),
+                "path": ctx.path,
+                "route": ctx.matched_route,
+            }),
+        )
+    }
+}
+
+/// Returns a handler for [`Router::fallback`](::axum::Router::fallback) that responds to
+/// unmatched routes with a `404 Not Found` rendered by the formatter `F`.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{axum::{fallback, method_not_allowed}, response::Json};
+/// use axum::{routing::get, Router};
+/// use http::Method;
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async {}).fallback(method_not_allowed::<Json>([Method::GET])))
+///     .fallback(fallback::<Json>());
+/// # }
+/// ```
+pub fn fallback<F>() -> impl Fn(Uri) -> Ready<HttpErrorResponse<F>> + Clone + Send + Sync + 'static
+where
+    F: FormatResponse + Send + 'static,
+{
+    |uri: Uri| {
+        let http_error = HttpError::from_status_code(StatusCode::NOT_FOUND)
+            .with_key_value("path", uri.path().to_owned());
+        ready(http_error.into())
+    }
+}
+
+/// Returns a handler for [`MethodRouter::fallback`](::axum::routing::MethodRouter::fallback)
+/// that responds to unsupported methods with a `405 Method Not Allowed` rendered by the
+/// formatter `F`. The `allowed` methods of the route are listed in the `Allow` header, as
+/// required for `405` responses.
+pub fn method_not_allowed<F>(
+    allowed: impl IntoIterator<Item = Method>,
+) -> impl Fn(Method) -> Ready<HttpErrorResponse<F>> + Clone + Send + Sync + 'static
+where
+    F: FormatResponse + Send + 'static,
+{
+    let allow = allowed
+        .into_iter()
+        .map(|method| method.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    move |method: Method| {
+        let http_error = HttpError::from_status_code(StatusCode::METHOD_NOT_ALLOWED)
+            .with_key_value("method", method.to_string())
+            .with_header(ALLOW, allow.as_str());
+        ready(http_error.into())
+    }
+}
+
+/// Returns a handler for [`HandleErrorLayer`](::axum::error_handling::HandleErrorLayer) that
+/// responds to errors of tower middleware with a [`HttpError`] rendered by the formatter `F`.
+///
+/// Errors are converted with [`HttpError::classify_box_error`]: timeouts of
+/// [`Timeout`](tower::timeout::Timeout) map to `408 Request Timeout` and requests rejected by
+/// [`LoadShed`](tower::load_shed::LoadShed) map to `503 Service Unavailable`. A [`HttpError`]
+/// returned by a middleware is rendered as is, other errors become the source of a `500 Internal
+/// Server Error` unless they are classified otherwise.
+///
+/// ```
+/// use anyhow_http::{axum::handle_error, response::Json};
+/// use axum::{error_handling::HandleErrorLayer, routing::get, Router};
+/// use std::time::Duration;
+/// use tower::ServiceBuilder;
+///
+/// let app: Router = Router::new().route("/", get(|| async {})).layer(
+///     ServiceBuilder::new()
+///         .layer(HandleErrorLayer::new(handle_error::<Json>()))
+///         .timeout(Duration::from_secs(10)),
+/// );
+/// ```
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub fn handle_error<F>(
+) -> impl Fn(tower::BoxError) -> Ready<HttpErrorResponse<F>> + Clone + Send + Sync + 'static
+where
+    F: FormatResponse + Send + 'static,
+{
+    |err: tower::BoxError| ready(HttpError::classify_box_error(err).into())
+}
+
+macro_rules! impl_from_rejection {
+    (
+        $($(#[$meta:meta])* $rejection:ty),* ;
+        $($(#[$custom_meta:meta])* $custom:ty),* $(,)?
+    ) => {
+        $(
+            $(#[$meta])*
+            #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+            impl From<$rejection> for HttpError {
+                fn from(rejection: $rejection) -> Self {
+                    HttpError::from_status_code(rejection.status())
+                        .with_reason(rejection.body_text())
+                        .with_source_err(rejection)
+                }
+            }
+        )*
+
+        /// Converts an error into a [`HttpError`] if it is one of the supported extractor
+        /// rejections.
+        pub(crate) fn downcast_rejection(err: anyhow::Error) -> Result<HttpError, anyhow::Error> {
+            $(
+                $(#[$meta])*
+                let err = match err.downcast::<$rejection>() {
+                    Ok(rejection) => return Ok(rejection.into()),
+                    Err(err) => err,
+                };
+            )*
+            $(
+                $(#[$custom_meta])*
+                let err = match err.downcast::<$custom>() {
+                    Ok(rejection) => return Ok(rejection.into()),
+                    Err(err) => err,
+                };
+            )*
+            Err(err)
+        }
+    };
+}
+
+// Extractor rejections keep their status code and message, e.g. a malformed JSON body is a
+// `400 Bad Request` rather than a `500`. Rejections after the `;` provide their own `From` impl.
+impl_from_rejection!(
+    JsonRejection,
+    PathRejection,
+    QueryRejection,
+    ExtensionRejection,
+    #[cfg(feature = "multipart")]
+    MultipartRejection;
+    #[cfg(feature = "multipart")]
+    MultipartError,
+);
+
+/// Converts a [`MultipartError`] into a [`HttpError`] with the status code chosen by `axum`, e.g.
+/// `413 Payload Too Large` if a size limit is exceeded and `400 Bad Request` for malformed
+/// bodies. The name of the offending field is recorded as `field` data and an exceeded size limit
+/// as `limit` data.
+#[cfg(feature = "multipart")]
+#[cfg_attr(docsrs, doc(cfg(feature = "multipart")))]
+impl From<MultipartError> for HttpError {
+    fn from(err: MultipartError) -> Self {
+        let mut http_error = HttpError::from_status_code(err.status()).with_reason(err.body_text());
+        let multer_err =
+            std::error::Error::source(&err).and_then(|e| e.downcast_ref::<multer::Error>());
+        match multer_err {
+            Some(
+                multer::Error::UnknownField { field_name }
+                | multer::Error::IncompleteFieldData { field_name },
+            ) => {
+                if let Some(field_name) = field_name {
+                    http_error.insert_data("field", field_name.clone());
+                }
+            }
+            Some(multer::Error::FieldSizeExceeded { limit, field_name }) => {
+                if let Some(field_name) = field_name {
+                    http_error.insert_data("field", field_name.clone());
+                }
+                http_error.insert_data("limit", *limit);
+            }
+            Some(multer::Error::StreamSizeExceeded { limit }) => {
+                http_error.insert_data("limit", *limit);
+            }
+            _ => {}
+        }
+        http_error.with_source_err(err)
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_ACCEPT: Option<HeaderValue>;
+}
+
+/// Returns the `Accept` header of the request that is currently handled within
+/// [`NegotiateLayer`].
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+pub(crate) fn current_accept() -> Option<String> {
+    CURRENT_ACCEPT
+        .try_with(|accept| accept.as_ref()?.to_str().ok().map(String::from))
+        .ok()
+        .flatten()
+}
+
+/// A layer that captures the request's `Accept` header for the
+/// [`Negotiate`](crate::response::Negotiate) formatter. The header is scoped to the task that
+/// handles the request.
+#[derive(Debug, Clone, Default)]
+pub struct NegotiateLayer;
+
+impl NegotiateLayer {
+    /// Creates a [`NegotiateLayer`].
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for NegotiateLayer {
+    type Service = NegotiateService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NegotiateService { inner }
+    }
+}
+
+/// The service created by [`NegotiateLayer`].
+#[derive(Debug, Clone)]
+pub struct NegotiateService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<Request<B>> for NegotiateService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = NegotiateFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let accept = req.headers().get(ACCEPT).cloned();
+        let inner = CURRENT_ACCEPT.sync_scope(accept.clone(), || self.inner.call(req));
+        NegotiateFuture {
+            inner: Box::pin(CURRENT_ACCEPT.scope(accept, inner)),
+        }
+    }
+}
+
+/// The response future of [`NegotiateService`]. The `Accept` header is made available while the
+/// inner future is polled.
+pub struct NegotiateFuture<F> {
+    inner: Pin<Box<TaskLocalFuture<Option<HeaderValue>, F>>>,
+}
+
+impl<F: Future> Future for NegotiateFuture<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_CONFIG: Arc<FormatterConfig>;
+}
+
+/// Returns the [`FormatterConfig`] of the request that is currently handled within
+/// [`FormatterConfigLayer`] or [`formatter_config_from_state`].
+pub(crate) fn current_formatter_config() -> Option<Arc<FormatterConfig>> {
+    CURRENT_CONFIG.try_with(Arc::clone).ok()
+}
+
+/// Runtime configuration of the built-in formatters, so deployments can differ without
+/// switching formatter types. Installed with [`FormatterConfigLayer`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct FormatterConfig {
+    /// Whether the source chain is emitted as `debug` member by [`Json`](crate::response::Json)
+    /// and Problem Details. Should only be enabled in development.
+    pub include_debug: bool,
+    /// The name of the service emitted as `service` member.
+    pub service: Option<Cow<'static, str>>,
+    /// The base url of the Problem Details `type`, which is followed by the status code, e.g.
+    /// `https://example.com/errors/404`. Defaults to `about:blank`.
+    pub problem_type_base: Option<Cow<'static, str>>,
+    /// Whether the `Content-Length` header of buffered error responses is omitted. By default it
+    /// is set, so compression layers like `tower-http`'s `CompressionLayer` can tell small bodies
+    /// apart, which aren't worth compressing. Note that axum's router sets the header for
+    /// responses of routes either way.
+    pub omit_content_length: bool,
+}
+
+impl FormatterConfig {
+    /// Creates an empty [`FormatterConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the source chain is emitted.
+    pub fn with_include_debug(mut self, include_debug: bool) -> Self {
+        self.include_debug = include_debug;
+        self
+    }
+
+    /// Sets the name of the service.
+    pub fn with_service<S: Into<Cow<'static, str>>>(mut self, service: S) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+
+    /// Sets the base url of the Problem Details `type`.
+    pub fn with_problem_type_base<S: Into<Cow<'static, str>>>(mut self, base: S) -> Self {
+        self.problem_type_base = Some(base.into());
+        self
+    }
+
+    /// Sets whether the `Content-Length` header is omitted.
+    pub fn with_omit_content_length(mut self, omit_content_length: bool) -> Self {
+        self.omit_content_length = omit_content_length;
+        self
+    }
+}
+
+/// A layer that makes a [`FormatterConfig`] available to the formatters while a request is
+/// handled. A [`FormatterConfig`] in the request extensions, e.g. added with axum's
+/// `Extension` layer, takes precedence over the one of the layer. The configuration is scoped to
+/// the task that handles the request, so it is not visible to other requests or tasks spawned
+/// by the handler. To take the configuration from the application state, see
+/// [`formatter_config_from_state`].
+///
+/// ```
+/// use anyhow_http::axum::{FormatterConfig, FormatterConfigLayer};
+/// use axum::{routing::get, Router};
+///
+/// let config = FormatterConfig::new()
+///     .with_include_debug(cfg!(debug_assertions))
+///     .with_service("billing");
+/// let app: Router = Router::new()
+///     .route("/", get(|| async {}))
+///     .layer(FormatterConfigLayer::new(config));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FormatterConfigLayer {
+    config: Arc<FormatterConfig>,
+}
+
+impl FormatterConfigLayer {
+    /// Creates a [`FormatterConfigLayer`].
+    pub fn new(config: FormatterConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for FormatterConfigLayer {
+    type Service = FormatterConfigService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FormatterConfigService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// The service created by [`FormatterConfigLayer`].
+#[derive(Debug, Clone)]
+pub struct FormatterConfigService<S> {
+    inner: S,
+    config: Arc<FormatterConfig>,
+}
+
+impl<S, B> Service<Request<B>> for FormatterConfigService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = FormatterConfigFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let config = match req.extensions().get::<FormatterConfig>() {
+            Some(config) => Arc::new(config.clone()),
+            None => self.config.clone(),
+        };
+        let inner = CURRENT_CONFIG.sync_scope(config.clone(), || self.inner.call(req));
+        FormatterConfigFuture {
+            inner: Box::pin(CURRENT_CONFIG.scope(config, inner)),
+        }
+    }
+}
+
+/// The response future of [`FormatterConfigService`]. The [`FormatterConfig`] is made available
+/// while the inner future is polled.
+pub struct FormatterConfigFuture<F> {
+    inner: Pin<Box<TaskLocalFuture<Arc<FormatterConfig>, F>>>,
+}
+
+impl<F: Future> Future for FormatterConfigFuture<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+/// A middleware for [`axum::middleware::from_fn_with_state`](::axum::middleware::from_fn_with_state)
+/// that makes the [`FormatterConfig`] of the application state available to the formatters,
+/// like [`FormatterConfigLayer`]. A [`FormatterConfig`] in the request extensions takes
+/// precedence.
+///
+/// ```
+/// use anyhow_http::axum::{formatter_config_from_state, FormatterConfig};
+/// use axum::{extract::FromRef, middleware, routing::get, Router};
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     debug: bool,
+/// }
+///
+/// impl FromRef<AppState> for FormatterConfig {
+///     fn from_ref(state: &AppState) -> Self {
+///         FormatterConfig::new().with_include_debug(state.debug)
+///     }
+/// }
+///
+/// let state = AppState { debug: cfg!(debug_assertions) };
+/// let app: Router = Router::new()
+///     .route("/", get(|| async {}))
+///     .layer(middleware::from_fn_with_state(
+///         state.clone(),
+///         formatter_config_from_state::<AppState>,
+///     ))
+///     .with_state(state);
+/// ```
+pub async fn formatter_config_from_state<S>(
+    State(state): State<S>,
+    req: ::axum::extract::Request,
+    next: Next,
+) -> ::axum::response::Response
+where
+    FormatterConfig: FromRef<S>,
+{
+    let config = match req.extensions().get::<FormatterConfig>() {
+        Some(config) => config.clone(),
+        None => FormatterConfig::from_ref(&state),
+    };
+    CURRENT_CONFIG.scope(Arc::new(config), next.run(req)).await
+}
+
+tokio::task_local! {
+    static CURRENT_TIMING: RequestTiming;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RequestTiming {
+    start: Instant,
+    server_timing: bool,
+}
+
+/// Records the time elapsed since the start of the request that is currently handled within
+/// [`ElapsedLayer`], unless the [`HttpError`] already carries it.
+pub(crate) fn with_request_elapsed(http_error: HttpError) -> HttpError {
+    let Ok(timing) = CURRENT_TIMING.try_with(|timing| *timing) else {
+        return http_error;
+    };
+    if http_error.get::<u64>(ELAPSED_DATA_KEY).is_some() {
+        return http_error;
+    }
+    let elapsed = timing.start.elapsed();
+    let http_error = http_error.with_elapsed(elapsed);
+    if timing.server_timing {
+        http_error.with_server_timing("handler", elapsed)
+    } else {
+        http_error
+    }
+}
+
+/// A layer that measures the duration of each request and records it on outgoing error
+/// responses, see [`HttpError::with_elapsed`]. The start of the request is scoped to the task
+/// that handles it.
+///
+/// ```
+/// use anyhow_http::axum::ElapsedLayer;
+/// use axum::{routing::get, Router};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async {}))
+///     .layer(ElapsedLayer::new().with_server_timing(true));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ElapsedLayer {
+    server_timing: bool,
+}
+
+impl ElapsedLayer {
+    /// Creates an [`ElapsedLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Additionally emits the duration as `Server-Timing: handler;dur=..` header.
+    pub fn with_server_timing(mut self, server_timing: bool) -> Self {
+        self.server_timing = server_timing;
+        self
+    }
+}
+
+impl<S> Layer<S> for ElapsedLayer {
+    type Service = ElapsedService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ElapsedService {
+            inner,
+            server_timing: self.server_timing,
+        }
+    }
+}
+
+/// The service created by [`ElapsedLayer`].
+#[derive(Debug, Clone)]
+pub struct ElapsedService<S> {
+    inner: S,
+    server_timing: bool,
+}
+
+impl<S, B> Service<Request<B>> for ElapsedService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ElapsedFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let timing = RequestTiming {
+            start: Instant::now(),
+            server_timing: self.server_timing,
+        };
+        let inner = CURRENT_TIMING.sync_scope(timing, || self.inner.call(req));
+        ElapsedFuture {
+            inner: Box::pin(CURRENT_TIMING.scope(timing, inner)),
+        }
+    }
+}
+
+/// The response future of [`ElapsedService`]. The request start is made available while the
+/// inner future is polled.
+pub struct ElapsedFuture<F> {
+    inner: Pin<Box<TaskLocalFuture<RequestTiming, F>>>,
+}
+
+impl<F: Future> Future for ElapsedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+/// A rule of [`RouteErrorOverrideLayer`] that rewrites the error responses of the routes below a
+/// path prefix. The prefix matches whole path segments, i.e. `/admin` matches `/admin` and
+/// `/admin/users` but not `/administrator`.
+///
+/// Rewriting the status replaces the error with a fresh [`HttpError`] of that status, so the
+/// response doesn't give away the original error, e.g. that a `404` was a `403`. Rewriting only
+/// the reason keeps the rest of the error.
+#[derive(Debug, Clone)]
+pub struct RouteErrorOverride {
+    path_prefix: Cow<'static, str>,
+    when_status: Option<StatusCode>,
+    status: Option<StatusCode>,
+    reason: Option<Cow<'static, str>>,
+}
+
+impl RouteErrorOverride {
+    /// Creates a rule for the routes below `path_prefix` that matches all error responses.
+    pub fn new<P: Into<Cow<'static, str>>>(path_prefix: P) -> Self {
+        Self {
+            path_prefix: path_prefix.into(),
+            when_status: None,
+            status: None,
+            reason: None,
+        }
+    }
+
+    /// Only matches error responses with status code `status`.
+    pub fn when_status(mut self, status: StatusCode) -> Self {
+        self.when_status = Some(status);
+        self
+    }
+
+    /// Rewrites the status code of matched errors to `status`.
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Rewrites the reason of matched errors to `reason`.
+    pub fn with_reason<S: Into<Cow<'static, str>>>(mut self, reason: S) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    fn matches_path(&self, path: &str) -> bool {
+        let prefix = self.path_prefix.trim_end_matches('/');
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    fn apply(&self, http_error: HttpError) -> HttpError {
+        let http_error = match self.status {
+            Some(status) => HttpError::from_status_code(status),
+            None => http_error,
+        };
+        match &self.reason {
+            Some(reason) => http_error.with_reason(reason.clone()),
+            None => http_error,
+        }
+    }
+}
+
+/// A layer that rewrites the status or reason of error responses for designated routes, e.g. to
+/// respond to probes of `/admin` with `404 Not Found` instead of `403 Forbidden`. The rules are
+/// applied to responses that carry a [`HttpError`], i.e. that are rendered by this crate, and the
+/// first matching rule wins. Rewritten errors are rendered again with the formatter `F`.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{axum::{RouteErrorOverride, RouteErrorOverrideLayer}, response::Json};
+/// use axum::{routing::get, Router};
+/// use http::StatusCode;
+///
+/// let app: Router = Router::new()
+///     .route("/admin/users", get(|| async {}))
+///     .layer(RouteErrorOverrideLayer::<Json>::new([
+///         RouteErrorOverride::new("/admin")
+///             .when_status(StatusCode::FORBIDDEN)
+///             .with_status(StatusCode::NOT_FOUND),
+///     ]));
+/// # }
+/// ```
+pub struct RouteErrorOverrideLayer<F> {
+    rules: Arc<[RouteErrorOverride]>,
+    _formatter: PhantomData<fn() -> F>,
+}
+
+impl<F> RouteErrorOverrideLayer<F> {
+    /// Creates a [`RouteErrorOverrideLayer`] with the given rules.
+    pub fn new(rules: impl IntoIterator<Item = RouteErrorOverride>) -> Self {
+        Self {
+            rules: rules.into_iter().collect(),
+            _formatter: PhantomData,
+        }
+    }
+}
+
+impl<F> Clone for RouteErrorOverrideLayer<F> {
+    fn clone(&self) -> Self {
+        Self {
+            rules: self.rules.clone(),
+            _formatter: PhantomData,
+        }
+    }
+}
+
+impl<S, F> Layer<S> for RouteErrorOverrideLayer<F> {
+    type Service = RouteErrorOverrideService<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RouteErrorOverrideService {
+            inner,
+            rules: self.rules.clone(),
+            _formatter: PhantomData,
+        }
+    }
+}
+
+/// The service created by [`RouteErrorOverrideLayer`].
+pub struct RouteErrorOverrideService<S, F> {
+    inner: S,
+    rules: Arc<[RouteErrorOverride]>,
+    _formatter: PhantomData<fn() -> F>,
+}
+
+impl<S: Clone, F> Clone for RouteErrorOverrideService<S, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            rules: self.rules.clone(),
+            _formatter: PhantomData,
+        }
+    }
+}
+
+impl<S, F, B> Service<Request<B>> for RouteErrorOverrideService<S, F>
+where
+    S: Service<Request<B>, Response = ::axum::response::Response>,
+    F: FormatResponse,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = RouteErrorOverrideFuture<S::Future, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let path = req.uri().path();
+        let rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches_path(path))
+            .cloned()
+            .collect();
+        RouteErrorOverrideFuture {
+            inner: Box::pin(self.inner.call(req)),
+            rules,
+            _formatter: PhantomData,
+        }
+    }
+}
+
+/// The response future of [`RouteErrorOverrideService`]. Error responses are rewritten by the
+/// rules that match the path of the request.
+pub struct RouteErrorOverrideFuture<Fut, F> {
+    inner: Pin<Box<Fut>>,
+    rules: Vec<RouteErrorOverride>,
+    _formatter: PhantomData<fn() -> F>,
+}
+
+impl<Fut, E, F> Future for RouteErrorOverrideFuture<Fut, F>
+where
+    Fut: Future<Output = Result<::axum::response::Response, E>>,
+    F: FormatResponse,
+{
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let resp = match self.inner.as_mut().poll(cx) {
+            Poll::Ready(Ok(resp)) => resp,
+            other => return other,
+        };
+        let rule = self.rules.iter().find(|rule| {
+            rule.when_status
+                .is_none_or(|status| status == resp.status())
+        });
+        let (Some(rule), Some(http_error)) = (rule, resp.extensions().get::<Arc<HttpError>>())
+        else {
+            return Poll::Ready(Ok(resp));
+        };
+        let http_error = rule.apply(HttpError::clone(http_error));
+        Poll::Ready(Ok(::axum::response::IntoResponse::into_response(
+            HttpErrorResponse::<F>::from(http_error),
+        )))
+    }
+}
+
+/// The data key under which [`RequestMeta`] is recorded, see [`HttpError::with_request_meta`].
+pub const REQUEST_META_DATA_KEY: &str = "request_meta";
+
+/// Information about the client of a request, e.g. for abuse investigation. Captured by
+/// [`RequestMetaLayer`], which honors `X-Forwarded-For` and `X-Forwarded-Proto` of trusted
+/// proxies, and available as extractor. Without the layer, the extractor only trusts the
+/// connection, i.e. the peer address of axum's [`ConnectInfo`](::axum::extract::ConnectInfo).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestMeta {
+    client_ip: Option<IpAddr>,
+    user_agent: Option<String>,
+    forwarded_proto: Option<String>,
+}
+
+impl RequestMeta {
+    /// Captures the request information. Forwarding headers are only honored if the peer is one
+    /// of `trusted_proxies`, and the client is the last address in `X-Forwarded-For` that is not
+    /// a trusted proxy.
+    fn capture(headers: &HeaderMap, extensions: &Extensions, trusted_proxies: &[IpAddr]) -> Self {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let peer = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let trusted = peer.is_some_and(|peer| trusted_proxies.contains(&peer));
+
+        let mut client_ip = peer;
+        if trusted {
+            let hops = headers
+                .get_all(X_FORWARDED_FOR)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .collect::<Vec<_>>();
+            for hop in hops.into_iter().rev() {
+                let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                    break;
+                };
+                client_ip = Some(ip);
+                if !trusted_proxies.contains(&ip) {
+                    break;
+                }
+            }
+        }
+
+        Self {
+            client_ip,
+            user_agent: header(USER_AGENT).map(String::from),
+            forwarded_proto: header(X_FORWARDED_PROTO)
+                .filter(|_| trusted)
+                .map(|proto| proto.trim().to_owned()),
+        }
+    }
+
+    /// Returns the IP address of the client.
+    pub fn client_ip(&self) -> Option<IpAddr> {
+        self.client_ip
+    }
+
+    /// Returns the `User-Agent` of the client.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    /// Returns the protocol the client used to connect to a trusted proxy, e.g. `https`.
+    pub fn forwarded_proto(&self) -> Option<&str> {
+        self.forwarded_proto.as_deref()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestMeta
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(match parts.extensions.get::<RequestMeta>() {
+            Some(meta) => meta.clone(),
+            None => RequestMeta::capture(&parts.headers, &parts.extensions, &[]),
+        })
+    }
+}
+
+/// A part of [`RequestMeta`], see [`HttpError::with_request_meta`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestMetaField {
+    /// The IP address of the client, recorded as `client_ip`.
+    ClientIp,
+    /// The `User-Agent` of the client, recorded as `user_agent`.
+    UserAgent,
+    /// The forwarded protocol, recorded as `forwarded_proto`.
+    ForwardedProto,
+}
+
+impl HttpError {
+    /// Records the selected `fields` of a [`RequestMeta`] as `request_meta` data. Fields that
+    /// were not captured are omitted. Client information may be personal data, so only the
+    /// fields that are needed should be recorded.
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")] {
+    /// use anyhow_http::{
+    ///     axum::{RequestMeta, RequestMetaField},
+    ///     http_error,
+    ///     response::HttpJsonResult,
+    /// };
+    ///
+    /// async fn handler(meta: RequestMeta) -> HttpJsonResult<()> {
+    ///     Err(http_error!(TOO_MANY_REQUESTS)
+    ///         .with_request_meta(&meta, &[RequestMetaField::ClientIp]))?
+    /// }
+    /// # }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+    pub fn with_request_meta(self, meta: &RequestMeta, fields: &[RequestMetaField]) -> Self {
+        let mut data = serde_json::Map::new();
+        for field in fields {
+            let (key, value) = match field {
+                RequestMetaField::ClientIp => {
+                    ("client_ip", meta.client_ip.map(|ip| ip.to_string()))
+                }
+                RequestMetaField::UserAgent => ("user_agent", meta.user_agent.clone()),
+                RequestMetaField::ForwardedProto => {
+                    ("forwarded_proto", meta.forwarded_proto.clone())
+                }
+            };
+            if let Some(value) = value {
+                data.insert(key.to_owned(), value.into());
+            }
+        }
+        if data.is_empty() {
+            return self;
+        }
+        self.with_key_value(REQUEST_META_DATA_KEY, data)
+    }
+}
+
+/// A layer that captures the [`RequestMeta`] of each request into the request extensions. The
+/// forwarding headers are only honored for requests of trusted proxies, which requires the peer
+/// address, i.e. serving the app with
+/// [`into_make_service_with_connect_info`](::axum::Router::into_make_service_with_connect_info).
+///
+/// ```
+/// use anyhow_http::axum::RequestMetaLayer;
+/// use axum::{routing::get, Router};
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// let proxy = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+/// let app: Router = Router::new()
+///     .route("/", get(|| async {}))
+///     .layer(RequestMetaLayer::new().with_trusted_proxies([proxy]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RequestMetaLayer {
+    trusted_proxies: Arc<[IpAddr]>,
+}
+
+impl RequestMetaLayer {
+    /// Creates a [`RequestMetaLayer`] that trusts no proxies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the addresses of the proxies whose forwarding headers are honored.
+    pub fn with_trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = proxies.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Layer<S> for RequestMetaLayer {
+    type Service = RequestMetaService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestMetaService {
+            inner,
+            trusted_proxies: self.trusted_proxies.clone(),
+        }
+    }
+}
+
+/// The service created by [`RequestMetaLayer`].
+#[derive(Debug, Clone)]
+pub struct RequestMetaService<S> {
+    inner: S,
+    trusted_proxies: Arc<[IpAddr]>,
+}
+
+impl<S, B> Service<Request<B>> for RequestMetaService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let meta = RequestMeta::capture(req.headers(), req.extensions(), &self.trusted_proxies);
+        req.extensions_mut().insert(meta);
+        self.inner.call(req)
+    }
+}
+
+impl HttpError {
+    /// Converts the error into a server-sent event with type `error` and the error rendered by
+    /// the formatter `F` as data, e.g. to report an error mid-stream.
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")] {
+    /// use anyhow_http::{http_error, response::Json};
+    ///
+    /// let event = http_error!(NOT_FOUND, "user not found").to_sse_event::<Json>();
+    /// # }
+    /// ```
+    pub fn to_sse_event<F: FormatResponse>(&self) -> Event {
+        let data = F::format_response(self);
+        Event::default()
+            .event("error")
+            .data(String::from_utf8_lossy(&data))
+    }
+}
+
+/// A stream adapter for [`Sse`](::axum::response::Sse) responses. Events of the inner stream are
+/// passed through. The first error terminates the stream with an `error` event rendered by the
+/// formatter `F`, see [`HttpError::to_sse_event`].
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{axum::SseErrorStream, http_error, response::Json, HttpError};
+/// use axum::{
+///     response::{
+///         sse::{Event, Sse},
+///         IntoResponse,
+///     },
+///     routing::get,
+///     Router,
+/// };
+/// use futures_util::stream;
+///
+/// async fn handler() -> impl IntoResponse {
+///     let events = stream::iter([
+///         Ok::<_, HttpError>(Event::default().data("1")),
+///         Err(http_error!(SERVICE_UNAVAILABLE, "upstream closed")),
+///     ]);
+///     Sse::new(SseErrorStream::<_, Json>::new(events))
+/// }
+///
+/// let app: Router = Router::new().route("/events", get(handler));
+/// # }
+/// ```
+pub struct SseErrorStream<S, F> {
+    inner: Option<Pin<Box<S>>>,
+    _formatter: PhantomData<fn() -> F>,
+}
+
+impl<S, F> SseErrorStream<S, F> {
+    /// Wraps the stream `inner`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner: Some(Box::pin(inner)),
+            _formatter: PhantomData,
+        }
+    }
+}
+
+impl<S, E, F> Stream for SseErrorStream<S, F>
+where
+    S: Stream<Item = Result<Event, E>>,
+    E: Into<HttpError>,
+    F: FormatResponse,
+{
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(inner) = self.inner.as_mut() else {
+            return Poll::Ready(None);
+        };
+        match inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(event))) => Poll::Ready(Some(Ok(event))),
+            Poll::Ready(Some(Err(err))) => {
+                self.inner = None;
+                Poll::Ready(Some(Ok(err.into().to_sse_event::<F>())))
+            }
+            Poll::Ready(None) => {
+                self.inner = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The maximum length in bytes of the reason of a WebSocket close frame.
+const WS_CLOSE_REASON_MAX_LEN: usize = 123;
+
+/// A WebSocket close frame for a [`HttpError`], see [`HttpError::to_ws_close`]. It converts into
+/// the close frame of axum's `ws` feature with
+/// `CloseFrame { code: close.code(), reason: close.reason().to_owned().into() }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsClose {
+    code: u16,
+    reason: String,
+}
+
+impl WsClose {
+    /// Returns the close code, see [`HttpError::ws_close_code`].
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Returns the reason, at most 123 bytes long.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl HttpError {
+    /// Returns the WebSocket close code for the status code of the error:
+    ///
+    /// - `400 Bad Request` and `422 Unprocessable Entity` map to `1007` (invalid payload data),
+    /// - `413 Payload Too Large` to `1009` (message too big),
+    /// - `415 Unsupported Media Type` to `1003` (unsupported data),
+    /// - other client errors to `1008` (policy violation),
+    /// - `502 Bad Gateway` and `504 Gateway Timeout` to `1014` (bad gateway),
+    /// - `503 Service Unavailable` to `1013` (try again later),
+    /// - other server errors to `1011` (internal error).
+    pub fn ws_close_code(&self) -> u16 {
+        match self.status_code() {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => 1007,
+            StatusCode::PAYLOAD_TOO_LARGE => 1009,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => 1003,
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => 1014,
+            StatusCode::SERVICE_UNAVAILABLE => 1013,
+            status_code if status_code.is_client_error() => 1008,
+            _ => 1011,
+        }
+    }
+
+    /// Converts the error into a WebSocket close frame with the [close code](Self::ws_close_code)
+    /// of its status and its message as reason, see
+    /// [`default_reason`](crate::response::default_reason). The reason is truncated to the 123
+    /// bytes that fit into a close frame and then ends with an ellipsis.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let close = http_error!(FORBIDDEN, "session expired").to_ws_close();
+    /// assert_eq!(close.code(), 1008);
+    /// assert_eq!(close.reason(), "session expired");
+    /// ```
+    pub fn to_ws_close(&self) -> WsClose {
+        let reason = crate::response::default_reason(self).unwrap_or_default();
+        WsClose {
+            code: self.ws_close_code(),
+            reason: crate::http_error::truncate_reason(reason, WS_CLOSE_REASON_MAX_LEN)
+                .into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::axum::{body::Body, routing::get, Router};
+    use http::{Request, StatusCode};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn error_context_from_request_parts() {
+        let captured = Arc::new(Mutex::new(None));
+        let app = Router::new().route(
+            "/users/:id",
+            get({
+                let captured = captured.clone();
+                move |ctx: ErrorContext| async move {
+                    *captured.lock().unwrap() = Some(ctx);
+                }
+            }),
+        );
+
+        let resp = app
+            .oneshot(Request::get("/users/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let ctx = captured.lock().unwrap().take().unwrap();
+        assert_eq!(ctx.method(), Method::GET);
+        assert_eq!(ctx.path(), "/users/42");
+        assert_eq!(ctx.matched_route(), Some("/users/:id"));
+
+        let e = HttpError::default().with_request_context(&ctx);
+        assert_eq!(
+            e.get::<serde_json::Value>("request"),
+            Some(serde_json::json!({
+                "method": "GET",
+                "path": "/users/42",
+                "route": "/users/:id",
+            }))
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn fallback_handlers() {
+        use crate::response::Json;
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {}).fallback(method_not_allowed::<Json>([Method::GET])),
+            )
+            .fallback(fallback::<Json>());
+
+        let resp = app
+            .clone()
+            .oneshot(Request::get("/missing").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "error": { "message": "Not Found", "path": "/missing" } })
+        );
+
+        let resp = app
+            .oneshot(Request::delete("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers()[ALLOW], "GET");
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "error": { "message": "Method Not Allowed", "method": "DELETE" } })
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn http_error_from_rejection() {
+        use crate::response::HttpJsonResult;
+        use ::axum::{
+            extract::{Path, Query},
+            routing::post,
+            Json,
+        };
+        use std::collections::HashMap;
+
+        async fn json(
+            body: Result<Json<HashMap<String, u32>>, JsonRejection>,
+        ) -> HttpJsonResult<()> {
+            let Json(_) = body.map_err(HttpError::from)?;
+            Ok(())
+        }
+
+        async fn path(id: Result<Path<u32>, PathRejection>) -> HttpJsonResult<()> {
+            // converted through the blanket `From` of `HttpErrorResponse`
+            id?;
+            Ok(())
+        }
+
+        async fn query(
+            query: Result<Query<HashMap<String, u32>>, QueryRejection>,
+        ) -> HttpJsonResult<()> {
+            query?;
+            Ok(())
+        }
+
+        let app = Router::new()
+            .route("/json", post(json))
+            .route("/path/:id", get(path))
+            .route("/query", get(query));
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::post("/json")
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .body(Body::from("{"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .clone()
+            .oneshot(Request::post("/json").body(Body::from("{}")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let resp = app
+            .clone()
+            .oneshot(Request::get("/path/abc").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            r#"{"error":{"message":"Invalid URL: Cannot parse `\"abc\"` to a `u32`"}}"#
+        );
+
+        let resp = app
+            .oneshot(Request::get("/query?a=b").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(all(feature = "multipart", feature = "json"))]
+    #[tokio::test]
+    async fn http_error_from_multipart_error() {
+        use crate::response::HttpJsonResult;
+        use ::axum::{
+            extract::{DefaultBodyLimit, Multipart},
+            routing::post,
+        };
+
+        async fn upload(multipart: Result<Multipart, MultipartRejection>) -> HttpJsonResult<()> {
+            let mut multipart = multipart?;
+            while let Some(field) = multipart.next_field().await? {
+                field.bytes().await?;
+            }
+            Ok(())
+        }
+
+        let app = Router::new()
+            .route("/", post(upload))
+            .layer(DefaultBodyLimit::max(64));
+        let multipart = |body: &'static str| {
+            Request::post("/")
+                .header(
+                    http::header::CONTENT_TYPE,
+                    "multipart/form-data; boundary=X",
+                )
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::post("/")
+                    .header(http::header::CONTENT_TYPE, "multipart/form-data")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .clone()
+            .oneshot(multipart(
+                "--X\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["field"], "a");
+
+        let resp = app
+            .oneshot(multipart(
+                "--X\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\n\
+                 0123456789abcdefghijklmnopqrstuvwxyz0123456789\r\n--X--\r\n",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[cfg(feature = "json")]
+    async fn negotiate(accept: Option<&str>) -> (String, String) {
+        use crate::{
+            http_error,
+            response::{HttpResult, Negotiate},
+        };
+
+        async fn handler() -> HttpResult<(), Negotiate> {
+            Err(http_error!(NOT_FOUND, "user <1> not found"))?
+        }
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(NegotiateLayer::new());
+        let mut req = Request::get("/");
+        if let Some(accept) = accept {
+            req = req.header(ACCEPT, accept);
+        }
+        let resp = app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()[http::header::VARY], "accept");
+        let content_type = resp.headers()[http::header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn negotiate_layer() {
+        let (content_type, body) = negotiate(None).await;
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, r#"{"error":{"message":"user <1> not found"}}"#);
+
+        let (content_type, body) = negotiate(Some("text/html,application/json;q=0.9")).await;
+        assert_eq!(content_type, "text/html");
+        assert!(body.contains("<p>user &lt;1&gt; not found</p>"));
+
+        let (content_type, body) = negotiate(Some("text/plain")).await;
+        assert_eq!(content_type, "text/plain");
+        assert_eq!(body, "404 Not Found: user <1> not found");
+
+        let (content_type, body) = negotiate(Some("application/problem+json")).await;
+        assert_eq!(content_type, "application/problem+json");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "detail": "user <1> not found",
+                "status": 404,
+                "title": "Not Found",
+                "type": "about:blank",
+            })
+        );
+
+        let (content_type, _) = negotiate(Some("image/png, text/*;q=0.5")).await;
+        assert_eq!(content_type, "text/html");
+
+        let (content_type, _) = negotiate(Some("text/*;q=0.5, text/html;q=0")).await;
+        assert_eq!(content_type, "text/plain");
+
+        let (content_type, _) = negotiate(Some("text/html;q=0, */*")).await;
+        assert_eq!(content_type, "application/json");
+
+        let (content_type, _) = negotiate(Some("text/plain, text/html")).await;
+        assert_eq!(content_type, "text/plain");
+
+        let (content_type, _) = negotiate(Some("text/*, application/problem+json")).await;
+        assert_eq!(content_type, "application/problem+json");
+
+        let (content_type, _) = negotiate(Some("image/png")).await;
+        assert_eq!(content_type, "application/json");
+        assert!(current_accept().is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn formatter_config_layer() {
+        use crate::{
+            http_error,
+            response::{HttpJsonResult, HttpResult, Negotiate},
+        };
+        use ::axum::Extension;
+
+        async fn json() -> HttpJsonResult<()> {
+            Err(http_error!(NOT_FOUND, "not found").with_source_err(anyhow::anyhow!("no row")))?
+        }
+
+        async fn problem() -> HttpResult<(), Negotiate> {
+            Err(http_error!(NOT_FOUND, "not found"))?
+        }
+
+        async fn body(app: Router, req: Request<Body>) -> serde_json::Value {
+            let resp = app.oneshot(req).await.unwrap();
+            let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        let config = FormatterConfig::new()
+            .with_include_debug(true)
+            .with_service("billing")
+            .with_problem_type_base("https://example.com/errors/");
+        let app = Router::new()
+            .route("/json", get(json))
+            .route("/problem", get(problem))
+            .layer(FormatterConfigLayer::new(config))
+            .layer(NegotiateLayer::new());
+
+        let resp = body(
+            app.clone(),
+            Request::get("/json").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(
+            resp,
+            serde_json::json!({
+                "error": { "message": "not found", "service": "billing", "debug": ["no row"] }
+            })
+        );
+
+        let resp = body(
+            app.clone(),
+            Request::get("/problem")
+                .header(ACCEPT, "application/problem+json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(resp["type"], "https://example.com/errors/404");
+        assert_eq!(resp["service"], "billing");
+
+        let app = app.layer(Extension(FormatterConfig::new()));
+        let resp = body(app, Request::get("/json").body(Body::empty()).unwrap()).await;
+        assert_eq!(
+            resp,
+            serde_json::json!({ "error": { "message": "not found" } })
+        );
+        assert!(current_formatter_config().is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn formatter_config_state() {
+        use crate::{http_error, response::HttpJsonResult};
+        use ::axum::middleware;
+
+        #[derive(Clone)]
+        struct AppState {
+            service: &'static str,
+        }
+
+        impl FromRef<AppState> for FormatterConfig {
+            fn from_ref(state: &AppState) -> Self {
+                FormatterConfig::new().with_service(state.service)
+            }
+        }
+
+        async fn handler() -> HttpJsonResult<()> {
+            Err(http_error!(NOT_FOUND, "not found"))?
+        }
+
+        let state = AppState { service: "billing" };
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                formatter_config_from_state::<AppState>,
+            ))
+            .with_state(state);
+        let resp = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({ "error": { "message": "not found", "service": "billing" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn formatter_config_layer_panic() {
+        let svc = FormatterConfigLayer::new(FormatterConfig::new()).layer(tower::service_fn(
+            |_: Request<Body>| async {
+                assert!(current_formatter_config().is_some());
+                panic!("handler panicked");
+                #[allow(unreachable_code)]
+                Ok::<_, Infallible>(())
+            },
+        ));
+        let res = tokio::spawn(svc.oneshot(Request::new(Body::empty()))).await;
+        assert!(res.unwrap_err().is_panic());
+        // the test runtime runs the task on this thread, the config must not outlive it
+        assert!(current_formatter_config().is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "tower", feature = "json"))]
+    async fn handle_error_tower() {
+        use crate::{http_error, response::Json};
+        use ::axum::{error_handling::HandleErrorLayer, BoxError};
+        use std::time::Duration;
+        use tower::ServiceBuilder;
+
+        async fn slow() {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        }
+
+        let app = Router::new().route("/", get(slow)).layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(handle_error::<Json>()))
+                .timeout(Duration::from_millis(10)),
+        );
+        let resp = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::REQUEST_TIMEOUT);
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, r#"{"error":{"message":"Request Timeout"}}"#);
+
+        let handler = handle_error::<Json>();
+        let err: BoxError = Box::new(tower::load_shed::error::Overloaded::new());
+        let resp = handler(err).await;
+        assert_eq!(
+            resp.http_error.status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        let err: BoxError = Box::new(http_error!(TOO_MANY_REQUESTS));
+        let resp = handler(err).await;
+        assert_eq!(resp.http_error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        let err: BoxError = "buffer closed".into();
+        let resp = handler(err).await;
+        assert_eq!(
+            resp.http_error.status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn elapsed_layer() {
+        use crate::{http_error, response::HttpJsonResult};
+        use std::time::Duration;
+
+        async fn handler() -> HttpJsonResult<()> {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            Err(http_error!(SERVICE_UNAVAILABLE))?
+        }
+
+        async fn preset() -> HttpJsonResult<()> {
+            Err(http_error!(SERVICE_UNAVAILABLE).with_elapsed(Duration::from_secs(1)))?
+        }
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .route("/preset", get(preset))
+            .route("/ok", get(|| async {}))
+            .layer(ElapsedLayer::new().with_server_timing(true));
+
+        let resp = app
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(resp.headers()["server-timing"]
+            .to_str()
+            .unwrap()
+            .starts_with("handler;dur="));
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"][ELAPSED_DATA_KEY].as_u64().unwrap() >= 5);
+
+        let resp = app
+            .clone()
+            .oneshot(Request::get("/preset").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(resp.headers().get("server-timing").is_none());
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"][ELAPSED_DATA_KEY], 1000);
+
+        let resp = app
+            .oneshot(Request::get("/ok").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(resp.headers().get("server-timing").is_none());
+        assert!(CURRENT_TIMING.try_with(|_| ()).is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn route_error_override_layer() {
+        use crate::{http_error, response::HttpJsonResult, response::Json};
+
+        async fn forbidden() -> HttpJsonResult<()> {
+            Err(http_error!(FORBIDDEN, "admin role required"))?
+        }
+
+        async fn unavailable() -> HttpJsonResult<()> {
+            Err(http_error!(SERVICE_UNAVAILABLE, "db-1 down").with_key_value("retry", true))?
+        }
+
+        let app = Router::new()
+            .route("/admin/users", get(forbidden))
+            .route("/administrator", get(forbidden))
+            .route("/reports", get(unavailable))
+            .route("/admin/ok", get(|| async {}))
+            .layer(RouteErrorOverrideLayer::<Json>::new([
+                RouteErrorOverride::new("/admin/")
+                    .when_status(StatusCode::FORBIDDEN)
+                    .with_status(StatusCode::NOT_FOUND),
+                RouteErrorOverride::new("/reports").with_reason("reports are unavailable"),
+            ]));
+
+        let call = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let resp = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = resp.status();
+                let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        assert_eq!(
+            call("/admin/users").await,
+            (
+                StatusCode::NOT_FOUND,
+                r#"{"error":{"message":"Not Found"}}"#.to_string()
+            )
+        );
+        assert_eq!(call("/administrator").await.0, StatusCode::FORBIDDEN);
+        assert_eq!(call("/admin/ok").await.0, StatusCode::OK);
+        assert_eq!(
+            call("/reports").await,
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                r#"{"error":{"message":"reports are unavailable","retry":true}}"#.to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn request_meta_layer() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let app = Router::new()
+            .route(
+                "/",
+                get({
+                    let captured = captured.clone();
+                    move |meta: RequestMeta| async move {
+                        captured.lock().unwrap().push(meta);
+                    }
+                }),
+            )
+            .layer(RequestMetaLayer::new().with_trusted_proxies([proxy]));
+
+        let request = |peer: &str, forwarded_for: &str| {
+            let mut req = Request::get("/")
+                .header(USER_AGENT, "curl/8.0")
+                .header(X_FORWARDED_FOR, forwarded_for)
+                .header(X_FORWARDED_PROTO, "https")
+                .body(Body::empty())
+                .unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 4000)));
+            req
+        };
+        // the client is the last untrusted hop forwarded by a trusted proxy
+        app.clone()
+            .oneshot(request("10.0.0.1", "203.0.113.7, 198.51.100.2, 10.0.0.1"))
+            .await
+            .unwrap();
+        // forwarding headers of untrusted peers are ignored
+        app.clone()
+            .oneshot(request("192.0.2.9", "203.0.113.7"))
+            .await
+            .unwrap();
+        // without the peer address, the client is unknown
+        app.oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(
+            captured[0].client_ip(),
+            Some("198.51.100.2".parse().unwrap())
+        );
+        assert_eq!(captured[0].user_agent(), Some("curl/8.0"));
+        assert_eq!(captured[0].forwarded_proto(), Some("https"));
+        assert_eq!(captured[1].client_ip(), Some("192.0.2.9".parse().unwrap()));
+        assert_eq!(captured[1].forwarded_proto(), None);
+        assert_eq!(captured[2], RequestMeta::default());
+
+        let e = HttpError::default()
+            .with_request_meta(
+                &captured[0],
+                &[RequestMetaField::ClientIp, RequestMetaField::UserAgent],
+            )
+            .with_request_meta(&captured[2], &[RequestMetaField::ClientIp]);
+        assert_eq!(
+            e.get::<serde_json::Value>(REQUEST_META_DATA_KEY),
+            Some(serde_json::json!({ "client_ip": "198.51.100.2", "user_agent": "curl/8.0" }))
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn compression_layer() {
+        use crate::{http_error, response::HttpJsonResult};
+        use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH};
+        use std::io::Read;
+        use tower_http::compression::CompressionLayer;
+
+        async fn small() -> HttpJsonResult<()> {
+            Err(http_error!(GONE))?
+        }
+
+        async fn large() -> HttpJsonResult<()> {
+            Err(http_error!(BAD_REQUEST, "invalid ids").with_key_value("ids", vec![1; 100]))?
+        }
+
+        let app = Router::new()
+            .route("/small", get(small))
+            .route("/large", get(large))
+            .layer(CompressionLayer::new());
+        let request = |uri| {
+            Request::get(uri)
+                .header(ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(request("/small")).await.unwrap();
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        let content_length = resp.headers()[CONTENT_LENGTH].clone();
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(content_length, body.len().to_string());
+        assert_eq!(body, r#"{"error":{"message":"Gone"}}"#);
+
+        let resp = app.clone().oneshot(request("/large")).await.unwrap();
+        assert_eq!(resp.headers()[CONTENT_ENCODING], "gzip");
+        assert!(resp.headers().get(CONTENT_LENGTH).is_none());
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        let decoded: serde_json::Value = serde_json::from_str(&decoded).unwrap();
+        assert_eq!(decoded["error"]["message"], "invalid ids");
+
+        let config = FormatterConfig::new().with_omit_content_length(true);
+        let resp = CURRENT_CONFIG.sync_scope(Arc::new(config), || {
+            ::axum::response::IntoResponse::into_response(http_error!(GONE))
+        });
+        assert!(resp.headers().get(CONTENT_LENGTH).is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn sse_error_stream() {
+        use crate::{http_error, response::Json};
+        use ::axum::response::Sse;
+        use futures_util::stream;
+
+        let app = Router::new().route(
+            "/events",
+            get(|| async {
+                let events = stream::iter([
+                    Ok(Event::default().data("1")),
+                    Err(http_error!(SERVICE_UNAVAILABLE, "upstream closed")),
+                    Ok(Event::default().data("2")),
+                ]);
+                Sse::new(SseErrorStream::<_, Json>::new(events))
+            }),
+        );
+
+        let resp = app
+            .oneshot(Request::get("/events").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = ::axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            "data: 1\n\nevent: error\ndata: {\"error\":{\"message\":\"upstream closed\"}}\n\n"
+        );
+    }
+
+    #[test]
+    fn http_error_to_ws_close() {
+        use crate::http_error;
+
+        for (http_error, code) in [
+            (http_error!(BAD_REQUEST), 1007),
+            (http_error!(PAYLOAD_TOO_LARGE), 1009),
+            (http_error!(UNSUPPORTED_MEDIA_TYPE), 1003),
+            (http_error!(UNAUTHORIZED), 1008),
+            (http_error!(NOT_FOUND), 1008),
+            (http_error!(GATEWAY_TIMEOUT), 1014),
+            (http_error!(SERVICE_UNAVAILABLE), 1013),
+            (http_error!(INTERNAL_SERVER_ERROR), 1011),
+        ] {
+            assert_eq!(http_error.ws_close_code(), code);
+            assert_eq!(http_error.to_ws_close().code(), code);
+        }
+
+        let close = http_error!(NOT_FOUND).to_ws_close();
+        assert_eq!(close.reason(), "Not Found");
+
+        let close = http_error!(BAD_REQUEST, "{}", "ä".repeat(100)).to_ws_close();
+        assert_eq!(close.reason(), "ä".repeat(60) + "…");
+    }
+}
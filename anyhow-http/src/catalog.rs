@@ -0,0 +1,85 @@
+use http::StatusCode;
+
+use crate::HttpError;
+
+/// A statically declared error, e.g. an entry of a catalog declared with
+/// [`http_error_catalog!`](crate::http_error_catalog) or of the `catalog()` generated by the
+/// derive macro. Unlike [`HttpError`], a descriptor can be a `const`, as it holds no
+/// heap-allocated data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorDescriptor {
+    /// The name of the declared error.
+    pub name: &'static str,
+    /// The status code of the error.
+    pub status_code: StatusCode,
+    /// The error reason if any. Reasons of derived errors are the templates as declared, e.g.
+    /// `order {0} not found`.
+    pub reason: Option<&'static str>,
+    /// The error code, emitted as `code` data.
+    pub code: Option<ErrorCode>,
+    /// The keys of the data the error carries, including `code`.
+    pub data_keys: &'static [&'static str],
+}
+
+/// The code of an [`ErrorDescriptor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A numeric code, e.g. `1001`.
+    Numeric(i64),
+    /// A symbolic code, e.g. `BILLING_PAYMENT_REQUIRED`.
+    Symbolic(&'static str),
+}
+
+impl ErrorDescriptor {
+    /// Creates a [`HttpError`] from the descriptor.
+    pub fn to_http_error(&self) -> HttpError {
+        let http_error = HttpError::from_status_code(self.status_code);
+        let http_error = match self.reason {
+            Some(reason) => http_error.with_reason(reason),
+            None => http_error,
+        };
+        match self.code {
+            Some(ErrorCode::Numeric(code)) => http_error.with_key_value("code", code),
+            Some(ErrorCode::Symbolic(code)) => http_error.with_key_value("code", code),
+            None => http_error,
+        }
+    }
+}
+
+impl From<ErrorDescriptor> for HttpError {
+    fn from(descriptor: ErrorDescriptor) -> Self {
+        descriptor.to_http_error()
+    }
+}
+
+impl From<ErrorDescriptor> for anyhow::Error {
+    fn from(descriptor: ErrorDescriptor) -> Self {
+        descriptor.to_http_error().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_descriptor_to_http_error() {
+        let descriptor = ErrorDescriptor {
+            name: "USER_NOT_FOUND",
+            status_code: StatusCode::NOT_FOUND,
+            reason: Some("user not found"),
+            code: Some(ErrorCode::Numeric(1001)),
+            data_keys: &["code"],
+        };
+        let e = HttpError::from(descriptor);
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.reason(), Some("user not found".into()));
+        assert_eq!(e.get::<i64>("code"), Some(1001));
+
+        let e = HttpError::from(ErrorDescriptor {
+            code: Some(ErrorCode::Symbolic("USER_NOT_FOUND")),
+            ..descriptor
+        });
+        assert_eq!(e.get::<String>("code").as_deref(), Some("USER_NOT_FOUND"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Classification of well-known error types into status codes, for errors that reach the
+//! application as trait objects, e.g. from tower middleware.
+use http::StatusCode;
+use std::{error::Error as StdError, io};
+
+use crate::HttpError;
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Returns the status code for an [`io::ErrorKind`]: `NotFound` maps to `404 Not Found`,
+/// `PermissionDenied` to `403 Forbidden`, `TimedOut` to `504 Gateway Timeout` and all other
+/// kinds to `500 Internal Server Error`.
+pub fn io_error_kind_status(kind: io::ErrorKind) -> StatusCode {
+    match kind {
+        io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        io::ErrorKind::TimedOut => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Returns the status code for a well-known error type, or `None` if the error is not known.
+///
+/// With feature `tower`, timeouts of tower's `Timeout` map to `408 Request Timeout` and requests
+/// rejected by `LoadShed` map to `503 Service Unavailable`. [`io::Error`]s are classified with
+/// [`io_error_kind_status`].
+pub fn error_status(err: &(dyn StdError + 'static)) -> Option<StatusCode> {
+    #[cfg(feature = "tower")]
+    {
+        if err.is::<tower::timeout::error::Elapsed>() {
+            return Some(StatusCode::REQUEST_TIMEOUT);
+        }
+        if err.is::<tower::load_shed::error::Overloaded>() {
+            return Some(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    }
+    err.downcast_ref::<io::Error>()
+        .map(|err| io_error_kind_status(err.kind()))
+}
+
+impl HttpError {
+    /// Creates a [`HttpError`] from a boxed error, e.g. the `BoxError` of a tower middleware. A
+    /// boxed [`HttpError`] is returned as is. Otherwise the error and its sources are classified
+    /// with [`error_status`], falling back to `500 Internal Server Error`, and the error becomes
+    /// the source.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    /// use std::io;
+    ///
+    /// let err = HttpError::classify_box_error(Box::new(io::Error::from(io::ErrorKind::NotFound)));
+    /// assert_eq!(err.status_code(), 404);
+    /// ```
+    pub fn classify_box_error(err: BoxError) -> Self {
+        let err = match err.downcast::<HttpError>() {
+            Ok(http_error) => return *http_error,
+            Err(err) => err,
+        };
+        let status_code =
+            std::iter::successors(Some(&*err as &(dyn StdError + 'static)), |e| (*e).source())
+                .find_map(error_status)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        HttpError::from_status_code(status_code).with_boxed_source_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+
+    #[test]
+    fn classify_box_error() {
+        let e = HttpError::classify_box_error(Box::new(http_error!(CONFLICT, "exists")));
+        assert_eq!(e.status_code(), StatusCode::CONFLICT);
+        assert_eq!(e.reason().as_deref(), Some("exists"));
+
+        let e = HttpError::classify_box_error(Box::new(io::Error::from(
+            io::ErrorKind::PermissionDenied,
+        )));
+        assert_eq!(e.status_code(), StatusCode::FORBIDDEN);
+        assert!(e.source().is_some());
+
+        let err: BoxError = anyhow::Error::new(io::Error::from(io::ErrorKind::TimedOut))
+            .context("upstream call")
+            .into();
+        let e = HttpError::classify_box_error(err);
+        assert_eq!(e.status_code(), StatusCode::GATEWAY_TIMEOUT);
+
+        let e = HttpError::classify_box_error("unknown".into());
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    #[cfg(feature = "tower")]
+    fn classify_tower_errors() {
+        let err = tower::load_shed::error::Overloaded::new();
+        assert_eq!(error_status(&err), Some(StatusCode::SERVICE_UNAVAILABLE));
+        let e = HttpError::classify_box_error(Box::new(err));
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
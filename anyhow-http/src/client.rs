@@ -0,0 +1,130 @@
+//! Mapping responses of upstream services into [`HttpError`], for gateways and services that
+//! call other HTTP APIs.
+//!
+//! ```
+//! use anyhow_http::{client::ensure_success, HttpError};
+//! use bytes::Bytes;
+//!
+//! fn fetch_user() -> http::Response<Bytes> {
+//!     http::Response::builder()
+//!         .status(404)
+//!         .body(Bytes::from_static(br#"{"error":{"message":"user not found"}}"#))
+//!         .unwrap()
+//! }
+//!
+//! fn handler() -> Result<Bytes, HttpError> {
+//!     let resp = ensure_success(fetch_user())?;
+//!     Ok(resp.into_body())
+//! }
+//!
+//! let err = handler().unwrap_err();
+//! assert_eq!(err.status_code(), 404);
+//! assert_eq!(err.reason().as_deref(), Some("user not found"));
+//! assert_eq!(err.get::<u16>("upstream_status"), Some(404));
+//! ```
+use http::StatusCode;
+
+use crate::HttpError;
+
+/// Classifies an upstream response. Returns `None` for `2xx` responses.
+///
+/// Client and server errors preserve the upstream status, all other statuses map to
+/// `502 Bad Gateway`. The body is read as an error envelope of the
+/// [`Json`](crate::response::Json) formatter, see [`HttpError::from_json_envelope`], and the
+/// upstream status is added as `upstream_status` data. Headers of the upstream response are not
+/// copied.
+pub fn classify<B: AsRef<[u8]>>(resp: &http::Response<B>) -> Option<HttpError> {
+    let status_code = resp.status();
+    (!status_code.is_success()).then(|| upstream_error(status_code, resp.body().as_ref()))
+}
+
+/// Returns the response if its status is `2xx` and the [classified](classify) [`HttpError`]
+/// otherwise.
+pub fn ensure_success<B: AsRef<[u8]>>(
+    resp: http::Response<B>,
+) -> Result<http::Response<B>, HttpError> {
+    match classify(&resp) {
+        Some(http_error) => Err(http_error),
+        None => Ok(resp),
+    }
+}
+
+/// Returns the [`reqwest::Response`] if its status is `2xx`. Otherwise the body is read and
+/// [classified](classify), and the upstream url is added as `upstream_url` data.
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+pub async fn ensure_success_reqwest(
+    resp: reqwest::Response,
+) -> Result<reqwest::Response, HttpError> {
+    let status_code = resp.status();
+    if status_code.is_success() {
+        return Ok(resp);
+    }
+    let url = resp.url().to_string();
+    let body = resp.bytes().await.unwrap_or_default();
+    Err(upstream_error(status_code, &body).with_key_value("upstream_url", url))
+}
+
+fn upstream_error(status_code: StatusCode, body: &[u8]) -> HttpError {
+    let mut http_error = HttpError::from_json_envelope(status_code, body);
+    if !status_code.is_client_error() && !status_code.is_server_error() {
+        http_error.set_status_code(StatusCode::BAD_GATEWAY);
+    }
+    http_error.with_key_value("upstream_status", status_code.as_u16())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    fn upstream_response(status: u16, body: &'static str) -> http::Response<Bytes> {
+        http::Response::builder()
+            .status(status)
+            .header("x-upstream", "1")
+            .body(Bytes::from_static(body.as_bytes()))
+            .unwrap()
+    }
+
+    #[test]
+    fn classify_upstream_response() {
+        assert!(classify(&upstream_response(200, "ok")).is_none());
+        assert!(ensure_success(upstream_response(204, "")).is_ok());
+
+        let e = classify(&upstream_response(
+            409,
+            r#"{"error":{"message":"order exists","order":7}}"#,
+        ))
+        .unwrap();
+        assert_eq!(e.status_code(), StatusCode::CONFLICT);
+        assert_eq!(e.reason().as_deref(), Some("order exists"));
+        assert_eq!(e.get::<u64>("order"), Some(7));
+        assert_eq!(e.get::<u16>("upstream_status"), Some(409));
+        assert!(e.headers().is_none());
+
+        let e = ensure_success(upstream_response(503, "unavailable")).unwrap_err();
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(e.reason().is_none());
+
+        let e = classify(&upstream_response(302, "")).unwrap();
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(e.get::<u16>("upstream_status"), Some(302));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn ensure_success_reqwest_response() {
+        let resp = reqwest::Response::from(upstream_response(
+            404,
+            r#"{"error":{"message":"user not found"}}"#,
+        ));
+        let e = ensure_success_reqwest(resp).await.unwrap_err();
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.reason().as_deref(), Some("user not found"));
+        assert!(e.get::<String>("upstream_url").is_some());
+
+        let resp = reqwest::Response::from(upstream_response(200, "ok"));
+        assert!(ensure_success_reqwest(resp).await.is_ok());
+    }
+}
@@ -0,0 +1,71 @@
+use cookie::Cookie;
+use http::header::SET_COOKIE;
+use std::borrow::Cow;
+
+use crate::HttpError;
+
+impl HttpError {
+    /// Appends a `Set-Cookie` header for `cookie`, which is anything that converts into a
+    /// [`Cookie`], e.g. a `(name, value)` pair or a [`CookieBuilder`](cookie::CookieBuilder).
+    /// Previous cookies are kept, see [`append_header`](Self::append_header).
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    /// use cookie::Cookie;
+    ///
+    /// let err = http_error!(UNAUTHORIZED)
+    ///     .with_set_cookie(("lang", "de"))
+    ///     .with_set_cookie(Cookie::build(("retry", "1")).path("/login").http_only(true));
+    /// let cookies: Vec<_> = err.headers().unwrap().get_all("set-cookie").iter().collect();
+    /// assert_eq!(cookies, ["lang=de", "retry=1; HttpOnly; Path=/login"]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookie")))]
+    pub fn with_set_cookie<'c, C: Into<Cookie<'c>>>(self, cookie: C) -> Self {
+        self.append_header(SET_COOKIE, cookie.into().to_string())
+    }
+
+    /// Appends a `Set-Cookie` header that expires the cookie `name` with path `/`, e.g. to end
+    /// the session on a `401 Unauthorized` response. Cookies with another path or domain are only
+    /// removed by a cookie with the same attributes, which can be passed to
+    /// [`with_set_cookie`](Self::with_set_cookie) after [`Cookie::make_removal`].
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let err = http_error!(UNAUTHORIZED).with_clear_cookie("session");
+    /// let cookie = err.headers().unwrap()["set-cookie"].to_str().unwrap();
+    /// assert!(cookie.starts_with("session=; Path=/; Max-Age=0; Expires="));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "cookie")))]
+    pub fn with_clear_cookie<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        let mut cookie = Cookie::build((name, "")).path("/").build();
+        cookie.make_removal();
+        self.with_set_cookie(cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+
+    #[test]
+    fn http_error_with_cookies() {
+        let err = http_error!(UNAUTHORIZED)
+            .with_set_cookie(Cookie::build(("session", "abc")).secure(true))
+            .with_clear_cookie("session".to_string());
+        let cookies: Vec<_> = err
+            .headers()
+            .unwrap()
+            .get_all(SET_COOKIE)
+            .iter()
+            .map(|v| Cookie::parse(v.to_str().unwrap().to_owned()).unwrap())
+            .collect();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name_value(), ("session", "abc"));
+        assert_eq!(cookies[0].secure(), Some(true));
+        assert_eq!(cookies[1].name_value(), ("session", ""));
+        assert_eq!(cookies[1].path(), Some("/"));
+        assert_eq!(cookies[1].max_age(), Some(cookie::time::Duration::ZERO));
+    }
+}
@@ -0,0 +1,335 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bytes::Bytes;
+use core::fmt;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::borrow::Cow;
+
+#[cfg(not(feature = "preserve-order"))]
+type Map = std::collections::BTreeMap<String, DataValue>;
+#[cfg(feature = "preserve-order")]
+type Map = indexmap::IndexMap<String, DataValue>;
+
+/// The encoding marker of serialized [`DataValue::Binary`].
+const BINARY_ENCODING: &str = "base64";
+
+/// A value in a [`DataMap`].
+///
+/// ```
+/// use anyhow_http::DataValue;
+///
+/// let value = DataValue::from(serde_json::json!({ "id": 1 }));
+/// assert_eq!(value.as_json().unwrap()["id"], 1);
+/// assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"id":1}"#);
+/// ```
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum DataValue {
+    /// A Json value.
+    Json(serde_json::Value),
+    /// Binary data, which is serialized base64-encoded as `{"encoding": "base64", "data": ".."}`,
+    /// see [`HttpError::with_binary_data`](crate::HttpError::with_binary_data).
+    Binary(Bytes),
+    /// Pre-serialized Json, which is written to responses as is, see
+    /// [`HttpError::with_raw_json`](crate::HttpError::with_raw_json).
+    #[cfg(feature = "raw-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-json")))]
+    RawJson(Box<serde_json::value::RawValue>),
+}
+
+impl DataValue {
+    /// Returns the Json value, if this is one.
+    pub fn as_json(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::Json(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the binary data, if this is any.
+    pub fn as_binary(&self) -> Option<&Bytes> {
+        match self {
+            Self::Binary(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the pre-serialized Json, if this is any.
+    #[cfg(feature = "raw-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-json")))]
+    pub fn as_raw_json(&self) -> Option<&serde_json::value::RawValue> {
+        match self {
+            Self::RawJson(raw) => Some(raw),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as Json. Binary data is base64-encoded and pre-serialized Json is
+    /// parsed, which yields `null` if it is malformed.
+    pub fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        match self {
+            Self::Json(value) => Cow::Borrowed(value),
+            Self::Binary(bytes) => Cow::Owned(serde_json::json!({
+                "encoding": BINARY_ENCODING,
+                "data": BASE64.encode(bytes),
+            })),
+            #[cfg(feature = "raw-json")]
+            Self::RawJson(raw) => Cow::Owned(serde_json::from_str(raw.get()).unwrap_or_default()),
+        }
+    }
+
+    /// Deserializes the value into `V`.
+    pub(crate) fn deserialize<V: serde::de::DeserializeOwned>(&self) -> Option<V> {
+        match self {
+            Self::Json(value) => V::deserialize(value).ok(),
+            Self::Binary(_) => V::deserialize(self.to_json().as_ref()).ok(),
+            #[cfg(feature = "raw-json")]
+            Self::RawJson(raw) => serde_json::from_str(raw.get()).ok(),
+        }
+    }
+}
+
+impl fmt::Debug for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(value) => value.fmt(f),
+            Self::Binary(bytes) => bytes.fmt(f),
+            #[cfg(feature = "raw-json")]
+            Self::RawJson(raw) => raw.fmt(f),
+        }
+    }
+}
+
+impl PartialEq for DataValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Json(a), Self::Json(b)) => a == b,
+            (Self::Binary(a), Self::Binary(b)) => a == b,
+            #[cfg(feature = "raw-json")]
+            (Self::RawJson(a), Self::RawJson(b)) => a.get() == b.get(),
+            _ => false,
+        }
+    }
+}
+
+/// Compares Json values, e.g. `data["id"] == 1`.
+impl<T> PartialEq<T> for DataValue
+where
+    serde_json::Value: PartialEq<T>,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.as_json().is_some_and(|value| value == other)
+    }
+}
+
+impl From<serde_json::Value> for DataValue {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<Bytes> for DataValue {
+    fn from(bytes: Bytes) -> Self {
+        Self::Binary(bytes)
+    }
+}
+
+#[cfg(feature = "raw-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "raw-json")))]
+impl From<Box<serde_json::value::RawValue>> for DataValue {
+    fn from(raw: Box<serde_json::value::RawValue>) -> Self {
+        Self::RawJson(raw)
+    }
+}
+
+impl Serialize for DataValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Json(value) => value.serialize(serializer),
+            Self::Binary(_) => self.to_json().serialize(serializer),
+            #[cfg(feature = "raw-json")]
+            Self::RawJson(raw) => raw.serialize(serializer),
+        }
+    }
+}
+
+/// The map that stores the data of a [`HttpError`](crate::HttpError). Keys are sorted, or kept
+/// in insertion order with feature `preserve-order`. The API is the same either way, so enabling
+/// the feature in one crate of a dependency graph doesn't break another.
+///
+/// ```
+/// use anyhow_http::DataMap;
+///
+/// let mut data = DataMap::new();
+/// data.insert("id", serde_json::json!(1));
+/// assert_eq!(data["id"], 1);
+/// assert_eq!(data.remove("id"), Some(serde_json::Value::from(1).into()));
+/// assert!(data.is_empty());
+/// ```
+#[derive(Clone, Default, PartialEq)]
+pub struct DataMap(Map);
+
+impl DataMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&DataValue> {
+        self.0.get(key)
+    }
+
+    /// Returns whether the map contains `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// Inserts a value, returning the previous value of `key`. A replaced key keeps its position
+    /// with feature `preserve-order`.
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Option<DataValue>
+    where
+        K: Into<String>,
+        V: Into<DataValue>,
+    {
+        self.0.insert(key.into(), value.into())
+    }
+
+    /// Removes the value of `key`, keeping the order of the remaining keys.
+    pub fn remove(&mut self, key: &str) -> Option<DataValue> {
+        #[cfg(not(feature = "preserve-order"))]
+        return self.0.remove(key);
+        #[cfg(feature = "preserve-order")]
+        return self.0.shift_remove(key);
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the keys.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Returns an iterator over the entries.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DataValue)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Returns the entries as Json object, e.g. to embed them into a custom response. See
+    /// [`DataValue::to_json`].
+    pub fn to_json_map(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.0
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_json().into_owned()))
+            .collect()
+    }
+}
+
+impl fmt::Debug for DataMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl core::ops::Index<&str> for DataMap {
+    type Output = DataValue;
+
+    /// Returns the value of `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map doesn't contain `key`.
+    fn index(&self, key: &str) -> &DataValue {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no data for key `{key}`"))
+    }
+}
+
+impl<K: Into<String>, V: Into<DataValue>> FromIterator<(K, V)> for DataMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut data = Self::new();
+        data.extend(iter);
+        data
+    }
+}
+
+impl<K: Into<String>, V: Into<DataValue>> Extend<(K, V)> for DataMap {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl Serialize for DataMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_map_order() {
+        let data: DataMap = [("b", 1), ("a", 2), ("c", 3)]
+            .map(|(k, v)| (k, serde_json::Value::from(v)))
+            .into_iter()
+            .collect();
+        let keys: Vec<_> = data.keys().collect();
+        #[cfg(not(feature = "preserve-order"))]
+        assert_eq!(keys, ["a", "b", "c"]);
+        #[cfg(feature = "preserve-order")]
+        assert_eq!(keys, ["b", "a", "c"]);
+
+        let mut data = data;
+        data.remove("a");
+        assert_eq!(data.keys().count(), 2);
+        assert!(!data.contains_key("a"));
+    }
+
+    #[test]
+    fn data_map_serialize() {
+        let data: DataMap = [("id", serde_json::Value::from(1))].into_iter().collect();
+        assert_eq!(serde_json::to_string(&data).unwrap(), r#"{"id":1}"#);
+    }
+
+    #[test]
+    fn data_map_serialize_binary() {
+        let mut data = DataMap::new();
+        data.insert("body", Bytes::from_static(b"\xff\x00"));
+        assert_eq!(
+            serde_json::to_value(&data).unwrap(),
+            serde_json::json!({ "body": { "encoding": "base64", "data": "/wA=" } })
+        );
+        assert_eq!(
+            data["body"].as_binary(),
+            Some(&Bytes::from_static(b"\xff\x00"))
+        );
+        assert!(data["body"].as_json().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "raw-json")]
+    fn data_map_serialize_raw_json() {
+        let raw = serde_json::value::RawValue::from_string("[1, 2]".to_string()).unwrap();
+        let mut data = DataMap::new();
+        data.insert("ids", raw);
+        // raw Json is written through as is, including its whitespace
+        assert_eq!(serde_json::to_string(&data).unwrap(), r#"{"ids":[1, 2]}"#);
+        assert_eq!(data["ids"].to_json().as_ref(), &serde_json::json!([1, 2]));
+        assert_eq!(data["ids"].as_raw_json().unwrap().get(), "[1, 2]");
+    }
+}
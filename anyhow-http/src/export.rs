@@ -0,0 +1,191 @@
+//! Export of the declared errors of an application as machine-readable contract, e.g. for
+//! frontend teams that handle error responses.
+use serde::{Serialize, Serializer};
+use std::{io, path::Path};
+
+use crate::{ErrorCode, ErrorDescriptor};
+
+/// A Json document that lists declared errors, collected from catalogs declared with
+/// [`http_error_catalog!`](crate::http_error_catalog) and the `catalog()` of derived errors.
+/// Each error is listed with its status, code, reason template and data keys.
+///
+/// ```
+/// use anyhow_http::{export::ErrorContract, http_error_catalog};
+///
+/// http_error_catalog! {
+///     pub mod errors {
+///         USER_NOT_FOUND => (NOT_FOUND, "user not found", code = 1001),
+///     }
+/// }
+///
+/// let contract = ErrorContract::new().with_descriptors(errors::ALL);
+/// assert_eq!(
+///     contract.to_json(),
+///     serde_json::json!({
+///         "errors": [{
+///             "name": "USER_NOT_FOUND",
+///             "status": 404,
+///             "title": "Not Found",
+///             "reason": "user not found",
+///             "code": 1001,
+///             "data_keys": ["code"],
+///         }]
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContract {
+    errors: Vec<ErrorDescriptor>,
+}
+
+impl ErrorContract {
+    /// Creates an empty contract.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the given errors, skipping errors whose name is listed already.
+    pub fn with_descriptors<'a, I>(mut self, descriptors: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ErrorDescriptor>,
+    {
+        for descriptor in descriptors {
+            if !self.errors.iter().any(|e| e.name == descriptor.name) {
+                self.errors.push(*descriptor);
+            }
+        }
+        self
+    }
+
+    /// Returns the listed errors.
+    pub fn descriptors(&self) -> &[ErrorDescriptor] {
+        &self.errors
+    }
+
+    /// Returns the contract as Json document.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Writes the contract as pretty-printed Json document to `path`, e.g. at startup or from a
+    /// test that keeps a checked-in contract up to date.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        std::fs::write(path, json)
+    }
+}
+
+impl Serialize for ErrorContract {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Contract<'a> {
+            errors: Vec<Entry<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct Entry<'a> {
+            name: &'a str,
+            status: u16,
+            title: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            reason: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            code: Option<Code<'a>>,
+            data_keys: &'a [&'a str],
+        }
+
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Code<'a> {
+            Numeric(i64),
+            Symbolic(&'a str),
+        }
+
+        Contract {
+            errors: self
+                .errors
+                .iter()
+                .map(|e| Entry {
+                    name: e.name,
+                    status: e.status_code.as_u16(),
+                    title: e.status_code.canonical_reason(),
+                    reason: e.reason,
+                    code: e.code.map(|code| match code {
+                        ErrorCode::Numeric(code) => Code::Numeric(code),
+                        ErrorCode::Symbolic(code) => Code::Symbolic(code),
+                    }),
+                    data_keys: e.data_keys,
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    const CONFLICT: ErrorDescriptor = ErrorDescriptor {
+        name: "OrderError::Conflict",
+        status_code: StatusCode::CONFLICT,
+        reason: Some("order {0} exists"),
+        code: Some(ErrorCode::Symbolic("ORDER_CONFLICT")),
+        data_keys: &["code", "order"],
+    };
+
+    const LOCKED: ErrorDescriptor = ErrorDescriptor {
+        name: "OrderError::Locked",
+        status_code: StatusCode::LOCKED,
+        reason: None,
+        code: None,
+        data_keys: &[],
+    };
+
+    #[test]
+    fn error_contract_to_json() {
+        let contract = ErrorContract::new()
+            .with_descriptors(&[CONFLICT, LOCKED])
+            .with_descriptors(&[CONFLICT]);
+        assert_eq!(contract.descriptors(), [CONFLICT, LOCKED]);
+        assert_eq!(
+            contract.to_json(),
+            serde_json::json!({
+                "errors": [
+                    {
+                        "name": "OrderError::Conflict",
+                        "status": 409,
+                        "title": "Conflict",
+                        "reason": "order {0} exists",
+                        "code": "ORDER_CONFLICT",
+                        "data_keys": ["code", "order"],
+                    },
+                    {
+                        "name": "OrderError::Locked",
+                        "status": 423,
+                        "title": "Locked",
+                        "data_keys": [],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn error_contract_write() {
+        let path = std::env::temp_dir().join(format!("error-contract-{}.json", std::process::id()));
+        ErrorContract::new()
+            .with_descriptors(&[LOCKED])
+            .write(&path)
+            .unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written.ends_with("}\n"));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            ErrorContract::new().with_descriptors(&[LOCKED]).to_json()
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use http::StatusCode;
-use std::{borrow::Cow, result::Result as StdResult};
+use std::{borrow::Cow, fmt::Display, result::Result as StdResult};
 
 use crate::HttpError;
 
@@ -44,6 +44,60 @@ pub trait ResultExt {
     ) -> StdResult<Self::Item, HttpError>
     where
         S: Into<Cow<'static, str>>;
+
+    /// Shorthand for [`map_status(StatusCode::NOT_FOUND)`](Self::map_status).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError, ResultExt};
+    ///
+    /// let user: Result<(), HttpError> = Err(anyhow::anyhow!("no rows")).map_not_found();
+    /// assert_eq!(user, Err(http_error!(NOT_FOUND)));
+    /// ```
+    fn map_not_found(self) -> StdResult<Self::Item, HttpError>
+    where
+        Self: Sized,
+    {
+        self.map_status(StatusCode::NOT_FOUND)
+    }
+
+    /// Shorthand for [`map_http_error(StatusCode::BAD_REQUEST, reason)`](Self::map_http_error).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError, ResultExt};
+    ///
+    /// let s: Result<i32, HttpError> = "nan".parse::<i32>().map_bad_request("invalid number");
+    /// assert_eq!(s, Err(http_error!(BAD_REQUEST, "invalid number")));
+    /// ```
+    fn map_bad_request<S>(self, reason: S) -> StdResult<Self::Item, HttpError>
+    where
+        Self: Sized,
+        S: Into<Cow<'static, str>>,
+    {
+        self.map_http_error(StatusCode::BAD_REQUEST, reason)
+    }
+
+    /// Shorthand for [`map_http_error(StatusCode::CONFLICT, reason)`](Self::map_http_error).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError, ResultExt};
+    ///
+    /// let order: Result<(), HttpError> =
+    ///     Err(anyhow::anyhow!("duplicate key")).map_conflict("order exists");
+    /// assert_eq!(order, Err(http_error!(CONFLICT, "order exists")));
+    /// ```
+    fn map_conflict<S>(self, reason: S) -> StdResult<Self::Item, HttpError>
+    where
+        Self: Sized,
+        S: Into<Cow<'static, str>>,
+    {
+        self.map_http_error(StatusCode::CONFLICT, reason)
+    }
 }
 
 impl<E, T> ResultExt for StdResult<T, E>
@@ -93,6 +147,45 @@ pub trait OptionExt {
     /// assert_eq!(x, Err(http_error!(BAD_REQUEST)));
     /// ```
     fn ok_or_status(self, status_code: StatusCode) -> StdResult<Self::Item, HttpError>;
+
+    /// Transforms the `Option<T>` into a `Result<T, HttpError<R>>`, mapping `Some(v)` to
+    /// `Ok(v)` and `None` to `Err(HttpError)` with status code and reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http::StatusCode;
+    /// use anyhow_http::{http_error, HttpError, OptionExt};
+    ///
+    /// let x: Result<_, HttpError> =
+    ///     None::<()>.ok_or_http_error(StatusCode::NOT_FOUND, "user not found");
+    /// assert_eq!(x, Err(http_error!(NOT_FOUND, "user not found")));
+    /// ```
+    fn ok_or_http_error<S>(
+        self,
+        status_code: StatusCode,
+        reason: S,
+    ) -> StdResult<Self::Item, HttpError>
+    where
+        S: Into<Cow<'static, str>>;
+
+    /// Transforms the `Option<T>` into a `Result<T, HttpError<R>>`, mapping `Some(v)` to
+    /// `Ok(v)` and `None` to `Err(HttpError)` computed by the closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError, OptionExt};
+    ///
+    /// let id = 1234;
+    /// let x: Result<(), HttpError> = None.ok_or_else_http_error(|| {
+    ///     http_error!(NOT_FOUND, "user not found").with_key_value("id", id)
+    /// });
+    /// assert_eq!(x.unwrap_err().get::<i32>("id"), Some(1234));
+    /// ```
+    fn ok_or_else_http_error<F>(self, f: F) -> StdResult<Self::Item, HttpError>
+    where
+        F: FnOnce() -> HttpError;
 }
 
 impl<T> OptionExt for std::option::Option<T> {
@@ -104,6 +197,106 @@ impl<T> OptionExt for std::option::Option<T> {
             None => Err(HttpError::from_status_code(status_code)),
         }
     }
+
+    fn ok_or_http_error<S>(self, status_code: StatusCode, reason: S) -> StdResult<T, HttpError>
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        match self {
+            Some(v) => Ok(v),
+            None => Err(HttpError::from_status_code(status_code).with_reason(reason)),
+        }
+    }
+
+    fn ok_or_else_http_error<F>(self, f: F) -> StdResult<T, HttpError>
+    where
+        F: FnOnce() -> HttpError,
+    {
+        match self {
+            Some(v) => Ok(v),
+            None => Err(f()),
+        }
+    }
+}
+
+/// Extension trait to attach a status code and [`anyhow::Context`]-style context in one call,
+/// implemented for [`Result`] and [`Option`].
+///
+/// The context is added to the source error of the resulting [`HttpError`], so it shows up in
+/// logs but not in the response.
+///
+/// # Example
+///
+/// ```
+/// use http::StatusCode;
+/// use anyhow_http::{HttpContext, HttpError};
+///
+/// fn load(id: u64) -> Result<String, HttpError> {
+///     let raw = std::fs::read_to_string(format!("/users/{id}"))
+///         .with_http_context(StatusCode::NOT_FOUND, || format!("failed to read user {id}"))?;
+///     raw.lines()
+///         .next()
+///         .map(String::from)
+///         .http_context(StatusCode::UNPROCESSABLE_ENTITY, "empty user file")
+/// }
+///
+/// let err = load(1234).unwrap_err();
+/// assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+/// assert_eq!(err.source().unwrap().to_string(), "failed to read user 1234");
+/// ```
+pub trait HttpContext<T> {
+    /// Maps the error or `None` to a [`HttpError`] with the given status code and context.
+    fn http_context<C>(self, status_code: StatusCode, context: C) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static;
+
+    /// Maps the error or `None` to a [`HttpError`] with the given status code and context that
+    /// is evaluated lazily.
+    fn with_http_context<C, F>(self, status_code: StatusCode, f: F) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+impl<T, E> HttpContext<T> for StdResult<T, E>
+where
+    E: Into<anyhow::Error> + Send + Sync + 'static,
+{
+    fn http_context<C>(self, status_code: StatusCode, context: C) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.with_http_context(status_code, || context)
+    }
+
+    fn with_http_context<C, F>(self, status_code: StatusCode, f: F) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|e| {
+            HttpError::from_err(e)
+                .with_status_code(status_code)
+                .with_source_context(f())
+        })
+    }
+}
+
+impl<T> HttpContext<T> for Option<T> {
+    fn http_context<C>(self, status_code: StatusCode, context: C) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.with_http_context(status_code, || context)
+    }
+
+    fn with_http_context<C, F>(self, status_code: StatusCode, f: F) -> StdResult<T, HttpError>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.ok_or_else(|| HttpError::from_status_code(status_code).with_source_context(f()))
+    }
 }
 
 #[cfg(test)]
@@ -137,6 +330,26 @@ mod tests {
         assert_eq!(e.reason, Some("invalid request".into()));
     }
 
+    #[test]
+    fn http_err_ext_result_shorthands() {
+        let result: StdResult<(), _> = Err(anyhow!("error"));
+        let Err(e) = result.map_not_found() else {
+            unreachable!()
+        };
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(e.source.unwrap().to_string(), "error".to_owned());
+
+        let result: StdResult<(), _> = Err(anyhow!("duplicate key"));
+        let Err(e) = result.map_conflict("order exists") else {
+            unreachable!()
+        };
+        assert_eq!(e.status_code, StatusCode::CONFLICT);
+        assert_eq!(e.reason, Some("order exists".into()));
+
+        let result: StdResult<i32, anyhow::Error> = Ok(1);
+        assert_eq!(result.map_bad_request("invalid"), Ok(1));
+    }
+
     #[test]
     fn http_err_ext_option() {
         let opt: Option<()> = None;
@@ -146,4 +359,60 @@ mod tests {
         assert_eq!(e.status_code, StatusCode::BAD_REQUEST);
         assert!(e.source.is_none());
     }
+
+    #[test]
+    fn http_err_ext_option_ok_or_http_error() {
+        let http_result: StdResult<_, HttpError> =
+            None::<()>.ok_or_http_error(StatusCode::NOT_FOUND, "not found");
+
+        let Err(e) = http_result else { unreachable!() };
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(e.reason, Some("not found".into()));
+
+        let http_result: StdResult<_, HttpError> =
+            Some(1).ok_or_http_error(StatusCode::NOT_FOUND, "not found");
+        assert_eq!(http_result, Ok(1));
+    }
+
+    #[test]
+    fn http_err_ext_option_ok_or_else_http_error() {
+        let http_result: StdResult<(), HttpError> = None.ok_or_else_http_error(|| {
+            HttpError::from_status_code(StatusCode::NOT_FOUND).with_key_value("id", 1)
+        });
+
+        let Err(e) = http_result else { unreachable!() };
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(e.get::<i32>("id"), Some(1));
+
+        let http_result: StdResult<_, HttpError> = Some(1).ok_or_else_http_error(|| unreachable!());
+        assert_eq!(http_result, Ok(1));
+    }
+
+    #[test]
+    fn http_err_ext_http_context() {
+        let result: StdResult<(), _> = Err(anyhow!("error"));
+        let Err(e) = result.http_context(StatusCode::BAD_GATEWAY, "upstream failed") else {
+            unreachable!()
+        };
+        assert_eq!(e.status_code, StatusCode::BAD_GATEWAY);
+        assert!(e.reason.is_none());
+        let source = e.source.unwrap();
+        assert_eq!(source.to_string(), "upstream failed");
+        assert_eq!(source.root_cause().to_string(), "error");
+
+        let Err(e) = None::<()>.with_http_context(StatusCode::NOT_FOUND, || format!("user {}", 1))
+        else {
+            unreachable!()
+        };
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(e.source.unwrap().to_string(), "user 1");
+
+        let ok: StdResult<_, HttpError> = Ok::<_, anyhow::Error>(1)
+            .with_http_context(StatusCode::NOT_FOUND, || -> &str { unreachable!() });
+        assert_eq!(ok, Ok(1));
+        assert_eq!(
+            Some(1).http_context(StatusCode::NOT_FOUND, "missing"),
+            Ok(1)
+        );
+    }
 }
@@ -1,28 +1,72 @@
 use anyhow::anyhow;
+use bytes::Bytes;
 use core::fmt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::borrow::Cow;
 use std::error::Error as StdError;
-use std::{borrow::Cow, collections::HashMap};
+use std::sync::Arc;
+use std::time::Duration;
 
-use http::StatusCode;
+use http::{
+    header::LOCATION, status::InvalidStatusCode, HeaderMap, HeaderName, HeaderValue, StatusCode,
+    Uri,
+};
+
+use crate::{DataMap, Violation};
+
+/// The data key under which the elapsed time in milliseconds is recorded, see
+/// [`HttpError::with_elapsed`].
+pub const ELAPSED_DATA_KEY: &str = "elapsed_ms";
+
+/// The data key under which the documentation link of an error is recorded, see
+/// [`HttpError::with_docs_url`].
+pub const DOCS_URL_DATA_KEY: &str = "documentation";
 
 /// [`HttpError`] is an error that encapsulates data to generate Http error responses.
 pub struct HttpError {
     pub(crate) status_code: StatusCode,
     pub(crate) reason: Option<Cow<'static, str>>,
-    pub(crate) source: Option<anyhow::Error>,
-    pub(crate) data: Option<HashMap<String, serde_json::Value>>,
+    pub(crate) source: Option<Arc<anyhow::Error>>,
+    /// Boxed, since most errors don't carry any of it and a small error keeps `Result`s cheap to
+    /// move on the happy path.
+    pub(crate) extra: Option<Box<Extra>>,
+    pub(crate) allow_non_error_status: bool,
+    /// Stored as `u16` to keep the error small, larger limits are equivalent to no limit.
+    pub(crate) source_chain_limit: Option<u16>,
+    pub(crate) origin: Option<&'static str>,
+}
+
+/// The parts of a [`HttpError`] that are rarely set.
+#[derive(Clone, Default)]
+pub(crate) struct Extra {
+    pub(crate) public_message: Option<Cow<'static, str>>,
+    pub(crate) data: Option<DataMap>,
+    pub(crate) violations: Option<Vec<Violation>>,
+    pub(crate) headers: Option<HeaderMap>,
+}
+
+impl Extra {
+    fn is_set(&self) -> bool {
+        self.public_message.is_some()
+            || self.data.is_some()
+            || self.violations.is_some()
+            || self.headers.is_some()
+    }
 }
 
 impl fmt::Debug for HttpError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "HttpError\nStatus: {status_code}\nReason: {reason:?}\nData: {data:?}\n\nSource: {source:?}",
+            "HttpError\nStatus: {status_code}\nReason: {reason:?}\nPublic message: {public_message:?}\nData: {data:?}\nViolations: {violations:?}\nHeaders: {headers:?}\nOrigin: {origin:?}\n\nSource: {source:?}",
             status_code = self.status_code,
+            origin = self.origin,
             reason = self.reason,
-            data = self.data,
+            public_message = self.public_message(),
+            data = self.data_map(),
+            violations = self.violation_list(),
+            headers = self.headers(),
             source = self.source
         )
     }
@@ -30,7 +74,12 @@ impl fmt::Debug for HttpError {
 
 impl fmt::Display for HttpError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match (&self.reason, &self.source) {
+        let source = self
+            .source
+            .as_deref()
+            .filter(|_| self.source_chain_limit != Some(0))
+            .map(|s| SourceChain(s, self.source_chain_limit.map(|l| l as usize)));
+        match (&self.reason, source) {
             (None, None) => write!(f, "HttpError({})", self.status_code),
             (Some(r), None) => write!(f, "HttpError({}): {r}", self.status_code),
             (None, Some(s)) if f.alternate() => {
@@ -45,11 +94,32 @@ impl fmt::Display for HttpError {
     }
 }
 
+/// Renders a source error like [`anyhow::Error`], but with the alternate format only the first
+/// causes up to the [limit](HttpError::with_source_chain_limit).
+struct SourceChain<'a>(&'a anyhow::Error, Option<usize>);
+
+impl fmt::Display for SourceChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(limit) if f.alternate() => {
+                for (i, e) in self.0.chain().take(limit).enumerate() {
+                    if i > 0 {
+                        f.write_str(": ")?;
+                    }
+                    write!(f, "{e}")?;
+                }
+                Ok(())
+            }
+            _ => fmt::Display::fmt(self.0, f),
+        }
+    }
+}
+
 impl StdError for HttpError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         self.source
             .as_deref()
-            .map(|e| e as &(dyn StdError + 'static))
+            .map(|e| &**e as &(dyn StdError + 'static))
     }
 }
 
@@ -60,11 +130,118 @@ impl Default for HttpError {
     }
 }
 
+/// The destructured parts of a [`HttpError`], see [`HttpError::into_parts`].
+#[derive(Debug)]
+pub struct HttpErrorParts {
+    pub status_code: StatusCode,
+    pub reason: Option<Cow<'static, str>>,
+    pub public_message: Option<Cow<'static, str>>,
+    pub source: Option<Arc<anyhow::Error>>,
+    pub data: Option<DataMap>,
+    pub violations: Option<Vec<Violation>>,
+    pub headers: Option<HeaderMap>,
+    pub allow_non_error_status: bool,
+    pub source_chain_limit: Option<usize>,
+    pub origin: Option<&'static str>,
+}
+
+impl Default for HttpErrorParts {
+    fn default() -> Self {
+        HttpError::new().into_parts()
+    }
+}
+
+/// Cloning deep-copies status, reason, data, violations and headers. The source error is shared
+/// between the clones.
+impl Clone for HttpError {
+    fn clone(&self) -> Self {
+        Self {
+            status_code: self.status_code,
+            reason: self.reason.clone(),
+            source: self.source.clone(),
+            extra: self.extra.clone(),
+            allow_non_error_status: self.allow_non_error_status,
+            source_chain_limit: self.source_chain_limit,
+            origin: self.origin,
+        }
+    }
+}
+
+/// A source error that is still shared with other [`HttpError`]s when context is added to it.
+/// It renders and chains like the shared error.
+struct SharedSource(Arc<anyhow::Error>);
+
+impl fmt::Debug for SharedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for SharedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self.0, f)
+    }
+}
+
+impl StdError for SharedSource {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Looks through [`SharedSource`] to find a [`HttpError`] in a source chain.
+fn downcast_http_error_ref<'a>(err: &'a (dyn StdError + 'static)) -> Option<&'a HttpError> {
+    err.downcast_ref::<HttpError>().or_else(|| {
+        err.downcast_ref::<SharedSource>()
+            .and_then(|s| s.0.downcast_ref::<HttpError>())
+    })
+}
+
+/// The ellipsis that marks a truncated reason.
+const ELLIPSIS: &str = "…";
+
+/// Truncates `s` to at most `max_len` bytes on a char boundary. A truncated string ends with an
+/// ellipsis, which counts towards `max_len` if it fits.
+pub(crate) fn truncate_reason(s: Cow<'static, str>, max_len: usize) -> Cow<'static, str> {
+    if s.len() <= max_len {
+        return s;
+    }
+    let (budget, ellipsis) = match max_len.checked_sub(ELLIPSIS.len()) {
+        Some(budget) => (budget, ELLIPSIS),
+        None => (max_len, ""),
+    };
+    let end = (0..=budget)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or_default();
+    let mut s = s.into_owned();
+    s.truncate(end);
+    s.push_str(ellipsis);
+    Cow::Owned(s)
+}
+
+/// Selects which [`HttpError`] wins if an error chain contains several, see
+/// [`HttpError::from_err_with_policy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DowncastPolicy {
+    /// The [`HttpError`] closest to the top of the chain.
+    #[default]
+    Outermost,
+    /// The [`HttpError`] closest to the root cause.
+    Innermost,
+    /// The [`HttpError`] with the highest status code. Ties are resolved like
+    /// [`Outermost`](Self::Outermost).
+    HighestStatus,
+}
+
 impl PartialEq for HttpError {
     fn eq(&self, other: &Self) -> bool {
         self.status_code == other.status_code
             && self.reason == other.reason
-            && self.data == other.data
+            && self.public_message() == other.public_message()
+            && self.data_map() == other.data_map()
+            && self.violation_list() == other.violation_list()
+            && self.headers() == other.headers()
     }
 }
 
@@ -75,7 +252,10 @@ impl HttpError {
             status_code: StatusCode::INTERNAL_SERVER_ERROR,
             reason: None,
             source: None,
-            data: None,
+            extra: None,
+            allow_non_error_status: false,
+            source_chain_limit: None,
+            origin: None,
         }
     }
 
@@ -93,10 +273,31 @@ impl HttpError {
             status_code,
             reason: Some(Cow::Borrowed(reason)),
             source: None,
-            data: None,
+            extra: None,
+            allow_non_error_status: false,
+            source_chain_limit: None,
+            origin: None,
         }
     }
 
+    /// Creates a [`HttpError`] with status 422 from one or more field-level violations.
+    ///
+    /// ```
+    /// use anyhow_http::{HttpError, Violation};
+    ///
+    /// let err = HttpError::from_violations([
+    ///     Violation::new("email").with_code("invalid_format"),
+    ///     Violation::new("age").with_message("must be at least 18"),
+    /// ]);
+    /// assert_eq!(err.violations().len(), 2);
+    /// ```
+    pub fn from_violations<I>(violations: I) -> Self
+    where
+        I: IntoIterator<Item = Violation>,
+    {
+        Self::from_status_code(StatusCode::UNPROCESSABLE_ENTITY).with_violations(violations)
+    }
+
     /// Creates a [`HttpError`] from a status code.
     pub const fn from_status_code(status_code: StatusCode) -> Self {
         let mut http_err = Self::new();
@@ -104,132 +305,891 @@ impl HttpError {
         http_err
     }
 
-    /// Sets the status code.
+    /// Creates a [`HttpError`] from a raw status code in the range `100..=999`, including
+    /// non-standard codes such as `499 Client Closed Request` used by proxies.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    ///
+    /// let err = HttpError::from_status_u16(499).unwrap();
+    /// assert_eq!(err.status_code(), 499);
+    /// assert!(HttpError::from_status_u16(1000).is_err());
+    /// ```
+    pub fn from_status_u16(status_code: u16) -> Result<Self, InvalidStatusCode> {
+        StatusCode::from_u16(status_code).map(Self::from_status_code)
+    }
+
+    /// Creates a [`HttpError`] with a redirection status code and a `Location` header. Returns
+    /// `None` if the status code is neither `3xx` nor `201 Created` or if the location is not a
+    /// valid URI.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    /// use http::StatusCode;
+    ///
+    /// let err = HttpError::redirect(StatusCode::SEE_OTHER, "/orders/42").unwrap();
+    /// assert_eq!(err.headers().unwrap()["location"], "/orders/42");
+    /// assert!(HttpError::redirect(StatusCode::NOT_FOUND, "/orders/42").is_none());
+    /// ```
+    pub fn redirect<U>(status_code: StatusCode, location: U) -> Option<Self>
+    where
+        U: TryInto<Uri>,
+    {
+        if !status_code.is_redirection() && status_code != StatusCode::CREATED {
+            return None;
+        }
+        let location = location.try_into().ok()?;
+        let mut http_err = Self::from_status_code(status_code);
+        http_err.set_location(&location)?;
+        Some(http_err)
+    }
+
+    /// Sets the status code. Use [`try_with_status_code`](Self::try_with_status_code) to reject
+    /// status codes below `400`.
+    #[cfg(not(feature = "strict"))]
     pub const fn with_status_code(mut self, status_code: StatusCode) -> Self {
         self.status_code = status_code;
         self
     }
 
+    /// Sets the status code.
+    ///
+    /// With feature `strict` this is not a `const fn`, and setting a status code below `400`
+    /// panics in debug builds unless [`allow_non_error_status`](Self::allow_non_error_status)
+    /// was called before.
+    #[cfg(feature = "strict")]
+    pub fn with_status_code(mut self, status_code: StatusCode) -> Self {
+        self.set_status_code(status_code);
+        self
+    }
+
+    /// Sets the status code, unless it is below `400` and
+    /// [`allow_non_error_status`](Self::allow_non_error_status) was not called before. The
+    /// unmodified error is returned otherwise.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    /// use http::StatusCode;
+    ///
+    /// let err = HttpError::default().try_with_status_code(StatusCode::NO_CONTENT);
+    /// assert_eq!(err.unwrap_err().status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    /// ```
+    pub fn try_with_status_code(mut self, status_code: StatusCode) -> Result<Self, Self> {
+        if !self.allow_non_error_status && status_code.as_u16() < 400 {
+            return Err(self);
+        }
+        self.status_code = status_code;
+        Ok(self)
+    }
+
+    /// Allows setting status codes below `400` with
+    /// [`try_with_status_code`](Self::try_with_status_code) and, under feature `strict`,
+    /// [`with_status_code`](Self::with_status_code) and
+    /// [`set_status_code`](Self::set_status_code), e.g. for redirects.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    /// use http::StatusCode;
+    ///
+    /// let err = HttpError::default()
+    ///     .allow_non_error_status()
+    ///     .try_with_status_code(StatusCode::NOT_MODIFIED)
+    ///     .unwrap();
+    /// assert_eq!(err.status_code(), StatusCode::NOT_MODIFIED);
+    /// ```
+    pub const fn allow_non_error_status(mut self) -> Self {
+        self.allow_non_error_status = true;
+        self
+    }
+
+    /// Records where the error was declared, e.g. the enum variant it was converted from. The
+    /// derive macro records `Enum::Variant` for every converted variant. The origin is not
+    /// rendered into responses; it identifies errors in logs, metrics labels and tests.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let err = http_error!(NOT_FOUND).with_origin("UserError::NotFound");
+    /// assert_eq!(err.origin(), Some("UserError::NotFound"));
+    /// ```
+    pub const fn with_origin(mut self, origin: &'static str) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Limits how much of the source chain is rendered to the first `limit` errors, e.g. because
+    /// deeply nested [`anyhow::Context`] produces unwieldy log lines. The limit applies to the
+    /// alternate [`Display`](fmt::Display) format, [`source_chain`](Self::source_chain) and
+    /// the chains rendered by the built-in formatters and integrations. A limit of `0` omits the
+    /// source entirely.
+    ///
+    /// ```
+    /// use anyhow::Context;
+    /// use anyhow_http::http_error;
+    ///
+    /// let source = Err::<(), _>(anyhow::anyhow!("connection refused"))
+    ///     .context("query failed")
+    ///     .context("failed to load user")
+    ///     .unwrap_err();
+    /// let err = http_error!(INTERNAL_SERVER_ERROR, source = source).with_source_chain_limit(2);
+    /// assert_eq!(
+    ///     format!("{err:#}"),
+    ///     "HttpError(500 Internal Server Error): source: failed to load user: query failed"
+    /// );
+    /// assert_eq!(err.source_chain().count(), 2);
+    /// ```
+    pub const fn with_source_chain_limit(mut self, limit: usize) -> Self {
+        self.source_chain_limit = Some(if limit > u16::MAX as usize {
+            u16::MAX
+        } else {
+            limit as u16
+        });
+        self
+    }
+
     /// Sets the error reason.
     pub fn with_reason<S: Into<Cow<'static, str>>>(mut self, reason: S) -> Self {
         self.reason = Some(reason.into());
         self
     }
 
+    /// Sets the error reason, truncated to at most `max_len` bytes, e.g. for reasons that
+    /// interpolate user input. A truncated reason ends with an ellipsis.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let input = "a".repeat(1024);
+    /// let err = http_error!(BAD_REQUEST).with_reason_truncated(format!("invalid name {input}"), 20);
+    /// assert_eq!(err.reason().as_deref(), Some("invalid name aaaa…"));
+    /// ```
+    pub fn with_reason_truncated<S: Into<Cow<'static, str>>>(
+        self,
+        reason: S,
+        max_len: usize,
+    ) -> Self {
+        self.with_reason(truncate_reason(reason.into(), max_len))
+    }
+
+    /// Sets the error reason if `cond` is `true`.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let verbose = false;
+    /// let err = http_error!(BAD_REQUEST)
+    ///     .with_reason_if(verbose, "missing field `name`")
+    ///     .maybe_with_header(Some(("x-error-code", "E42")));
+    /// assert_eq!(err.reason(), None);
+    /// assert_eq!(err.headers().unwrap()["x-error-code"], "E42");
+    /// ```
+    pub fn with_reason_if<S: Into<Cow<'static, str>>>(self, cond: bool, reason: S) -> Self {
+        if cond {
+            self.with_reason(reason)
+        } else {
+            self
+        }
+    }
+
+    /// Sets the message that is shown to clients. Once a public message is set, the
+    /// [`reason`](Self::with_reason) is no longer rendered by the built-in formatters and only
+    /// shows up in logs, e.g. through [`Display`](fmt::Display) and [`Debug`](fmt::Debug).
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, response::default_reason};
+    ///
+    /// let err = http_error!(INTERNAL_SERVER_ERROR, "connection pool exhausted")
+    ///     .with_public_message("please try again later");
+    /// assert_eq!(default_reason(&err).as_deref(), Some("please try again later"));
+    /// assert!(err.to_string().contains("connection pool exhausted"));
+    /// ```
+    pub fn with_public_message<S: Into<Cow<'static, str>>>(mut self, message: S) -> Self {
+        self.set_public_message(message);
+        self
+    }
+
     /// Adds context to the source error. If no source is availabe a new [`anyhow::Error`] is
     /// created in its place.
     pub fn with_source_context<C>(mut self, context: C) -> Self
     where
         C: fmt::Display + Send + Sync + 'static,
     {
-        let source = match self.source {
-            Some(s) => s.context(context),
+        let source = match self.source.map(Arc::try_unwrap) {
+            Some(Ok(s)) => s.context(context),
+            Some(Err(shared)) => anyhow::Error::new(SharedSource(shared)).context(context),
             None => anyhow!("{context}"),
         };
-        self.source = Some(source);
+        self.source = Some(Arc::new(source));
         self
     }
 
-    /// Set the source error from a generic error trait object.
+    /// Set the source error from a generic error trait object. The source chain of the error is
+    /// preserved and the boxed error can be retrieved by downcasting the source to
+    /// `Box<dyn Error + Send + Sync>`.
     pub fn with_boxed_source_err(mut self, err: Box<dyn StdError + Send + Sync + 'static>) -> Self {
-        self.source = Some(anyhow!("{err}"));
+        self.source = Some(Arc::new(anyhow!(err)));
         self
     }
 
+    /// Set the source error from an [`eyre::Report`], preserving its source chain.
+    #[cfg(feature = "eyre")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "eyre")))]
+    pub fn with_source_report(self, report: eyre::Report) -> Self {
+        self.with_boxed_source_err(report.into())
+    }
+
     /// Set the source error from a generic error.
     pub fn with_source_err<E>(mut self, err: E) -> Self
     where
         E: Into<anyhow::Error>,
     {
-        self.source = Some(err.into());
+        self.source = Some(Arc::new(err.into()));
+        self
+    }
+
+    /// Set the source error from an error that is shared, e.g. with other [`HttpError`]s.
+    pub fn with_shared_source(mut self, err: Arc<anyhow::Error>) -> Self {
+        self.source = Some(err);
         self
     }
 
-    /// Append to to the inner data based on one or more key-value pairs.
-    ///
-    /// ```
-    /// use anyhow_http::HttpError;
-    ///
-    /// let err: HttpError = HttpError::default()
-    ///     .with_data([("key1", 1234), ("key2", 5678)])
-    ///     .unwrap();
-    /// ```
-    pub fn with_data<I, K, V>(mut self, values: I) -> Option<Self>
-    where
-        I: IntoIterator<Item = (K, V)>,
-        K: Into<String>,
-        V: Serialize + Sync + Send + 'static,
-    {
-        let iter = values
-            .into_iter()
-            .map(|(k, v)| Some((k.into(), serde_json::to_value(v).ok()?)));
+    /// Inherits status code, data, violations and headers from a [`HttpError`] in the source
+    /// chain, e.g. when it was wrapped with [`with_source_err`](Self::with_source_err) or
+    /// [`with_source_context`](Self::with_source_context). The status code is only inherited if it
+    /// is still the default `500` and only data keys and headers that are not set yet are
+    /// inherited.
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError};
+    /// use http::StatusCode;
+    ///
+    /// let inner: anyhow::Error = http_error!(NOT_FOUND, "user not found").into();
+    /// let err = HttpError::default()
+    ///     .with_source_err(inner)
+    ///     .with_source_context("failed to load profile")
+    ///     .merge_source_http_error();
+    /// assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+    /// ```
+    pub fn merge_source_http_error(mut self) -> Self {
+        let source_err = self.source.clone();
+        let Some(source) = source_err
+            .as_ref()
+            .and_then(|s| s.chain().find_map(downcast_http_error_ref))
+        else {
+            return self;
+        };
+
+        if self.status_code == StatusCode::INTERNAL_SERVER_ERROR {
+            self.status_code = source.status_code;
+        }
+        if let Some(data) = source.data_map() {
+            let target = self.data_mut();
+            for (k, v) in data.iter() {
+                if !target.contains_key(k) {
+                    target.insert(k, v.clone());
+                }
+            }
+        }
+        if let (None, Some(violations)) = (self.violation_list(), source.violation_list()) {
+            self.extra_mut().violations = Some(violations.to_vec());
+        }
+        if self.public_message().is_none() {
+            if let Some(message) = source.extra.as_ref().and_then(|e| e.public_message.clone()) {
+                self.set_public_message(message);
+            }
+        }
+        if let Some(headers) = source.headers() {
+            let target = self.headers_mut();
+            for name in headers.keys() {
+                if !target.contains_key(name) {
+                    for value in headers.get_all(name) {
+                        target.append(name, value.clone());
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Append to to the inner data based on one or more key-value pairs. All values are
+    /// serialized before any is added, so `None` is returned without partially adding the data
+    /// if a value fails to serialize.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    ///
+    /// let err: HttpError = HttpError::default()
+    ///     .with_data([("key1", 1234), ("key2", 5678)])
+    ///     .unwrap();
+    /// ```
+    pub fn with_data<I, K, V>(mut self, values: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Serialize + Sync + Send + 'static,
+    {
+        let values = values
+            .into_iter()
+            .map(|(k, v)| Some((k, serde_json::to_value(v).ok()?)))
+            .collect::<Option<Vec<_>>>()?;
+        self.data_mut().extend(values);
+        Some(self)
+    }
+
+    /// Adds a key-pair value to the inner data.
+    pub fn with_key_value<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Serialize + Sync + Send + 'static,
+    {
+        self.insert_data(key, value);
+        self
+    }
+
+    /// Adds binary data, e.g. a truncated request body, to the inner data. The bytes are stored as
+    /// [`DataValue::Binary`](crate::DataValue::Binary), which the Json formatters render
+    /// base64-encoded as `{"encoding": "base64", "data": ".."}` and
+    /// [`Logfmt`](crate::response::Logfmt) as `base64:..`. Use [`get_binary`](Self::get_binary)
+    /// to retrieve the bytes.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    /// use bytes::Bytes;
+    ///
+    /// let err = http_error!(BAD_REQUEST).with_binary_data("body", Bytes::from_static(b"\xff\x00"));
+    /// assert_eq!(
+    ///     err.get::<serde_json::Value>("body"),
+    ///     Some(serde_json::json!({ "encoding": "base64", "data": "/wA=" }))
+    /// );
+    /// assert_eq!(err.get_binary("body"), Some(Bytes::from_static(b"\xff\x00")));
+    /// ```
+    pub fn with_binary_data<K: Into<String>>(mut self, key: K, data: impl Into<Bytes>) -> Self {
+        self.data_mut().insert(key, data.into());
+        self
+    }
+
+    /// Adds an already constructed Json value to the inner data. Unlike
+    /// [`with_key_value`](Self::with_key_value) the value is moved into the data as is instead of
+    /// being serialized again.
+    pub fn with_json_value<K: Into<String>>(mut self, key: K, value: serde_json::Value) -> Self {
+        self.data_mut().insert(key, value);
+        self
+    }
+
+    /// Adds pre-serialized Json, e.g. a fragment of another Json document captured as
+    /// `Box<RawValue>`, to the inner data. The raw Json is stored as is and written through to
+    /// Json responses without being parsed and serialized again.
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, response::{FormatResponse, Json}};
+    /// use serde_json::value::RawValue;
+    ///
+    /// let upstream = RawValue::from_string(r#"{"code":"quota_exceeded"}"#.to_string()).unwrap();
+    /// let err = http_error!(BAD_GATEWAY).with_raw_json("upstream", upstream);
+    /// assert_eq!(
+    ///     Json::format_response(&err),
+    ///     r#"{"error":{"message":"Bad Gateway","upstream":{"code":"quota_exceeded"}}}"#
+    /// );
+    /// ```
+    #[cfg(feature = "raw-json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "raw-json")))]
+    pub fn with_raw_json<K: Into<String>>(
+        mut self,
+        key: K,
+        value: Box<serde_json::value::RawValue>,
+    ) -> Self {
+        self.data_mut().insert(key, value);
+        self
+    }
+
+    /// Adds a key-pair value to the inner data if `cond` is `true`.
+    pub fn with_data_if<K, V>(self, cond: bool, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Serialize + Sync + Send + 'static,
+    {
+        if cond {
+            self.with_key_value(key, value)
+        } else {
+            self
+        }
+    }
+
+    /// Adds a header that is emitted on the error response, replacing previous values of the
+    /// header. Invalid header names or values are ignored. See
+    /// [`append_header`](Self::append_header) for headers that repeat.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    ///
+    /// let err = HttpError::default().with_header("retry-after", "120");
+    /// assert_eq!(err.headers().unwrap()["retry-after"], "120");
+    /// ```
+    pub fn with_header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: TryInto<HeaderName>,
+        V: TryInto<HeaderValue>,
+    {
+        let (Ok(key), Ok(value)) = (key.try_into(), value.try_into()) else {
+            return self;
+        };
+        self.headers_mut().insert(key, value);
+        self
+    }
+
+    /// Appends a header value that is emitted on the error response, keeping previous values of
+    /// the header, e.g. for `Set-Cookie` or several `WWW-Authenticate` challenges. Invalid header
+    /// names or values are ignored.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let err = http_error!(UNAUTHORIZED)
+    ///     .append_header("www-authenticate", r#"Bearer realm="api""#)
+    ///     .append_header("www-authenticate", r#"Basic realm="api""#);
+    /// assert_eq!(err.headers().unwrap().get_all("www-authenticate").iter().count(), 2);
+    /// ```
+    pub fn append_header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: TryInto<HeaderName>,
+        V: TryInto<HeaderValue>,
+    {
+        let (Ok(key), Ok(value)) = (key.try_into(), value.try_into()) else {
+            return self;
+        };
+        self.headers_mut().append(key, value);
+        self
+    }
+
+    /// Sets the `Location` header, e.g. for `3xx` or `201 Created` responses. Locations that are
+    /// not a valid URI are ignored.
+    pub fn with_location<U>(mut self, location: U) -> Self
+    where
+        U: TryInto<Uri>,
+    {
+        if let Ok(location) = location.try_into() {
+            self.set_location(&location);
+        }
+        self
+    }
+
+    fn set_location(&mut self, location: &Uri) -> Option<()> {
+        let value = HeaderValue::try_from(location.to_string()).ok()?;
+        self.headers_mut().insert(LOCATION, value);
+        Some(())
+    }
+
+    /// Adds a header if one is given, see [`with_header`](Self::with_header).
+    pub fn maybe_with_header<K, V>(self, header: Option<(K, V)>) -> Self
+    where
+        K: TryInto<HeaderName>,
+        V: TryInto<HeaderValue>,
+    {
+        match header {
+            Some((key, value)) => self.with_header(key, value),
+            None => self,
+        }
+    }
+
+    /// Records the time spent handling the request in milliseconds as `elapsed_ms` data.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    /// use std::time::Duration;
+    ///
+    /// let err = http_error!(GATEWAY_TIMEOUT).with_elapsed(Duration::from_millis(1500));
+    /// assert_eq!(err.get::<u64>("elapsed_ms"), Some(1500));
+    /// ```
+    pub fn with_elapsed(self, elapsed: Duration) -> Self {
+        self.with_key_value(ELAPSED_DATA_KEY, elapsed.as_millis() as u64)
+    }
+
+    /// Links the error to its documentation, recorded as `documentation` data. The problem
+    /// details formatter renders it as the `type` of the problem. Takes precedence over the
+    /// resolver configured with
+    /// [`set_docs_url_resolver`](crate::response::set_docs_url_resolver).
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let err = http_error!(CONFLICT).with_docs_url("https://docs.example.com/errors/conflict");
+    /// assert_eq!(
+    ///     err.get::<String>("documentation").as_deref(),
+    ///     Some("https://docs.example.com/errors/conflict")
+    /// );
+    /// ```
+    pub fn with_docs_url<S: Into<Cow<'static, str>>>(self, url: S) -> Self {
+        self.with_key_value(DOCS_URL_DATA_KEY, url.into().into_owned())
+    }
+
+    /// Appends a `Server-Timing` metric with the given duration to the error response, e.g.
+    /// `handler;dur=12.345`.
+    pub fn with_server_timing(mut self, metric: &str, duration: Duration) -> Self {
+        let value = format!("{metric};dur={:.3}", duration.as_secs_f64() * 1000.0);
+        if let Ok(value) = HeaderValue::try_from(value) {
+            self.headers_mut()
+                .append(HeaderName::from_static("server-timing"), value);
+        }
+        self
+    }
+
+    /// Sets `Cache-Control: no-store` on the error response.
+    pub fn with_no_store(self) -> Self {
+        self.with_header(http::header::CACHE_CONTROL, "no-store")
+    }
+
+    /// Sets the `Content-Language` header of a localized error response and adds
+    /// `Accept-Language` to its `Vary` header. Invalid language tags are ignored.
+    ///
+    /// ```
+    /// use anyhow_http::http_error;
+    ///
+    /// let err = http_error!(NOT_FOUND, "Benutzer nicht gefunden").with_content_language("de");
+    /// assert_eq!(err.headers().unwrap()["content-language"], "de");
+    /// assert_eq!(err.headers().unwrap()["vary"], "accept-language");
+    /// ```
+    pub fn with_content_language<V: TryInto<HeaderValue>>(mut self, lang: V) -> Self {
+        let Ok(lang) = lang.try_into() else {
+            return self;
+        };
+        let headers = self.headers_mut();
+        headers.insert(http::header::CONTENT_LANGUAGE, lang);
+        let varies = headers
+            .get_all(http::header::VARY)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case("accept-language"));
+        if !varies {
+            headers.append(
+                http::header::VARY,
+                HeaderValue::from_static("accept-language"),
+            );
+        }
+        self
+    }
+
+    /// Appends a field-level violation.
+    pub fn with_violation(self, violation: Violation) -> Self {
+        self.with_violations([violation])
+    }
+
+    /// Appends one or more field-level violations.
+    pub fn with_violations<I>(mut self, violations: I) -> Self
+    where
+        I: IntoIterator<Item = Violation>,
+    {
+        self.extra_mut()
+            .violations
+            .get_or_insert_with(Default::default)
+            .extend(violations);
+        self
+    }
+
+    /// Retrieves a key-pair value from the inner data.
+    pub fn get<V>(&self, key: impl AsRef<str>) -> Option<V>
+    where
+        V: DeserializeOwned + Send + Sync,
+    {
+        self.data_map()
+            .and_then(|d| d.get(key.as_ref()))
+            .and_then(|v| v.deserialize())
+    }
+
+    /// Retrieves binary data added with [`with_binary_data`](Self::with_binary_data).
+    pub fn get_binary(&self, key: impl AsRef<str>) -> Option<Bytes> {
+        self.data_map()?.get(key.as_ref())?.as_binary().cloned()
+    }
+
+    /// Returns the status code.
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    /// Returns the error reason if any.
+    pub fn reason(&self) -> Option<Cow<'static, str>> {
+        self.reason.clone()
+    }
+
+    /// Returns where the error was declared, see [`with_origin`](Self::with_origin).
+    pub fn origin(&self) -> Option<&'static str> {
+        self.origin
+    }
+
+    /// Returns the public message if any.
+    pub fn public_message(&self) -> Option<&str> {
+        self.extra.as_ref()?.public_message.as_deref()
+    }
+
+    /// Returns the headers if any.
+    pub fn headers(&self) -> Option<&HeaderMap> {
+        self.extra.as_ref()?.headers.as_ref()
+    }
+
+    /// Returns the field-level violations.
+    pub fn violations(&self) -> &[Violation] {
+        self.violation_list().unwrap_or_default()
+    }
+
+    /// Returns the inner data, if any.
+    pub(crate) fn data_map(&self) -> Option<&DataMap> {
+        self.extra.as_ref()?.data.as_ref()
+    }
+
+    /// Returns the field-level violations, if any were added.
+    pub(crate) fn violation_list(&self) -> Option<&[Violation]> {
+        self.extra.as_ref()?.violations.as_deref()
+    }
+
+    fn extra_mut(&mut self) -> &mut Extra {
+        self.extra.get_or_insert_with(Default::default)
+    }
+
+    /// Returns the source error if any.
+    pub fn source(&self) -> Option<&anyhow::Error> {
+        self.source.as_deref()
+    }
+
+    /// Returns the source error and its causes, up to the
+    /// [limit](Self::with_source_chain_limit) if one is set.
+    pub fn source_chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        self.source
+            .iter()
+            .flat_map(|s| s.chain())
+            .take(self.source_chain_limit.map_or(usize::MAX, |l| l as usize))
+    }
+
+    /// Returns a shared handle to the source error if any, e.g. to pass it to a reporter that
+    /// outlives this [`HttpError`].
+    pub fn shared_source(&self) -> Option<Arc<anyhow::Error>> {
+        self.source.clone()
+    }
+
+    /// Returns whether the source chain contains an error of type `E`, see
+    /// [`downcast_ref`](Self::downcast_ref).
+    ///
+    /// ```
+    /// use anyhow::Context;
+    /// use anyhow_http::http_error;
+    ///
+    /// let source = "nan".parse::<u32>().context("invalid id").unwrap_err();
+    /// let err = http_error!(BAD_REQUEST, source = source);
+    /// assert!(err.is::<std::num::ParseIntError>());
+    /// assert!(!err.is::<std::io::Error>());
+    /// ```
+    pub fn is<E>(&self) -> bool
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        self.downcast_ref::<E>().is_some()
+    }
+
+    /// Returns the first error of type `E` in the source chain, including errors wrapped in
+    /// context by [`anyhow::Context`].
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        let source = self.source.as_deref()?;
+        source.downcast_ref::<E>().or_else(|| {
+            source.chain().find_map(|err| {
+                err.downcast_ref::<E>()
+                    .or_else(|| err.downcast_ref::<SharedSource>()?.0.downcast_ref::<E>())
+            })
+        })
+    }
+
+    /// Sets the status code in place. Under feature `strict`, setting a status code below `400`
+    /// panics in debug builds unless [`allow_non_error_status`](Self::allow_non_error_status)
+    /// was called before.
+    ///
+    /// ```
+    /// use anyhow_http::HttpError;
+    /// use http::StatusCode;
+    ///
+    /// fn adjust(err: &mut HttpError) {
+    ///     err.set_status_code(StatusCode::SERVICE_UNAVAILABLE);
+    ///     err.headers_mut().insert("retry-after", "30".parse().unwrap());
+    ///     err.insert_data("retryable", true);
+    /// }
+    ///
+    /// let mut err = HttpError::default();
+    /// adjust(&mut err);
+    /// assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    /// assert_eq!(err.get::<bool>("retryable"), Some(true));
+    /// ```
+    pub fn set_status_code(&mut self, status_code: StatusCode) {
+        #[cfg(feature = "strict")]
+        debug_assert!(
+            self.allow_non_error_status || status_code.as_u16() >= 400,
+            "non-error status code {status_code} set on HttpError, \
+             call `allow_non_error_status` if this is intended"
+        );
+        self.status_code = status_code;
+    }
+
+    /// Sets the error reason in place.
+    pub fn set_reason<S: Into<Cow<'static, str>>>(&mut self, reason: S) {
+        self.reason = Some(reason.into());
+    }
+
+    /// Sets the public message in place.
+    pub fn set_public_message<S: Into<Cow<'static, str>>>(&mut self, message: S) {
+        self.extra_mut().public_message = Some(message.into());
+    }
 
-        self.data = self
-            .data
-            .get_or_insert_with(HashMap::new)
-            .clone()
-            .into_iter()
-            .map(Option::Some)
-            .chain(iter)
-            .collect();
+    /// Returns a mutable reference to the headers, creating an empty map if there are none.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.extra_mut()
+            .headers
+            .get_or_insert_with(Default::default)
+    }
 
-        Some(self)
+    /// Returns a mutable reference to the inner data, creating an empty map if there is none.
+    pub fn data_mut(&mut self) -> &mut DataMap {
+        self.extra_mut().data.get_or_insert_with(Default::default)
     }
 
-    /// Adds a key-pair value to the inner data.
-    pub fn with_key_value<K, V>(mut self, key: K, value: V) -> Self
+    /// Inserts a key-pair value into the inner data in place. Values that fail to serialize are
+    /// ignored.
+    pub fn insert_data<K, V>(&mut self, key: K, value: V)
     where
         K: Into<String>,
         V: Serialize + Sync + Send + 'static,
     {
-        let Ok(value) = serde_json::to_value(value) else {
-            return self;
-        };
-        self.data
-            .get_or_insert_with(HashMap::new)
-            .insert(key.into(), value);
-        self
+        if let Ok(value) = serde_json::to_value(value) {
+            self.data_mut().insert(key, value);
+        }
     }
 
-    /// Retrieves a key-pair value from the inner data.
-    pub fn get<V>(&self, key: impl AsRef<str>) -> Option<V>
+    /// Creates a [`HttpError`] from a generic error. It attempts to downcast to an underlying
+    /// [`HttpError`] or, with feature `axum`, to an extractor rejection. With feature `tracing`,
+    /// the current span is recorded, see [`with_current_span`](Self::with_current_span).
+    pub fn from_err<E>(err: E) -> Self
     where
-        V: DeserializeOwned + Send + Sync,
+        E: Into<anyhow::Error>,
     {
-        self.data
-            .as_ref()
-            .and_then(|d| d.get(key.as_ref()))
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-    }
-
-    /// Returns the status code.
-    pub fn status_code(&self) -> StatusCode {
-        self.status_code
-    }
-
-    /// Returns the error reason if any.
-    pub fn reason(&self) -> Option<Cow<'static, str>> {
-        self.reason.clone()
+        let http_error = Self::downcast_or_wrap(err.into());
+        #[cfg(feature = "tracing")]
+        let http_error = http_error.with_current_span();
+        http_error
     }
 
-    /// Returns the source error if any.
-    pub fn source(&self) -> Option<&anyhow::Error> {
-        self.source.as_ref()
+    fn downcast_or_wrap(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<HttpError>() {
+            Ok(http_error) => return http_error,
+            Err(err) => err,
+        };
+        #[cfg(feature = "axum")]
+        let err = match crate::axum::downcast_rejection(err) {
+            Ok(http_error) => return http_error,
+            Err(err) => err,
+        };
+        Self {
+            source: Some(Arc::new(err)),
+            ..Self::default()
+        }
     }
 
-    /// Creates a [`HttpError`] from a generic error. It attempts to downcast to an underlying
-    /// [`HttpError`].
-    pub fn from_err<E>(err: E) -> Self
+    /// Creates a [`HttpError`] from a generic error like [`from_err`](Self::from_err), but
+    /// searches the whole source chain for embedded [`HttpError`]s and selects one according to
+    /// `policy`. The selected [`HttpError`] is returned as is, apart from the current span
+    /// recorded with feature `tracing`.
+    ///
+    /// ```
+    /// use anyhow::Context;
+    /// use anyhow_http::{http_error, DowncastPolicy, HttpError};
+    /// use http::StatusCode;
+    ///
+    /// let inner: anyhow::Error = http_error!(NOT_FOUND, "user not found").into();
+    /// let outer = HttpError::from_status_code(StatusCode::BAD_GATEWAY).with_source_err(inner);
+    /// let err = anyhow::Error::from(outer).context("failed to load profile");
+    ///
+    /// let e = HttpError::from_err_with_policy(err, DowncastPolicy::Innermost);
+    /// assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+    /// ```
+    pub fn from_err_with_policy<E>(err: E, policy: DowncastPolicy) -> Self
     where
         E: Into<anyhow::Error>,
     {
         let err = err.into();
-        match err.downcast::<HttpError>() {
-            Ok(http_error) => http_error,
-            Err(err) => Self {
-                source: Some(err),
-                ..Self::default()
-            },
+        let mut candidates = err
+            .chain()
+            .enumerate()
+            .filter_map(|(i, e)| Some((i, downcast_http_error_ref(e)?)));
+        let selected = match policy {
+            DowncastPolicy::Outermost => candidates.next(),
+            DowncastPolicy::Innermost => candidates.last(),
+            DowncastPolicy::HighestStatus => candidates.reduce(|max, candidate| {
+                if candidate.1.status_code > max.1.status_code {
+                    candidate
+                } else {
+                    max
+                }
+            }),
+        };
+        // the top of the chain is moved out instead of cloned
+        let selected = match selected {
+            Some((i, e)) if i > 0 => Some(e.clone()),
+            _ => None,
+        };
+        match selected {
+            #[cfg(feature = "tracing")]
+            Some(http_error) => http_error.with_current_span(),
+            #[cfg(not(feature = "tracing"))]
+            Some(http_error) => http_error,
+            None => Self::from_err(err),
+        }
+    }
+
+    /// Destructures the [`HttpError`] into its parts without cloning.
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, HttpError};
+    ///
+    /// let err = http_error!(NOT_FOUND, "user not found").with_key_value("id", 1);
+    /// let mut parts = err.into_parts();
+    /// parts.reason = None;
+    /// let err = HttpError::from_parts(parts);
+    /// assert_eq!(err.reason(), None);
+    /// assert_eq!(err.get::<u64>("id"), Some(1));
+    /// ```
+    pub fn into_parts(self) -> HttpErrorParts {
+        let extra = self.extra.map(|e| *e).unwrap_or_default();
+        HttpErrorParts {
+            status_code: self.status_code,
+            reason: self.reason,
+            public_message: extra.public_message,
+            source: self.source,
+            data: extra.data,
+            violations: extra.violations,
+            headers: extra.headers,
+            allow_non_error_status: self.allow_non_error_status,
+            source_chain_limit: self.source_chain_limit.map(|l| l as usize),
+            origin: self.origin,
+        }
+    }
+
+    /// Creates a [`HttpError`] from its parts.
+    pub fn from_parts(parts: HttpErrorParts) -> Self {
+        let extra = Extra {
+            public_message: parts.public_message,
+            data: parts.data,
+            violations: parts.violations,
+            headers: parts.headers,
+        };
+        let http_error = Self {
+            status_code: parts.status_code,
+            reason: parts.reason,
+            source: parts.source,
+            extra: extra.is_set().then(|| Box::new(extra)),
+            allow_non_error_status: parts.allow_non_error_status,
+            source_chain_limit: None,
+            origin: parts.origin,
+        };
+        match parts.source_chain_limit {
+            Some(limit) => http_error.with_source_chain_limit(limit),
+            None => http_error,
         }
     }
 
@@ -244,6 +1204,26 @@ impl From<anyhow::Error> for HttpError {
     }
 }
 
+/// Lets generic code that is parameterized over an error type use [`HttpError`] for operations
+/// that can't fail. [`HttpErrorResponse`](crate::response::HttpErrorResponse) converts from
+/// [`Infallible`](std::convert::Infallible) like from any other error.
+///
+/// ```
+/// use anyhow_http::HttpError;
+/// use std::convert::Infallible;
+///
+/// fn parse<E: From<Infallible>>(s: &str) -> Result<String, E> {
+///     Ok(s.parse::<String>()?)
+/// }
+///
+/// assert_eq!(parse::<HttpError>("id").unwrap(), "id");
+/// ```
+impl From<std::convert::Infallible> for HttpError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::anyhow;
@@ -276,6 +1256,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn http_error_with_binary_data() {
+        let e = HttpError::default()
+            .with_binary_data("body", Bytes::from_static(b"{\"truncated"))
+            .with_binary_data("empty", Bytes::new())
+            .with_key_value("text", "aGVsbG8=")
+            .with_key_value(
+                "other",
+                serde_json::json!({ "encoding": "base64", "data": "aGVsbG8=", "extra": 1 }),
+            );
+        assert_eq!(
+            e.get_binary("body"),
+            Some(Bytes::from_static(b"{\"truncated"))
+        );
+        assert_eq!(e.get_binary("empty"), Some(Bytes::new()));
+        assert!(e.get_binary("text").is_none());
+        assert!(e.get_binary("other").is_none());
+        assert!(e.get_binary("missing").is_none());
+    }
+
+    #[test]
+    fn http_error_with_source_chain_limit() {
+        let source = anyhow!("connection refused")
+            .context("query failed")
+            .context("failed to load user");
+        let e = HttpError::default().with_source_err(source);
+        assert_eq!(e.source_chain().count(), 3);
+        assert_eq!(
+            format!("{e:#}"),
+            "HttpError(500 Internal Server Error): source: failed to load user: query failed: connection refused"
+        );
+
+        let e = e.with_source_chain_limit(1);
+        assert_eq!(e.source_chain().count(), 1);
+        assert_eq!(
+            format!("{e:#}"),
+            "HttpError(500 Internal Server Error): source: failed to load user"
+        );
+        assert_eq!(
+            e.clone().to_string(),
+            "HttpError(500 Internal Server Error): source: failed to load user"
+        );
+
+        let e = e.with_reason("reason").with_source_chain_limit(0);
+        assert_eq!(e.source_chain().count(), 0);
+        assert_eq!(
+            format!("{e:#}"),
+            "HttpError(500 Internal Server Error): reason"
+        );
+        assert!(e.source().is_some());
+    }
+
     #[test]
     fn http_error_new_const() {
         const ERR: HttpError = HttpError::new();
@@ -363,10 +1395,69 @@ mod tests {
         assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn http_error_size() {
+        assert!(size_of::<HttpError>() <= 64);
+
+        let e = HttpError::default().with_reason("reason");
+        assert!(e.extra.is_none());
+        let e = HttpErrorParts::default();
+        assert!(HttpError::from_parts(e).extra.is_none());
+    }
+
     #[test]
     fn http_error_with_reason() {
         let e: HttpError = HttpError::default().with_reason("reason");
         assert_eq!(e.reason(), Some("reason".into()));
+        assert_eq!(e.public_message(), None);
+    }
+
+    #[test]
+    fn http_error_with_reason_truncated() {
+        let e: HttpError = HttpError::default().with_reason_truncated("reason", 6);
+        assert_eq!(e.reason(), Some("reason".into()));
+        let e: HttpError = HttpError::default().with_reason_truncated("reason", 5);
+        assert_eq!(e.reason(), Some("re…".into()));
+        let e: HttpError = HttpError::default().with_reason_truncated("größe", 6);
+        assert_eq!(e.reason(), Some("gr…".into()));
+        let e: HttpError = HttpError::default().with_reason_truncated("reason", 2);
+        assert_eq!(e.reason(), Some("re".into()));
+    }
+
+    #[test]
+    fn http_error_conditional_builders() {
+        let e: HttpError = HttpError::default()
+            .with_reason_if(false, "hidden")
+            .with_data_if(false, "hidden", 1)
+            .maybe_with_header(None::<(&str, &str)>);
+        assert_eq!(e.reason(), None);
+        assert_eq!(e.get::<i32>("hidden"), None);
+        assert!(e.headers().is_none());
+
+        let e: HttpError = HttpError::default()
+            .with_reason_if(true, "reason")
+            .with_data_if(true, "key", 1)
+            .maybe_with_header(Some(("x-request-id", "1234")));
+        assert_eq!(e.reason(), Some("reason".into()));
+        assert_eq!(e.get::<i32>("key"), Some(1));
+        assert_eq!(e.headers().unwrap()["x-request-id"], "1234");
+    }
+
+    #[test]
+    fn http_error_with_public_message() {
+        let e = HttpError::default()
+            .with_reason("internal")
+            .with_public_message("public");
+        assert_eq!(e.reason(), Some("internal".into()));
+        assert_eq!(e.public_message(), Some("public"));
+        assert_eq!(
+            e.to_string(),
+            "HttpError(500 Internal Server Error): internal"
+        );
+
+        let mut e = HttpError::default();
+        e.set_public_message("public");
+        assert_eq!(e.public_message(), Some("public"));
     }
 
     #[test]
@@ -385,6 +1476,16 @@ mod tests {
         let dyn_err = Box::new(fmt::Error) as Box<dyn StdError + Send + Sync + 'static>;
         let e: HttpError = HttpError::default().with_boxed_source_err(dyn_err);
         assert_eq!(e.source().unwrap().to_string(), fmt::Error.to_string());
+
+        let dyn_err: Box<dyn StdError + Send + Sync + 'static> =
+            anyhow!(fmt::Error).context("outer").into();
+        let e: HttpError = HttpError::default().with_boxed_source_err(dyn_err);
+        let source = e.source().unwrap();
+        assert_eq!(format!("{source:#}"), format!("outer: {}", fmt::Error));
+        assert_eq!(source.chain().count(), 2);
+        assert!(source
+            .downcast_ref::<Box<dyn StdError + Send + Sync + 'static>>()
+            .is_some());
     }
 
     #[test]
@@ -393,6 +1494,209 @@ mod tests {
         assert_eq!(e.source().unwrap().to_string(), fmt::Error.to_string());
     }
 
+    #[test]
+    #[cfg(feature = "preserve-order")]
+    fn http_error_data_preserve_order() {
+        let e = HttpError::default()
+            .with_key_value("b", 1)
+            .with_key_value("a", 2)
+            .with_key_value("c", 3);
+        let keys: Vec<_> = e.data_map().unwrap().keys().collect();
+        assert_eq!(keys, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn http_error_parts() {
+        let e = HttpError::from_status_code(StatusCode::CONFLICT)
+            .with_reason("conflict")
+            .with_key_value("key", 1)
+            .with_header("x-key", "value")
+            .with_violation(Violation::new("field"))
+            .with_source_err(fmt::Error);
+        let source = e.shared_source().unwrap();
+        let parts = e.clone().into_parts();
+        assert_eq!(parts.status_code, StatusCode::CONFLICT);
+        assert_eq!(parts.reason.as_deref(), Some("conflict"));
+        assert!(Arc::ptr_eq(parts.source.as_ref().unwrap(), &source));
+        assert_eq!(parts.data.as_ref().unwrap()["key"], 1);
+        assert_eq!(parts.violations.as_ref().unwrap().len(), 1);
+        assert_eq!(parts.headers.as_ref().unwrap()["x-key"], "value");
+        assert_eq!(HttpError::from_parts(parts), e);
+
+        let parts = HttpErrorParts::default();
+        assert_eq!(HttpError::from_parts(parts), HttpError::new());
+    }
+
+    #[test]
+    fn http_error_parts_round_trip() {
+        let e = HttpError::new()
+            .allow_non_error_status()
+            .with_status_code(StatusCode::FOUND)
+            .with_reason("reason")
+            .with_public_message("public message")
+            .with_key_value("key", 1)
+            .with_header("x-key", "value")
+            .with_violation(Violation::new("field"))
+            .with_source_err(fmt::Error)
+            .with_source_chain_limit(1)
+            .with_origin("Error::Found");
+        let source = e.shared_source().unwrap();
+
+        let parts = HttpError::from_parts(e.clone().into_parts()).into_parts();
+        assert_eq!(parts.status_code, StatusCode::FOUND);
+        assert_eq!(parts.reason.as_deref(), Some("reason"));
+        assert_eq!(parts.public_message.as_deref(), Some("public message"));
+        assert!(Arc::ptr_eq(parts.source.as_ref().unwrap(), &source));
+        assert_eq!(parts.data.as_ref().unwrap()["key"], 1);
+        assert_eq!(
+            parts.violations.as_deref(),
+            Some([Violation::new("field")].as_slice())
+        );
+        assert_eq!(parts.headers.as_ref().unwrap()["x-key"], "value");
+        assert!(parts.allow_non_error_status);
+        assert_eq!(parts.source_chain_limit, Some(1));
+        assert_eq!(parts.origin, Some("Error::Found"));
+
+        let round_trip = HttpError::from_parts(parts);
+        assert_eq!(round_trip, e);
+        assert_eq!(round_trip.source_chain().count(), 1);
+    }
+
+    #[test]
+    fn http_error_mutators() {
+        let mut e = HttpError::default();
+        e.set_status_code(StatusCode::CONFLICT);
+        e.set_reason("conflict");
+        e.headers_mut()
+            .insert("x-key", HeaderValue::from_static("value"));
+        e.data_mut().insert("key", serde_json::json!(1));
+        e.insert_data("other", "value");
+
+        assert_eq!(
+            e,
+            HttpError::from_status_code(StatusCode::CONFLICT)
+                .with_reason("conflict")
+                .with_header("x-key", "value")
+                .with_key_value("key", 1)
+                .with_key_value("other", "value")
+        );
+    }
+
+    #[test]
+    fn http_error_clone() {
+        let e = HttpError::from_status_code(StatusCode::NOT_FOUND)
+            .with_reason("not found")
+            .with_key_value("id", 1)
+            .with_header("x-request-id", "abc")
+            .with_violation(Violation::new("id"))
+            .with_source_err(fmt::Error)
+            .with_source_context("lookup failed");
+        let cloned = e.clone();
+        assert_eq!(cloned, e);
+        assert_eq!(
+            format!("{:#}", cloned.source().unwrap()),
+            format!("{:#}", e.source().unwrap())
+        );
+        assert!(cloned.source().unwrap().is::<fmt::Error>());
+        assert!(Arc::ptr_eq(
+            &cloned.shared_source().unwrap(),
+            &e.shared_source().unwrap()
+        ));
+        assert!(HttpError::default().clone().source().is_none());
+    }
+
+    #[test]
+    fn http_error_shared_source_context() {
+        let inner: anyhow::Error = HttpError::from_status_code(StatusCode::NOT_FOUND).into();
+        let e = HttpError::default().with_source_err(inner);
+        let shared = e.shared_source().unwrap();
+
+        let e = e.with_source_context("context").merge_source_http_error();
+        assert_eq!(
+            format!("{:#}", e.source().unwrap()),
+            "context: HttpError(404 Not Found)"
+        );
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(Arc::strong_count(&shared), 2);
+
+        let shared = Arc::new(anyhow!("shared"));
+        let e = HttpError::default().with_shared_source(shared.clone());
+        assert!(Arc::ptr_eq(&e.shared_source().unwrap(), &shared));
+    }
+
+    #[test]
+    fn http_error_downcast_ref() {
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out");
+        let e = HttpError::default()
+            .with_source_err(anyhow::Error::new(io).context("query failed"))
+            .with_source_context("load user");
+        assert_eq!(
+            e.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+        assert!(e.is::<std::io::Error>());
+        assert!(!e.is::<fmt::Error>());
+        assert!(!HttpError::default().is::<std::io::Error>());
+
+        let e = HttpError::default().with_source_err(fmt::Error);
+        assert!(e.is::<fmt::Error>());
+    }
+
+    #[test]
+    fn http_error_merge_source_http_error() {
+        let inner: anyhow::Error = HttpError::from_status_code(StatusCode::NOT_FOUND)
+            .with_key_value("id", 1)
+            .with_key_value("kind", "user")
+            .with_header("x-inner", "1")
+            .into();
+        let e: HttpError = HttpError::default()
+            .with_key_value("kind", "profile")
+            .with_source_err(inner)
+            .with_source_context("context")
+            .merge_source_http_error();
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.get::<i32>("id"), Some(1));
+        assert_eq!(e.get::<String>("kind"), Some("profile".into()));
+        assert_eq!(e.headers().unwrap()["x-inner"], "1");
+
+        let inner: anyhow::Error = HttpError::from_status_code(StatusCode::NOT_FOUND).into();
+        let e: HttpError = HttpError::from_status_code(StatusCode::BAD_GATEWAY)
+            .with_source_err(inner)
+            .merge_source_http_error();
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+
+        let e: HttpError = HttpError::default()
+            .with_source_err(anyhow!("error"))
+            .merge_source_http_error();
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(e.data_map().is_none());
+    }
+
+    #[test]
+    fn http_error_from_err_with_policy() {
+        fn err() -> anyhow::Error {
+            let innermost = HttpError::from_status_code(StatusCode::NOT_FOUND);
+            let middle: anyhow::Error =
+                HttpError::from_status_code(StatusCode::SERVICE_UNAVAILABLE)
+                    .with_source_err(innermost)
+                    .into();
+            let outer = HttpError::from_status_code(StatusCode::BAD_GATEWAY)
+                .with_shared_source(Arc::new(middle.context("context")));
+            anyhow::Error::from(outer).context("outer context")
+        }
+
+        let e = HttpError::from_err_with_policy(err(), DowncastPolicy::Outermost);
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+        let e = HttpError::from_err_with_policy(err(), DowncastPolicy::Innermost);
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        let e = HttpError::from_err_with_policy(err(), DowncastPolicy::HighestStatus);
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let e = HttpError::from_err_with_policy(anyhow!("error"), DowncastPolicy::Innermost);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(e.source().is_some());
+    }
+
     #[test]
     fn http_error_data() {
         let e: HttpError = HttpError::default().with_key_value("key", 1234);
@@ -407,6 +1711,229 @@ mod tests {
             .unwrap();
         assert_eq!(e.get::<i32>("key1"), Some(1234));
         assert_eq!(e.get::<i32>("key2"), Some(5678));
+
+        struct Unserializable;
+        impl Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("unserializable"))
+            }
+        }
+        let e: Option<HttpError> =
+            HttpError::default().with_data([("key1", Some(Unserializable)), ("key2", None)]);
+        assert!(e.is_none());
+        let mut e = HttpError::default();
+        e.insert_data("key1", Unserializable);
+        assert!(e.data_map().is_none());
+    }
+
+    #[test]
+    fn http_error_with_json_value() {
+        let e: HttpError =
+            HttpError::default().with_json_value("limits", serde_json::json!({ "max": 10 }));
+        assert_eq!(
+            e.get::<serde_json::Value>("limits"),
+            Some(serde_json::json!({ "max": 10 }))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "raw-json")]
+    fn http_error_with_raw_json() {
+        let raw = serde_json::value::RawValue::from_string("[1,2]".to_string()).unwrap();
+        let e: HttpError = HttpError::default().with_raw_json("ids", raw);
+        assert_eq!(e.get::<Vec<u32>>("ids"), Some(vec![1, 2]));
+        assert_eq!(
+            e.data_map().unwrap()["ids"].as_raw_json().unwrap().get(),
+            "[1,2]"
+        );
+    }
+
+    #[test]
+    fn http_error_allow_non_error_status() {
+        let e = HttpError::default()
+            .allow_non_error_status()
+            .with_status_code(StatusCode::NO_CONTENT);
+        assert_eq!(e.status_code(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            e.clone().with_status_code(StatusCode::OK).status_code(),
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[should_panic(expected = "non-error status code 200 OK")]
+    fn http_error_strict_non_error_status() {
+        HttpError::default().set_status_code(StatusCode::OK);
+    }
+
+    #[test]
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[should_panic(expected = "non-error status code 204 No Content")]
+    fn http_error_strict_with_non_error_status() {
+        let _ = HttpError::default().with_status_code(StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn http_error_try_with_status_code() {
+        let e = HttpError::default()
+            .try_with_status_code(StatusCode::BAD_GATEWAY)
+            .unwrap();
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+
+        let e = HttpError::default()
+            .with_reason("moved")
+            .try_with_status_code(StatusCode::FOUND)
+            .unwrap_err();
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(e.reason(), Some("moved".into()));
+
+        let e = HttpError::default()
+            .allow_non_error_status()
+            .try_with_status_code(StatusCode::FOUND)
+            .unwrap();
+        assert_eq!(e.status_code(), StatusCode::FOUND);
+    }
+
+    #[test]
+    fn http_error_with_header() {
+        let e: HttpError = HttpError::default()
+            .with_header("x-request-id", "1234")
+            .with_header(http::header::RETRY_AFTER, "120".to_string());
+        let headers = e.headers().unwrap();
+        assert_eq!(headers["x-request-id"], "1234");
+        assert_eq!(headers[http::header::RETRY_AFTER], "120");
+
+        let e: HttpError = HttpError::default().with_header("invalid header", "value");
+        assert!(e.headers().is_none());
+    }
+
+    #[test]
+    fn http_error_append_header() {
+        let e: HttpError = HttpError::default()
+            .with_header(http::header::SET_COOKIE, "a=1")
+            .append_header(http::header::SET_COOKIE, "b=2")
+            .append_header("invalid header", "value");
+        let cookies: Vec<_> = e
+            .headers()
+            .unwrap()
+            .get_all(http::header::SET_COOKIE)
+            .iter()
+            .collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+
+        // `with_header` replaces all appended values
+        let e = e.with_header(http::header::SET_COOKIE, "c=3");
+        assert_eq!(
+            e.headers()
+                .unwrap()
+                .get_all(http::header::SET_COOKIE)
+                .iter()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn http_error_with_content_language() {
+        let e: HttpError = HttpError::default()
+            .with_header(http::header::VARY, "Accept-Encoding, Accept-Language")
+            .with_content_language("de-CH")
+            .with_content_language("fr");
+        let headers = e.headers().unwrap();
+        assert_eq!(headers[http::header::CONTENT_LANGUAGE], "fr");
+        assert_eq!(headers.get_all(http::header::VARY).iter().count(), 1);
+
+        let e: HttpError = HttpError::default()
+            .with_content_language("en")
+            .with_content_language("en");
+        assert_eq!(
+            e.headers()
+                .unwrap()
+                .get_all(http::header::VARY)
+                .iter()
+                .count(),
+            1
+        );
+
+        let e: HttpError = HttpError::default().with_content_language("de\n");
+        assert!(e.headers().is_none());
+    }
+
+    #[test]
+    fn http_error_with_location() {
+        let e: HttpError = HttpError::from_status_code(StatusCode::CREATED)
+            .with_location("https://example.com/users/1");
+        assert_eq!(
+            e.headers().unwrap()[http::header::LOCATION],
+            "https://example.com/users/1"
+        );
+
+        let e: HttpError = HttpError::default().with_location("not a uri");
+        assert!(e.headers().is_none());
+    }
+
+    #[test]
+    fn http_error_redirect() {
+        let e = HttpError::redirect(StatusCode::SEE_OTHER, "/orders/42").unwrap();
+        assert_eq!(e.status_code(), StatusCode::SEE_OTHER);
+        assert_eq!(e.headers().unwrap()[http::header::LOCATION], "/orders/42");
+
+        let e = HttpError::redirect(StatusCode::CREATED, Uri::from_static("/orders/42")).unwrap();
+        assert_eq!(e.status_code(), StatusCode::CREATED);
+
+        assert!(HttpError::redirect(StatusCode::OK, "/orders/42").is_none());
+        assert!(HttpError::redirect(StatusCode::CONFLICT, "/orders/42").is_none());
+        assert!(HttpError::redirect(StatusCode::FOUND, "not a uri").is_none());
+    }
+
+    #[test]
+    fn http_error_with_elapsed() {
+        let e: HttpError = HttpError::default()
+            .with_elapsed(Duration::from_micros(12_345))
+            .with_server_timing("db", Duration::from_micros(2_500))
+            .with_server_timing("handler", Duration::from_micros(12_345));
+        assert_eq!(e.get::<u64>(ELAPSED_DATA_KEY), Some(12));
+        let timings: Vec<_> = e
+            .headers()
+            .unwrap()
+            .get_all("server-timing")
+            .iter()
+            .collect();
+        assert_eq!(timings, ["db;dur=2.500", "handler;dur=12.345"]);
+
+        let e: HttpError = HttpError::default().with_server_timing("invalid\n", Duration::ZERO);
+        assert!(e.headers().is_none());
+    }
+
+    #[test]
+    fn http_error_with_no_store() {
+        let e: HttpError = HttpError::default().with_no_store();
+        assert_eq!(
+            e.headers().unwrap()[http::header::CACHE_CONTROL],
+            "no-store"
+        );
+    }
+
+    #[test]
+    fn http_error_with_violations() {
+        let e: HttpError = HttpError::default()
+            .with_violation(Violation::new("name").with_code("required"))
+            .with_violations([Violation::new("/items/0").with_message("invalid item")]);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            e.violations(),
+            &[
+                Violation::new("name").with_code("required"),
+                Violation::new("/items/0").with_message("invalid item"),
+            ]
+        );
+
+        let e: HttpError = HttpError::from_violations([Violation::new("name")]);
+        assert_eq!(e.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(e.violations().len(), 1);
+
+        assert!(HttpError::default().violations().is_empty());
     }
 
     #[test]
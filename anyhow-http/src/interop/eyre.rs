@@ -0,0 +1,41 @@
+use eyre::Report;
+
+use crate::HttpError;
+
+/// Converts an eyre [`Report`] into a [`HttpError`]. A [`HttpError`] wrapped in the report is
+/// returned as is, any other report becomes the source of a `500 Internal Server Error`.
+impl From<Report> for HttpError {
+    fn from(report: Report) -> Self {
+        match report.downcast::<HttpError>() {
+            Ok(http_error) => http_error,
+            Err(report) => HttpError::default().with_source_report(report),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::http_error;
+
+    #[test]
+    fn http_error_from_eyre_report() {
+        let report = eyre::eyre!("connection refused").wrap_err("failed to load user");
+        let e = HttpError::from(report);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            format!("{:#}", e.source().unwrap()),
+            "failed to load user: connection refused"
+        );
+
+        let report = Report::new(http_error!(NOT_FOUND, "user not found"));
+        let e = HttpError::from(report);
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.reason().as_deref(), Some("user not found"));
+
+        let res: Result<(), HttpError> = (|| Err(eyre::eyre!("boom"))?)();
+        assert!(res.unwrap_err().source().is_some());
+    }
+}
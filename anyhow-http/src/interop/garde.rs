@@ -0,0 +1,48 @@
+use garde::Report;
+use http::StatusCode;
+
+use crate::{HttpError, Violation};
+
+/// Converts a garde [`Report`] into a `422 Unprocessable Entity` [`HttpError`] with one
+/// [`Violation`] per failed rule, addressed by the rendered garde path (e.g. `items[0].name`).
+impl From<Report> for HttpError {
+    fn from(report: Report) -> Self {
+        let mut violations: Vec<_> = report
+            .iter()
+            .map(|(path, err)| Violation::new(path.to_string()).with_message(err.to_string()))
+            .collect();
+        violations.sort_by(|a, b| a.field().cmp(b.field()));
+
+        HttpError::from_status_code(StatusCode::UNPROCESSABLE_ENTITY)
+            .with_violations(violations)
+            .with_source_err(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garde::{Error, Path};
+
+    use super::*;
+
+    #[test]
+    fn http_error_from_garde_report() {
+        let mut report = Report::new();
+        report.append(Path::new("name"), Error::new("length is lower than 1"));
+        report.append(
+            Path::new("items").join(0).join("id"),
+            Error::new("not a valid id"),
+        );
+
+        let e = HttpError::from(report);
+        assert_eq!(e.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            e.violations(),
+            &[
+                Violation::new("items[0].id").with_message("not a valid id"),
+                Violation::new("name").with_message("length is lower than 1"),
+            ]
+        );
+        assert!(e.source().is_some());
+    }
+}
@@ -0,0 +1,256 @@
+use bytes::Bytes;
+use http::{
+    header::{InvalidHeaderName, InvalidHeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
+    method::InvalidMethod,
+    status::InvalidStatusCode,
+    uri::InvalidUri,
+    StatusCode,
+};
+use std::sync::Arc;
+
+use crate::{
+    response::{build_response, FormatResponse, Formatter, ResponseBody},
+    HttpError,
+};
+
+impl HttpError {
+    /// Creates a buffered [`http::Response`] formatted with `F`, for servers and tests that don't
+    /// use `axum`. The body is formatted with
+    /// [`format_response_body`](FormatResponse::format_response_body) and status, headers and
+    /// extensions are set exactly like in the `axum` integration. As the response is buffered,
+    /// formatters that return a streaming body fall back to
+    /// [`format_response`](FormatResponse::format_response).
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")] {
+    /// use anyhow_http::{http_error, response::Json};
+    ///
+    /// let resp = http_error!(NOT_FOUND, "user not found").into_http_response::<Json>();
+    /// assert_eq!(resp.status(), 404);
+    /// assert_eq!(resp.body(), r#"{"error":{"message":"user not found"}}"#);
+    /// # }
+    /// ```
+    pub fn into_http_response<F: FormatResponse>(self) -> http::Response<Bytes> {
+        let body = match F::format_response_body(&self) {
+            ResponseBody::Full(bytes) => bytes,
+            ResponseBody::Streaming(_) => F::format_response(&self),
+        };
+        build_response(self, &Formatter::of::<F>(), body)
+    }
+
+    /// Recovers a [`HttpError`] from an error response. Responses created by this crate return
+    /// the original [`HttpError`]. Otherwise the status and headers are preserved and the body is
+    /// read as an error envelope of the [`Json`](crate::response::Json) formatter, see
+    /// [`from_json_envelope`](Self::from_json_envelope). Returns `None` if the response is not an
+    /// error.
+    pub fn try_from_http_response(resp: &http::Response<Bytes>) -> Option<Self> {
+        let status = resp.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return None;
+        }
+        if let Some(http_error) = resp.extensions().get::<Arc<HttpError>>() {
+            return Some(HttpError::clone(http_error));
+        }
+
+        let mut http_error = HttpError::from_json_envelope(status, resp.body());
+        let headers = resp
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != CONTENT_TYPE && *name != CONTENT_LENGTH);
+        for (name, value) in headers {
+            http_error.headers_mut().append(name, value.clone());
+        }
+        Some(http_error)
+    }
+
+    /// Creates a [`HttpError`] from a body that carries an error envelope as produced by the
+    /// [`Json`](crate::response::Json) formatter.
+    ///
+    /// The status is preserved, `message` becomes the reason and all other fields of the envelope
+    /// are added as data. If the body is not a valid envelope only the status is preserved.
+    pub fn from_json_envelope(status_code: StatusCode, body: &[u8]) -> Self {
+        let http_error = HttpError::from_status_code(status_code);
+        let Ok(serde_json::Value::Object(mut envelope)) = serde_json::from_slice(body) else {
+            return http_error;
+        };
+        let Some(serde_json::Value::Object(mut error)) = envelope.remove("error") else {
+            return http_error;
+        };
+
+        let http_error = match error.remove("message") {
+            Some(serde_json::Value::String(message)) => http_error.with_reason(message),
+            _ => http_error,
+        };
+        error
+            .into_iter()
+            .fold(http_error, |e, (k, v)| e.with_key_value(k, v))
+    }
+}
+
+/// Converts a [`http::Error`] into a `500 Internal Server Error` [`HttpError`]. These errors
+/// occur when building requests or responses from invalid parts, which is a server-side bug.
+impl From<http::Error> for HttpError {
+    fn from(err: http::Error) -> Self {
+        HttpError::from_status_code(StatusCode::INTERNAL_SERVER_ERROR).with_source_err(err)
+    }
+}
+
+macro_rules! impl_from_parse_error {
+    ($($err:ty),* $(,)?) => {
+        $(
+            /// Converts a parse error of the `http` crate into a `400 Bad Request`
+            /// [`HttpError`] with the error message as reason, as the parsed value commonly
+            /// originates from the client.
+            impl From<$err> for HttpError {
+                fn from(err: $err) -> Self {
+                    HttpError::from_status_code(StatusCode::BAD_REQUEST)
+                        .with_reason(err.to_string())
+                        .with_source_err(err)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_parse_error!(
+    InvalidHeaderName,
+    InvalidHeaderValue,
+    InvalidMethod,
+    InvalidStatusCode,
+    InvalidUri,
+);
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderValue, Method, Uri};
+
+    use super::*;
+    use crate::http_error;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn http_error_into_http_response() {
+        use crate::response::Json;
+
+        let resp = http_error!(NOT_FOUND, "user not found")
+            .with_header("x-request-id", "1234")
+            .into_http_response::<Json>();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(resp.headers()[http::header::CACHE_CONTROL], "no-store");
+        assert_eq!(resp.headers()["x-request-id"], "1234");
+        assert_eq!(resp.body(), r#"{"error":{"message":"user not found"}}"#);
+
+        let e = HttpError::try_from_http_response(&resp).unwrap();
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.reason(), Some("user not found".into()));
+    }
+
+    #[test]
+    fn http_error_into_http_response_body() {
+        struct Framed;
+        impl FormatResponse for Framed {
+            fn format_response(http_error: &HttpError) -> Bytes {
+                http_error.status_code().as_str().to_owned().into()
+            }
+            fn format_response_body(http_error: &HttpError) -> ResponseBody {
+                Bytes::from(format!("[{}]", http_error.status_code().as_str())).into()
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+        }
+
+        struct Streaming;
+        impl FormatResponse for Streaming {
+            fn format_response(http_error: &HttpError) -> Bytes {
+                http_error.status_code().as_str().to_owned().into()
+            }
+            fn format_response_body(http_error: &HttpError) -> ResponseBody {
+                ResponseBody::streaming(http_body_util::Full::new(Self::format_response(
+                    http_error,
+                )))
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+        }
+
+        let resp = http_error!(NOT_FOUND).into_http_response::<Framed>();
+        assert_eq!(resp.body(), "[404]");
+        let resp = http_error!(NOT_FOUND).into_http_response::<Streaming>();
+        assert_eq!(resp.body(), "404");
+    }
+
+    #[test]
+    fn http_error_try_from_http_response() {
+        let resp = http::Response::builder()
+            .status(StatusCode::CONFLICT)
+            .header(CONTENT_TYPE, "application/json")
+            .header("x-request-id", "1234")
+            .body(Bytes::from_static(
+                br#"{"error":{"message":"already exists","id":1}}"#,
+            ))
+            .unwrap();
+        let e = HttpError::try_from_http_response(&resp).unwrap();
+        assert_eq!(e.status_code(), StatusCode::CONFLICT);
+        assert_eq!(e.reason(), Some("already exists".into()));
+        assert_eq!(e.get::<u32>("id"), Some(1));
+        let headers = e.headers().unwrap();
+        assert_eq!(headers["x-request-id"], "1234");
+        assert!(headers.get(CONTENT_TYPE).is_none());
+
+        let resp = http::Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .body(Bytes::from_static(b"upstream unavailable"))
+            .unwrap();
+        let e = HttpError::try_from_http_response(&resp).unwrap();
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+        assert!(e.reason().is_none());
+        assert!(e.headers().is_none());
+
+        let resp = http::Response::new(Bytes::new());
+        assert!(HttpError::try_from_http_response(&resp).is_none());
+    }
+
+    #[test]
+    fn http_error_from_http_error() {
+        let err = http::Response::builder()
+            .header("invalid header", "value")
+            .body(())
+            .unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(e.reason().is_none());
+        assert!(e.source().is_some());
+    }
+
+    #[test]
+    fn http_error_from_parse_errors() {
+        let e = HttpError::from(HeaderValue::from_str("a\nb").unwrap_err());
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(e.reason(), Some("failed to parse header value".into()));
+
+        let e = HttpError::from("not a uri".parse::<Uri>().unwrap_err());
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+
+        let e = HttpError::from(Method::from_bytes(b"GE T").unwrap_err());
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+
+        let e = HttpError::from(StatusCode::from_u16(1000).unwrap_err());
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(e.reason(), Some("invalid status code".into()));
+    }
+
+    #[test]
+    fn http_error_from_parse_error_try() {
+        fn parse(value: &str) -> Result<HeaderValue, HttpError> {
+            Ok(HeaderValue::from_str(value)?)
+        }
+        assert!(parse("valid").is_ok());
+        assert_eq!(
+            parse("a\nb").unwrap_err().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
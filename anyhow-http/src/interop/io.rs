@@ -0,0 +1,45 @@
+use std::io;
+
+use crate::{classify::io_error_kind_status, HttpError};
+
+/// Converts an [`io::Error`] into a [`HttpError`] with the status code of its kind, see
+/// [`io_error_kind_status`]. The error becomes the source, so its message is not exposed.
+impl From<io::Error> for HttpError {
+    fn from(err: io::Error) -> Self {
+        HttpError::from_status_code(io_error_kind_status(err.kind())).with_source_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    #[test]
+    fn http_error_from_io_error() {
+        for (kind, status_code) in [
+            (io::ErrorKind::NotFound, StatusCode::NOT_FOUND),
+            (io::ErrorKind::PermissionDenied, StatusCode::FORBIDDEN),
+            (io::ErrorKind::TimedOut, StatusCode::GATEWAY_TIMEOUT),
+            (io::ErrorKind::BrokenPipe, StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let e = HttpError::from(io::Error::new(kind, "/etc/app.toml"));
+            assert_eq!(e.status_code(), status_code);
+            assert!(e.reason().is_none());
+            assert_eq!(e.source().unwrap().to_string(), "/etc/app.toml");
+        }
+    }
+
+    #[test]
+    fn http_error_from_io_error_through_anyhow() {
+        use anyhow::Context;
+
+        fn read() -> anyhow::Result<String> {
+            std::fs::read_to_string("/nonexistent/app.toml").context("loading the config")
+        }
+        // only explicit conversions map the kind, errors propagated through anyhow stay internal
+        let e = HttpError::from(read().unwrap_err());
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(e.source().unwrap().to_string(), "loading the config");
+    }
+}
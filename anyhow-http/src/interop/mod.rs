@@ -0,0 +1,17 @@
+//! Conversions from errors of third-party crates into [`HttpError`](crate::HttpError).
+
+#[cfg(feature = "eyre")]
+mod eyre;
+#[cfg(feature = "garde")]
+mod garde;
+mod http;
+#[cfg(feature = "io")]
+mod io;
+#[cfg(feature = "reqwest")]
+mod reqwest;
+#[cfg(feature = "json")]
+mod serde_json;
+#[cfg(feature = "sqlx")]
+mod sqlx;
+#[cfg(feature = "validator")]
+mod validator;
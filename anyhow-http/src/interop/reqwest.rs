@@ -0,0 +1,123 @@
+use http::StatusCode;
+
+use crate::HttpError;
+
+/// Converts a [`reqwest::Error`] of a call to an upstream service into a [`HttpError`].
+///
+/// Timeouts map to `504 Gateway Timeout`, errors caused by the upstream (connect, body, decode)
+/// map to `502 Bad Gateway` and failed request builds map to `500 Internal Server Error`. Errors
+/// created by [`reqwest::Response::error_for_status`] propagate the upstream status. The upstream
+/// url and status are added as `upstream_url` and `upstream_status` data.
+impl From<reqwest::Error> for HttpError {
+    fn from(err: reqwest::Error) -> Self {
+        let status_code = if err.is_timeout() {
+            StatusCode::GATEWAY_TIMEOUT
+        } else if err.is_status() {
+            err.status().unwrap_or(StatusCode::BAD_GATEWAY)
+        } else if err.is_builder() {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::BAD_GATEWAY
+        };
+
+        let mut http_error = HttpError::from_status_code(status_code);
+        if let Some(url) = err.url() {
+            http_error = http_error.with_key_value("upstream_url", url.to_string());
+        }
+        if let Some(status) = err.status() {
+            http_error = http_error.with_key_value("upstream_status", status.as_u16());
+        }
+        http_error.with_source_err(err)
+    }
+}
+
+impl HttpError {
+    /// Creates a [`HttpError`] from an upstream [`reqwest::Response`] that carries an error
+    /// envelope as produced by the [`Json`](crate::response::Json) formatter.
+    ///
+    /// The upstream status is preserved, `message` becomes the reason and all other fields of
+    /// the envelope are added as data. If the body is not a valid envelope only the status is
+    /// preserved.
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub async fn from_reqwest_response(resp: reqwest::Response) -> Self {
+        let status_code = resp.status();
+        match resp.bytes().await {
+            Ok(body) => HttpError::from_json_envelope(status_code, &body),
+            Err(_) => HttpError::from_status_code(status_code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn upstream_response(status: u16, body: &'static str) -> reqwest::Response {
+        http::Response::builder()
+            .status(status)
+            .body(body)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn http_error_from_reqwest_status_error() {
+        let err = upstream_response(404, "").error_for_status().unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.get::<u16>("upstream_status"), Some(404));
+    }
+
+    #[test]
+    fn http_error_from_reqwest_builder_error() {
+        let err = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn http_error_from_reqwest_connect_error() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let url = format!("http://{addr}/");
+        let err = reqwest::get(&url).await.unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(e.get::<String>("upstream_url"), Some(url));
+    }
+
+    #[tokio::test]
+    async fn http_error_from_reqwest_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let err = reqwest::Client::new()
+            .get(format!("http://{addr}/"))
+            .timeout(Duration::from_millis(10))
+            .send()
+            .await
+            .unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn http_error_from_reqwest_response() {
+        let resp = upstream_response(
+            409,
+            r#"{"error":{"message":"user already exists","code":1234}}"#,
+        );
+        let e = HttpError::from_reqwest_response(resp).await;
+        assert_eq!(e.status_code(), StatusCode::CONFLICT);
+        assert_eq!(e.reason(), Some("user already exists".into()));
+        assert_eq!(e.get::<i32>("code"), Some(1234));
+
+        let e = HttpError::from_reqwest_response(upstream_response(503, "unavailable")).await;
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(e.reason().is_none());
+    }
+}
@@ -0,0 +1,71 @@
+use http::StatusCode;
+use serde_json::error::Category;
+
+use crate::HttpError;
+
+/// Converts a [`serde_json::Error`] into a [`HttpError`].
+///
+/// Syntax, data and EOF errors are caused by the client and map to `400 Bad Request` with the
+/// error message as reason and the position as `line` and `column` data. I/O errors map to
+/// `500 Internal Server Error`.
+impl From<serde_json::Error> for HttpError {
+    fn from(err: serde_json::Error) -> Self {
+        match err.classify() {
+            Category::Io => {
+                HttpError::from_status_code(StatusCode::INTERNAL_SERVER_ERROR).with_source_err(err)
+            }
+            Category::Syntax | Category::Data | Category::Eof => {
+                HttpError::from_status_code(StatusCode::BAD_REQUEST)
+                    .with_reason(err.to_string())
+                    .with_key_value("line", err.line())
+                    .with_key_value("column", err.column())
+                    .with_source_err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_error_from_serde_json_syntax_error() {
+        let err = serde_json::from_str::<serde_json::Value>("{\"a\":}").unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(e.reason(), Some("expected value at line 1 column 6".into()));
+        assert_eq!(e.get::<usize>("line"), Some(1));
+        assert_eq!(e.get::<usize>("column"), Some(6));
+    }
+
+    #[test]
+    fn http_error_from_serde_json_data_error() {
+        let err = serde_json::from_str::<u32>("\"nan\"").unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::BAD_REQUEST);
+        assert!(e.source().is_some());
+    }
+
+    #[test]
+    fn http_error_from_serde_json_io_error() {
+        let err = serde_json::Error::io(std::io::Error::other("broken pipe"));
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(e.reason().is_none());
+        assert!(e.get::<usize>("line").is_none());
+    }
+
+    #[test]
+    fn http_error_from_serde_json_through_anyhow() {
+        use anyhow::Context;
+
+        let err = serde_json::from_str::<serde_json::Value>("")
+            .context("parsing the cached manifest")
+            .unwrap_err();
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(e.reason().is_none());
+        assert_eq!(e.source_chain().count(), 2);
+    }
+}
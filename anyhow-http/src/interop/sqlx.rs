@@ -0,0 +1,115 @@
+use http::StatusCode;
+use sqlx::error::ErrorKind;
+
+use crate::HttpError;
+
+/// Converts a [`sqlx::Error`] into a [`HttpError`].
+///
+/// [`sqlx::Error::RowNotFound`] maps to `404 Not Found` and unique violations map to
+/// `409 Conflict`. All other errors map to `500 Internal Server Error`. If the database reports
+/// a violated constraint, its name is added as `constraint` data.
+impl From<sqlx::Error> for HttpError {
+    fn from(err: sqlx::Error) -> Self {
+        let (status_code, constraint) = match &err {
+            sqlx::Error::RowNotFound => (StatusCode::NOT_FOUND, None),
+            sqlx::Error::Database(db_err) => {
+                let status_code = match db_err.kind() {
+                    ErrorKind::UniqueViolation => StatusCode::CONFLICT,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status_code, db_err.constraint().map(String::from))
+            }
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, None),
+        };
+
+        let http_error = HttpError::from_status_code(status_code);
+        let http_error = match constraint {
+            Some(constraint) => http_error.with_key_value("constraint", constraint),
+            None => http_error,
+        };
+        http_error.with_source_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::error::DatabaseError;
+    use std::{error::Error as StdError, fmt};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct MockDatabaseError {
+        unique_violation: bool,
+    }
+
+    impl fmt::Display for MockDatabaseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "mock database error")
+        }
+    }
+
+    impl StdError for MockDatabaseError {}
+
+    impl DatabaseError for MockDatabaseError {
+        fn message(&self) -> &str {
+            "mock database error"
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+
+        fn constraint(&self) -> Option<&str> {
+            Some("users_email_key")
+        }
+
+        fn kind(&self) -> ErrorKind {
+            if self.unique_violation {
+                ErrorKind::UniqueViolation
+            } else {
+                ErrorKind::Other
+            }
+        }
+    }
+
+    #[test]
+    fn http_error_from_sqlx_row_not_found() {
+        let e = HttpError::from(sqlx::Error::RowNotFound);
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert!(e.get::<String>("constraint").is_none());
+    }
+
+    #[test]
+    fn http_error_from_sqlx_unique_violation() {
+        let err = sqlx::Error::Database(Box::new(MockDatabaseError {
+            unique_violation: true,
+        }));
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            e.get::<String>("constraint"),
+            Some("users_email_key".into())
+        );
+    }
+
+    #[test]
+    fn http_error_from_sqlx_other() {
+        let err = sqlx::Error::Database(Box::new(MockDatabaseError {
+            unique_violation: false,
+        }));
+        let e = HttpError::from(err);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let e = HttpError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,82 @@
+use http::StatusCode;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::{HttpError, Violation};
+
+/// Converts [`ValidationErrors`] into a `422 Unprocessable Entity` [`HttpError`] with one
+/// [`Violation`] per failed rule. Nested fields are addressed as `parent.child` and list items as
+/// `list[0]`.
+impl From<ValidationErrors> for HttpError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut violations = Vec::new();
+        collect_violations(&errors, None, &mut violations);
+        violations.sort_by(|a, b| a.field().cmp(b.field()));
+
+        HttpError::from_status_code(StatusCode::UNPROCESSABLE_ENTITY)
+            .with_violations(violations)
+            .with_source_err(errors)
+    }
+}
+
+fn collect_violations(errors: &ValidationErrors, prefix: Option<&str>, out: &mut Vec<Violation>) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+        match kind {
+            ValidationErrorsKind::Field(errs) => {
+                out.extend(errs.iter().map(|e| {
+                    let violation = Violation::new(path.clone()).with_code(e.code.clone());
+                    match &e.message {
+                        Some(msg) => violation.with_message(msg.clone()),
+                        None => violation,
+                    }
+                }));
+            }
+            ValidationErrorsKind::Struct(errs) => collect_violations(errs, Some(&path), out),
+            ValidationErrorsKind::List(errs) => {
+                for (idx, errs) in errs {
+                    collect_violations(errs, Some(&format!("{path}[{idx}]")), out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use validator::ValidationError;
+
+    use super::*;
+
+    #[test]
+    fn http_error_from_validation_errors() {
+        let mut nested = ValidationErrors::new();
+        nested.add("city", ValidationError::new("required"));
+
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "email",
+            ValidationError::new("email").with_message(Cow::Borrowed("invalid email")),
+        );
+        errors.errors_mut().insert(
+            "address".into(),
+            ValidationErrorsKind::Struct(nested.into()),
+        );
+
+        let e = HttpError::from(errors);
+        assert_eq!(e.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            e.violations(),
+            &[
+                Violation::new("address.city").with_code("required"),
+                Violation::new("email")
+                    .with_code("email")
+                    .with_message("invalid email"),
+            ]
+        );
+        assert!(e.source().is_some());
+    }
+}
@@ -0,0 +1,100 @@
+use http::StatusCode;
+use std::borrow::Cow;
+
+use crate::HttpError;
+
+/// A type that classifies itself as an HTTP error. Implementing this trait provides a conversion
+/// into [`HttpError`] without the derive macro.
+///
+/// ```
+/// use anyhow_http::{HttpError, HttpErrorKind};
+/// use http::StatusCode;
+/// use std::borrow::Cow;
+///
+/// enum OrderError {
+///     NotFound(u64),
+///     Locked,
+/// }
+///
+/// impl HttpErrorKind for OrderError {
+///     fn status(&self) -> StatusCode {
+///         match self {
+///             Self::NotFound(_) => StatusCode::NOT_FOUND,
+///             Self::Locked => StatusCode::LOCKED,
+///         }
+///     }
+///
+///     fn code(&self) -> Option<&str> {
+///         match self {
+///             Self::NotFound(_) => Some("order_not_found"),
+///             Self::Locked => Some("order_locked"),
+///         }
+///     }
+///
+///     fn reason(&self) -> Option<Cow<'_, str>> {
+///         match self {
+///             Self::NotFound(id) => Some(format!("order {id} not found").into()),
+///             Self::Locked => None,
+///         }
+///     }
+/// }
+///
+/// let err = HttpError::from(OrderError::NotFound(42));
+/// assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+/// assert_eq!(err.reason().as_deref(), Some("order 42 not found"));
+/// assert_eq!(err.get::<String>("code").as_deref(), Some("order_not_found"));
+/// ```
+pub trait HttpErrorKind {
+    /// The status code of the error.
+    fn status(&self) -> StatusCode;
+
+    /// A machine-readable error code, emitted as `code` data. Defaults to `None`.
+    fn code(&self) -> Option<&str> {
+        None
+    }
+
+    /// The error reason. Defaults to `None`.
+    fn reason(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+impl<T: HttpErrorKind> From<T> for HttpError {
+    fn from(kind: T) -> Self {
+        let mut http_error = HttpError::from_status_code(kind.status());
+        if let Some(reason) = kind.reason() {
+            http_error.set_reason(reason.into_owned());
+        }
+        if let Some(code) = kind.code() {
+            http_error.insert_data("code", code.to_owned());
+        }
+        http_error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RateLimited;
+
+    impl HttpErrorKind for RateLimited {
+        fn status(&self) -> StatusCode {
+            StatusCode::TOO_MANY_REQUESTS
+        }
+    }
+
+    #[test]
+    fn http_error_from_kind() {
+        let e = HttpError::from(RateLimited);
+        assert_eq!(e.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(e.reason().is_none());
+        assert!(e.get::<String>("code").is_none());
+
+        let res: Result<(), HttpError> = (|| Err(RateLimited)?)();
+        assert_eq!(
+            res.unwrap_err().status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+}
@@ -7,6 +7,8 @@
 //! # Example with `axum`
 //!
 //! ```rust,no_run
+//! # #[cfg(all(feature = "axum", feature = "json"))]
+//! # mod example {
 //! use axum::{
 //!    routing::get,
 //!    response::IntoResponse,
@@ -33,19 +35,67 @@
 //!     fallible_operation()?;
 //!     Ok(())
 //! }
+//! # }
+//! # fn main() {}
 //! ```
 
+mod catalog;
+#[cfg(feature = "cookie")]
+mod cookie;
+mod data;
 mod extension;
 mod http_error;
+mod interop;
+mod kind;
+#[cfg(feature = "otel")]
+mod otel;
+mod panic;
+mod profile;
+#[cfg(feature = "json")]
+mod proto;
+#[cfg(feature = "tracing")]
+mod trace;
+mod typed;
+mod violation;
 
+pub use catalog::*;
+pub use data::{DataMap, DataValue};
 pub use extension::*;
 pub use http_error::*;
+pub use kind::*;
+pub use panic::install_panic_hook;
+pub use profile::*;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub use trace::SPAN_DATA_KEY;
+pub use typed::HttpErrorOf;
+pub use violation::*;
 
 #[doc(hidden)]
 pub mod macros;
 
+pub mod classify;
+
+pub mod client;
+
+pub mod export;
+
+pub mod report;
+
 pub mod response;
 
+pub mod sanitize;
+
+pub mod signal;
+
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub mod axum;
+
+#[cfg(feature = "derive-test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive-test-utils")))]
+pub mod test_utils;
+
 pub use http;
 
 #[cfg(feature = "derive")]
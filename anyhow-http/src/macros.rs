@@ -1,4 +1,14 @@
-/// Construct an ad-hoc [`HttpError`](super::HttpError) from a status code, optional source error and formatted reason.
+/// Construct an ad-hoc [`HttpError`](super::HttpError) from a status code, optional source error, data,
+/// headers and formatted reason.
+///
+/// The status code is either the name of a [`StatusCode`](http::StatusCode) constant or an integer
+/// literal in the range `100..=999`, e.g. the non-standard `499` used by proxies. Literals outside
+/// of the range fail to compile.
+///
+/// ```compile_fail
+/// # use anyhow_http::http_error;
+/// let err = http_error!(1000);
+/// ```
 ///
 /// ```
 /// # use anyhow::anyhow;
@@ -10,31 +20,65 @@
 ///     // with source
 ///     let source = anyhow!("source error");
 ///     Err(http_error!(BAD_REQUEST, source = source, reason = "invalid payload, code {}", CODE))?;
+///
+///     // with data and headers
+///     Err(http_error!(
+///         SERVICE_UNAVAILABLE,
+///         data = { "code" => CODE, "retryable" => true },
+///         headers = { "retry-after" => "30" },
+///         reason = "replica lagging"
+///     ))?;
+///
+///     // with a non-standard status code
+///     Err(http_error!(499, "client closed request"))?;
 ///     Ok(())
 /// }
 /// ```
 #[macro_export]
 macro_rules! http_error{
-    ($status_code:ident, $reason:literal) => {
-        $crate::HttpError::from_static($crate::http::StatusCode::$status_code, $reason)
+    (@status $status_code:ident) => {
+        $crate::http::StatusCode::$status_code
     };
-    ($status_code:ident $(, source = $src:expr)? $(, reason = $($arg:tt)*)?) => {{
-        let http_error
-            = $crate::HttpError::from_status_code($crate::http::StatusCode::$status_code)
+    (@status $status_code:literal) => {{
+        const _: () = ::core::assert!(
+            100 <= $status_code && $status_code <= 999,
+            "status codes must be in the range `100..=999`"
+        );
+        $crate::http::StatusCode::from_u16($status_code).unwrap()
+    }};
+    ($status_code:tt, $reason:literal) => {
+        $crate::HttpError::from_static($crate::http_error!(@status $status_code), $reason)
+    };
+    (
+        $status_code:tt
+        $(, source = $src:expr)?
+        $(, data = { $($key:expr => $value:expr),* $(,)? })?
+        $(, headers = { $($name:expr => $header:expr),* $(,)? })?
+        $(, reason = $($arg:tt)*)?
+    ) => {{
+        let http_error = $crate::HttpError::new()
+            .with_status_code($crate::http_error!(@status $status_code))
             $(
                 .with_source_err($src)
              )?
+            $($(
+                .with_key_value($key, $value)
+             )*)?
+            $($(
+                .with_header($name, $header)
+             )*)?
             $(
                 .with_reason(std::format!($($arg)*))
              )?;
         http_error
     }};
-    ($status_code:ident $(, $($arg:tt)*)?) => {
+    ($status_code:tt $(, $($arg:tt)*)?) => {
         $crate::http_error!($status_code $(, reason = $($arg)*)?)
     };
 }
 
-/// Shorthand macro to return early with an [`HttpError`](super::HttpError).
+/// Shorthand macro to return early with an [`HttpError`](super::HttpError). Accepts the same
+/// arguments as [`http_error!`].
 ///
 /// Example:
 /// ```
@@ -42,17 +86,223 @@ macro_rules! http_error{
 /// fn foo() -> anyhow::Result<()> {
 ///     http_error_bail!(BAD_REQUEST, "invalid payload")
 /// }
+///
+/// fn bar(id: u64) -> anyhow::Result<()> {
+///     http_error_bail!(
+///         TOO_MANY_REQUESTS,
+///         data = { "id" => id },
+///         headers = { "retry-after" => "30" },
+///         reason = "user {id} is rate limited"
+///     )
+/// }
 /// ```
 #[macro_export]
 macro_rules! http_error_bail {
-    ($status_code:ident $(, source = $src:expr)? $(, reason = $($arg:tt)*)?) => {
-        return Err($crate::http_error!($status_code $(, source = $src)? $(, reason = $($arg)*)?).into())
+    ($status_code:tt $(, $($arg:tt)*)?) => {
+        return Err($crate::http_error!($status_code $(, $($arg)*)?).into())
+    };
+}
+
+/// Maps an error to a [`HttpError`](super::HttpError) by matching it against patterns, keeping
+/// the error as source. Useful for foreign error types that can not derive
+/// [`HttpError`](super::HttpError).
+///
+/// ```
+/// # use anyhow_http::http_error_map;
+/// #[derive(Debug)]
+/// enum StoreError {
+///     NotFound,
+///     Conflict(u64),
+///     Io(std::io::Error),
+/// }
+/// # impl std::fmt::Display for StoreError {
+/// #     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// #         write!(f, "store error")
+/// #     }
+/// # }
+/// # impl std::error::Error for StoreError {}
+///
+/// let err = StoreError::Conflict(1);
+/// let err = http_error_map!(err, {
+///     StoreError::NotFound => NOT_FOUND,
+///     StoreError::Conflict(_) => CONFLICT,
+///     _ => INTERNAL_SERVER_ERROR,
+/// });
+/// assert_eq!(err.status_code(), http::StatusCode::CONFLICT);
+/// ```
+#[macro_export]
+macro_rules! http_error_map {
+    ($err:expr, { $($pat:pat $(if $guard:expr)? => $status_code:ident),+ $(,)? }) => {{
+        let err = $err;
+        let status_code = match &err {
+            $($pat $(if $guard)? => $crate::http::StatusCode::$status_code,)+
+        };
+        $crate::HttpError::from_status_code(status_code).with_source_err(err)
+    }};
+}
+
+/// Declares a module of statically defined errors as [`ErrorDescriptor`](super::ErrorDescriptor)
+/// constants with status code, reason and an optional numeric code. The module additionally
+/// provides `ALL` and `iter()` over all declared errors, e.g. for documentation or export.
+///
+/// The constants are descriptors rather than `const` [`HttpError`](crate::HttpError)s: the data
+/// of a [`HttpError`](crate::HttpError), such as the `code`, is stored in a heap-allocated map
+/// that can't be built in a const context. A descriptor is converted into a
+/// [`HttpError`](crate::HttpError) where it is used, with `into()`, `?` or
+/// [`to_http_error`](super::ErrorDescriptor::to_http_error).
+///
+/// ```
+/// # use anyhow_http::{http_error_catalog, HttpError};
+/// http_error_catalog! {
+///     pub mod errors {
+///         /// The requested user does not exist.
+///         USER_NOT_FOUND => (NOT_FOUND, "user not found", code = 1001),
+///         INVALID_INPUT => (BAD_REQUEST, "invalid input"),
+///     }
+/// }
+///
+/// fn foo() -> anyhow::Result<()> {
+///     Err(errors::USER_NOT_FOUND)?
+/// }
+///
+/// let err: HttpError = errors::USER_NOT_FOUND.into();
+/// assert_eq!(err.get::<i64>("code"), Some(1001));
+/// assert_eq!(errors::iter().count(), 2);
+/// ```
+#[macro_export]
+macro_rules! http_error_catalog {
+    (
+        $vis:vis mod $mod:ident {
+            $(
+                $(#[$meta:meta])*
+                $name:ident => ($status_code:ident, $reason:literal $(, code = $code:expr)? $(,)?)
+            ),* $(,)?
+        }
+    ) => {
+        $vis mod $mod {
+            $(
+                $(#[$meta])*
+                pub const $name: $crate::ErrorDescriptor = $crate::ErrorDescriptor {
+                    name: ::core::stringify!($name),
+                    status_code: $crate::http::StatusCode::$status_code,
+                    reason: ::core::option::Option::Some($reason),
+                    code: $crate::http_error_catalog!(@code $($code)?),
+                    data_keys: $crate::http_error_catalog!(@data_keys $($code)?),
+                };
+            )*
+
+            /// All declared errors.
+            pub const ALL: &[$crate::ErrorDescriptor] = &[$($name),*];
+
+            /// Returns an iterator over all declared errors.
+            pub fn iter() -> impl ::core::iter::Iterator<Item = &'static $crate::ErrorDescriptor> {
+                ALL.iter()
+            }
+        }
+    };
+    (@code $code:expr) => {
+        ::core::option::Option::Some($crate::ErrorCode::Numeric($code))
+    };
+    (@code) => {
+        ::core::option::Option::None
+    };
+    (@data_keys $code:expr) => {
+        &["code"]
     };
-    ($status_code:ident $(, $($arg:tt)*)?) => {
-        return Err($crate::http_error!($status_code $(, reason = $($arg)*)?).into())
+    (@data_keys) => {
+        &[]
     };
 }
 
+#[cfg(feature = "axum")]
+pub use ::axum;
+
+pub use serde_json::Value as JsonValue;
+
+/// Serializes a `data(..)` value that the derive macro computes from a reference to an enum
+/// value. Values that fail to serialize are skipped.
+pub fn to_json_value<V: serde::Serialize + ?Sized>(value: &V) -> Option<JsonValue> {
+    serde_json::to_value(value).ok()
+}
+
+/// Adds the data computed by [`to_json_value`] to a [`HttpError`](crate::HttpError) in order.
+/// Values that failed to serialize are skipped.
+pub fn with_self_data(
+    http_error: crate::HttpError,
+    data: impl IntoIterator<Item = (&'static str, Option<JsonValue>)>,
+) -> crate::HttpError {
+    data.into_iter()
+        .filter_map(|(k, v)| Some((k, v?)))
+        .fold(http_error, |http_error, (k, v)| {
+            http_error.with_json_value(k, v)
+        })
+}
+
+/// Emits a `tracing` event at `level` for a [`HttpError`](crate::HttpError) that was converted
+/// from the enum variant `variant`, if it is sampled by the
+/// [report policy](crate::report::set_report_policy). Used by the derive macro and a no-op
+/// without the `tracing` feature.
+pub fn log_http_error(level: &str, variant: &str, http_error: &crate::HttpError) {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Level;
+
+        if !crate::report::should_report(http_error) {
+            return;
+        }
+
+        let reason = http_error.reason();
+        let data = http_error
+            .data_map()
+            .and_then(|data| serde_json::to_string(data).ok());
+        macro_rules! log {
+            ($level:expr) => {
+                tracing::event!(
+                    $level,
+                    variant,
+                    status = http_error.status_code().as_u16(),
+                    reason = reason.as_deref(),
+                    data = data.as_deref(),
+                    "http error"
+                )
+            };
+        }
+        match level {
+            "trace" => log!(Level::TRACE),
+            "debug" => log!(Level::DEBUG),
+            "info" => log!(Level::INFO),
+            "warn" => log!(Level::WARN),
+            _ => log!(Level::ERROR),
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (level, variant, http_error);
+}
+
+/// Implements axum's `IntoResponse` for a type that converts into [`anyhow::Error`] by rendering
+/// it with the given formatter. Used by the derive macro and a no-op without the `axum` feature.
+#[cfg(feature = "axum")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_into_response {
+    ($ty:ty, $formatter:ty) => {
+        impl $crate::macros::axum::response::IntoResponse for $ty {
+            fn into_response(self) -> $crate::macros::axum::response::Response {
+                $crate::macros::axum::response::IntoResponse::into_response(
+                    $crate::response::HttpErrorResponse::<$formatter>::from(self),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "axum"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_into_response {
+    ($ty:ty, $formatter:ty) => {};
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -66,6 +316,27 @@ mod tests {
         assert_eq!(e.status_code, StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn http_error_status_code_literal() {
+        let e: HttpError = http_error!(499);
+        assert_eq!(e.status_code.as_u16(), 499);
+
+        let e: HttpError = http_error!(499, "client closed request");
+        assert_eq!(e.reason, Some("client closed request".into()));
+
+        let id = 1;
+        let e: HttpError = http_error!(599, data = { "id" => id }, reason = "upstream {id} failed");
+        assert_eq!(e.status_code.as_u16(), 599);
+        assert_eq!(e.get::<u64>("id"), Some(1));
+        assert_eq!(e.reason, Some("upstream 1 failed".into()));
+
+        let res: anyhow::Result<()> = (|| http_error_bail!(499))();
+        assert_eq!(
+            HttpError::from_err(res.unwrap_err()).status_code.as_u16(),
+            499
+        );
+    }
+
     #[test]
     fn http_error_only_reason() {
         let i = 1;
@@ -91,6 +362,44 @@ mod tests {
         assert_eq!(e.reason, Some("error 1".into()));
     }
 
+    #[test]
+    fn http_error_data_and_headers() {
+        let source = anyhow!("an error");
+        let e: HttpError = http_error!(
+            SERVICE_UNAVAILABLE,
+            source = source,
+            data = { "code" => 1234, "retryable" => true, },
+            headers = { http::header::RETRY_AFTER => "30" },
+            reason = "replica {} lagging",
+            2
+        );
+        assert_eq!(e.status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(e.source.is_some());
+        assert_eq!(e.get::<u32>("code"), Some(1234));
+        assert_eq!(e.get::<bool>("retryable"), Some(true));
+        assert_eq!(e.headers().unwrap()[http::header::RETRY_AFTER], "30");
+        assert_eq!(e.reason, Some("replica 2 lagging".into()));
+
+        let e: HttpError = http_error!(BAD_REQUEST, headers = { "x-error" => "1" });
+        assert_eq!(e.headers().unwrap()["x-error"], "1");
+        assert!(e.reason.is_none());
+
+        let bail = |id: u64| -> anyhow::Result<()> {
+            http_error_bail!(NOT_FOUND, data = { "id" => id }, reason = "user {id} not found")
+        };
+        let e = HttpError::from(bail(7).unwrap_err());
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(e.get::<u64>("id"), Some(7));
+        assert_eq!(e.reason, Some("user 7 not found".into()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "strict", debug_assertions))]
+    #[should_panic(expected = "non-error status code 200 OK")]
+    fn http_error_strict_non_error_status() {
+        let _ = http_error!(OK);
+    }
+
     #[test]
     fn http_error_static() {
         const ERR: HttpError = http_error!(BAD_REQUEST, "error");
@@ -98,6 +407,57 @@ mod tests {
         assert_eq!(ERR.reason, Some("error".into()));
     }
 
+    #[test]
+    fn http_error_map() {
+        let map = |err: std::io::Error| {
+            http_error_map!(err, {
+                e if e.kind() == std::io::ErrorKind::NotFound => NOT_FOUND,
+                e if e.raw_os_error().is_some() => SERVICE_UNAVAILABLE,
+                _ => INTERNAL_SERVER_ERROR,
+            })
+        };
+
+        let e = map(std::io::ErrorKind::NotFound.into());
+        assert_eq!(e.status_code, StatusCode::NOT_FOUND);
+        assert!(e
+            .source()
+            .and_then(|s| s.downcast_ref::<std::io::Error>())
+            .is_some());
+
+        let e = map(std::io::Error::from_raw_os_error(1));
+        assert_eq!(e.status_code, StatusCode::SERVICE_UNAVAILABLE);
+
+        let e = map(std::io::Error::other("error"));
+        assert_eq!(e.status_code, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    http_error_catalog! {
+        mod catalog {
+            USER_NOT_FOUND => (NOT_FOUND, "user not found", code = 1001),
+            INVALID_INPUT => (BAD_REQUEST, "invalid input"),
+        }
+    }
+
+    #[test]
+    fn http_error_catalog() {
+        assert_eq!(catalog::USER_NOT_FOUND.name, "USER_NOT_FOUND");
+        assert_eq!(catalog::USER_NOT_FOUND.status_code, StatusCode::NOT_FOUND);
+        assert_eq!(
+            catalog::USER_NOT_FOUND.code,
+            Some(crate::ErrorCode::Numeric(1001))
+        );
+        assert_eq!(catalog::USER_NOT_FOUND.data_keys, ["code"]);
+        assert_eq!(catalog::INVALID_INPUT.code, None);
+        assert!(catalog::INVALID_INPUT.data_keys.is_empty());
+
+        let names: Vec<_> = catalog::iter().map(|e| e.name).collect();
+        assert_eq!(names, ["USER_NOT_FOUND", "INVALID_INPUT"]);
+
+        let e: HttpError = catalog::INVALID_INPUT.into();
+        assert_eq!(e.status_code, StatusCode::BAD_REQUEST);
+        assert_eq!(e.reason, Some("invalid input".into()));
+    }
+
     #[test]
     fn http_error_bridge() {
         let _err: anyhow::Error = http_error!(BAD_REQUEST, "error",).into();
@@ -0,0 +1,183 @@
+use opentelemetry::{
+    trace::{get_active_span, Status},
+    KeyValue,
+};
+
+use crate::HttpError;
+
+/// Marks the active OpenTelemetry span as failed for server errors, records the status code as
+/// `http.response.status_code` and adds an `exception` event with the source chain, following
+/// the semantic conventions for exceptions. Client errors leave
+/// the span untouched, as do errors that are not sampled by the
+/// [report policy](crate::report::set_report_policy).
+pub(crate) fn record_http_error(http_error: &HttpError) {
+    if !http_error.status_code().is_server_error() || !crate::report::should_report(http_error) {
+        return;
+    }
+
+    let chain: Vec<_> = http_error.source_chain().map(ToString::to_string).collect();
+    let message = if chain.is_empty() {
+        http_error.to_string()
+    } else {
+        chain.join(": ")
+    };
+    let status_code = KeyValue::new(
+        "http.response.status_code",
+        i64::from(http_error.status_code().as_u16()),
+    );
+    let mut attributes = vec![
+        KeyValue::new("exception.type", "HttpError"),
+        KeyValue::new("exception.message", message.clone()),
+        status_code.clone(),
+    ];
+    if !chain.is_empty() {
+        attributes.push(KeyValue::new("exception.stacktrace", chain.join("\n")));
+    }
+
+    get_active_span(|span| {
+        span.set_attribute(status_code);
+        span.add_event("exception", attributes);
+        span.set_status(Status::error(message));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+    use opentelemetry::{
+        trace::{Span, SpanContext, TraceContextExt},
+        Context, Value,
+    };
+    use std::{
+        borrow::Cow,
+        sync::{Arc, Mutex},
+        time::SystemTime,
+    };
+
+    #[derive(Debug, Default)]
+    struct Recorded {
+        attributes: Vec<KeyValue>,
+        events: Vec<(String, Vec<KeyValue>)>,
+        status: Option<Status>,
+    }
+
+    /// A span that records into shared state, so tests can assert on it without an SDK.
+    #[derive(Debug)]
+    struct RecordingSpan {
+        span_context: SpanContext,
+        recorded: Arc<Mutex<Recorded>>,
+    }
+
+    impl Span for RecordingSpan {
+        fn add_event_with_timestamp<T>(
+            &mut self,
+            name: T,
+            _timestamp: SystemTime,
+            attributes: Vec<KeyValue>,
+        ) where
+            T: Into<Cow<'static, str>>,
+        {
+            let mut recorded = self.recorded.lock().unwrap();
+            recorded.events.push((name.into().into_owned(), attributes));
+        }
+
+        fn span_context(&self) -> &SpanContext {
+            &self.span_context
+        }
+
+        fn is_recording(&self) -> bool {
+            true
+        }
+
+        fn set_attribute(&mut self, attribute: KeyValue) {
+            self.recorded.lock().unwrap().attributes.push(attribute);
+        }
+
+        fn set_status(&mut self, status: Status) {
+            self.recorded.lock().unwrap().status = Some(status);
+        }
+
+        fn update_name<T>(&mut self, _new_name: T)
+        where
+            T: Into<Cow<'static, str>>,
+        {
+        }
+
+        fn add_link(&mut self, _span_context: SpanContext, _attributes: Vec<KeyValue>) {}
+
+        fn end_with_timestamp(&mut self, _timestamp: SystemTime) {}
+    }
+
+    fn record(http_error: &HttpError) -> Recorded {
+        let recorded = Arc::new(Mutex::new(Recorded::default()));
+        let span = RecordingSpan {
+            span_context: SpanContext::empty_context(),
+            recorded: Arc::clone(&recorded),
+        };
+        {
+            let _guard = Context::current_with_span(span).attach();
+            record_http_error(http_error);
+        }
+        Arc::into_inner(recorded).unwrap().into_inner().unwrap()
+    }
+
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+        attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    #[test]
+    fn record_http_error_server_error() {
+        let recorded = record(
+            &http_error!(BAD_GATEWAY, "upstream failed")
+                .with_source_err(anyhow::anyhow!("connection refused")),
+        );
+
+        assert_eq!(recorded.status, Some(Status::error("connection refused")));
+        assert_eq!(
+            attribute(&recorded.attributes, "http.response.status_code"),
+            Some(&Value::I64(502))
+        );
+
+        let [(name, attributes)] = recorded.events.as_slice() else {
+            panic!("expected one event, got {:?}", recorded.events);
+        };
+        assert_eq!(name, "exception");
+        assert_eq!(
+            attribute(attributes, "exception.type"),
+            Some(&"HttpError".into())
+        );
+        assert_eq!(
+            attribute(attributes, "exception.message"),
+            Some(&"connection refused".into())
+        );
+        assert_eq!(
+            attribute(attributes, "exception.stacktrace"),
+            Some(&"connection refused".into())
+        );
+        assert_eq!(
+            attribute(attributes, "http.response.status_code"),
+            Some(&Value::I64(502))
+        );
+    }
+
+    #[test]
+    fn record_http_error_client_error() {
+        let recorded = record(&http_error!(NOT_FOUND));
+        assert!(recorded.status.is_none());
+        assert!(recorded.attributes.is_empty());
+        assert!(recorded.events.is_empty());
+    }
+
+    #[test]
+    fn record_http_error_without_active_span() {
+        // recording must be a no-op without an active span or tracer provider
+        record_http_error(&http_error!(BAD_REQUEST));
+        record_http_error(
+            &http_error!(INTERNAL_SERVER_ERROR).with_source_err(anyhow::anyhow!("error")),
+        );
+    }
+}
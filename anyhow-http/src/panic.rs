@@ -0,0 +1,83 @@
+use std::{any::Any, io::Write, panic::Location};
+
+use http::StatusCode;
+
+use crate::{response::FormatResponse, HttpError};
+
+/// Installs a panic hook that writes panics to stderr in the shape of a `500` [`HttpError`]
+/// formatted with `F`, so crashes are reported like any other server error. The error has the
+/// reason `panic` and records the panic message as `panic` and its source location as
+/// `location` data. The panic message is kept apart from the `message` rendered by the
+/// formatters.
+///
+/// The hook replaces the previously installed one, including the default hook of the standard
+/// library.
+///
+/// ```no_run
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{install_panic_hook, response::Json};
+///
+/// install_panic_hook::<Json>();
+/// # }
+/// ```
+pub fn install_panic_hook<F: FormatResponse>() {
+    std::panic::set_hook(Box::new(|info| {
+        let http_error = panic_http_error(info.payload(), info.location());
+        let mut line = F::format_response(&http_error).to_vec();
+        line.push(b'\n');
+        let _ = std::io::stderr().write_all(&line);
+    }));
+}
+
+/// Creates the [`HttpError`] that describes a panic.
+fn panic_http_error(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> HttpError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let mut http_error = HttpError::from_status_code(StatusCode::INTERNAL_SERVER_ERROR)
+        .with_reason("panic")
+        .with_key_value("panic", message.to_owned());
+    if let Some(location) = location {
+        http_error.insert_data("location", location.to_string());
+    }
+    http_error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_http_error_from_payload() {
+        let location = Location::caller();
+        let e = panic_http_error(&"index out of bounds", Some(location));
+        assert_eq!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(e.reason(), Some("panic".into()));
+        assert_eq!(
+            e.get::<String>("panic").as_deref(),
+            Some("index out of bounds")
+        );
+        assert_eq!(e.get::<String>("location"), Some(location.to_string()));
+
+        let e = panic_http_error(&String::from("formatted 42"), None);
+        assert_eq!(e.get::<String>("panic").as_deref(), Some("formatted 42"));
+        assert!(e.get::<String>("location").is_none());
+
+        let e = panic_http_error(&42, None);
+        assert_eq!(e.get::<String>("panic").as_deref(), Some("Box<dyn Any>"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn panic_http_error_format_response() {
+        use crate::response::Json;
+
+        let e = panic_http_error(&"index out of bounds", None);
+        assert_eq!(
+            Json::format_response(&e),
+            r#"{"error":{"message":"panic","panic":"index out of bounds"}}"#
+        );
+    }
+}
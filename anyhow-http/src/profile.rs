@@ -0,0 +1,85 @@
+use std::sync::RwLock;
+
+/// The environment an application runs in, selected once at startup with [`set_profile`]. The
+/// profile gates the verbosity of the built-in formatters in one place:
+///
+/// - `Development` renders the source chain of errors in the built-in formatters: as `source` in
+///   Json (like [`DebugJson`](crate::response::DebugJson)), Problem Details and
+///   [`Logfmt`](crate::response::Logfmt) responses, and as `google.rpc.DebugInfo` detail in
+///   [`Connect`](crate::response::Connect) responses.
+/// - `Production` redacts the reason of server errors, so responses of `5xx` errors only carry
+///   their [public message](crate::HttpError::with_public_message) or the default reason of the
+///   status code.
+/// - `Staging` does neither.
+///
+/// Without a profile, formatters behave as configured individually.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{http_error, response::{FormatResponse, Json}, set_profile, Profile};
+///
+/// set_profile(Profile::Production);
+///
+/// let err = http_error!(INTERNAL_SERVER_ERROR, "connection to db-1 refused");
+/// assert_eq!(
+///     Json::format_response(&err),
+///     r#"{"error":{"message":"Internal Server Error"}}"#
+/// );
+/// let err = http_error!(NOT_FOUND, "user not found");
+/// assert_eq!(
+///     Json::format_response(&err),
+///     r#"{"error":{"message":"user not found"}}"#
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// Redacts the reason of server errors.
+    Production,
+    /// Keeps the configuration of the formatters.
+    Staging,
+    /// Renders source chains.
+    Development,
+}
+
+impl Profile {
+    /// Whether the source chain of errors is rendered.
+    pub const fn include_source_chain(self) -> bool {
+        matches!(self, Self::Development)
+    }
+
+    /// Whether the reason of server errors is redacted.
+    pub const fn redact_server_errors(self) -> bool {
+        matches!(self, Self::Production)
+    }
+}
+
+static PROFILE: RwLock<Option<Profile>> = RwLock::new(None);
+
+/// Sets the [`Profile`] of the application. This is meant to be called once at startup.
+pub fn set_profile(profile: Profile) {
+    if let Ok(mut current) = PROFILE.write() {
+        *current = Some(profile);
+    }
+}
+
+/// Returns the [`Profile`] configured with [`set_profile`], if any.
+pub fn profile() -> Option<Profile> {
+    *PROFILE.read().ok()?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_knobs() {
+        assert!(Profile::Development.include_source_chain());
+        assert!(!Profile::Staging.include_source_chain());
+        assert!(!Profile::Production.include_source_chain());
+
+        assert!(Profile::Production.redact_server_errors());
+        assert!(!Profile::Staging.redact_server_errors());
+        assert!(!Profile::Development.redact_server_errors());
+    }
+}
@@ -0,0 +1,153 @@
+//! A minimal protobuf encoder for the well-known messages that carry error details, see
+//! [`Connect`](crate::response::Connect).
+use crate::Violation;
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+
+/// Encodes a Json object as `google.protobuf.Struct`.
+pub(crate) fn encode_struct<'a>(
+    fields: impl IntoIterator<Item = (&'a str, &'a serde_json::Value)>,
+) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for (key, value) in fields {
+        let mut entry = Vec::new();
+        write_len(&mut entry, 1, key.as_bytes());
+        write_len(&mut entry, 2, &encode_value(value));
+        write_len(&mut buf, 1, &entry);
+    }
+    buf
+}
+
+/// Encodes violations as `google.rpc.BadRequest` with one field violation each. The description
+/// is the message of the violation, or its code if there is no message.
+pub(crate) fn encode_bad_request(violations: &[Violation]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for violation in violations {
+        let mut field_violation = Vec::new();
+        write_len(&mut field_violation, 1, violation.field().as_bytes());
+        if let Some(description) = violation.message().or(violation.code()) {
+            write_len(&mut field_violation, 2, description.as_bytes());
+        }
+        write_len(&mut buf, 1, &field_violation);
+    }
+    buf
+}
+
+/// Encodes a source chain as `google.rpc.DebugInfo` with one stack entry per error.
+pub(crate) fn encode_debug_info(stack_entries: &[String]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for entry in stack_entries {
+        write_len(&mut buf, 1, entry.as_bytes());
+    }
+    buf
+}
+
+/// Encodes a Json value as `google.protobuf.Value`.
+fn encode_value(value: &serde_json::Value) -> Vec<u8> {
+    use serde_json::Value;
+
+    let mut buf = Vec::new();
+    match value {
+        Value::Null => write_varint_field(&mut buf, 1, 0),
+        Value::Number(n) => {
+            write_tag(&mut buf, 2, WIRE_FIXED64);
+            buf.extend_from_slice(&n.as_f64().unwrap_or_default().to_le_bytes());
+        }
+        Value::String(s) => write_len(&mut buf, 3, s.as_bytes()),
+        Value::Bool(b) => write_varint_field(&mut buf, 4, u64::from(*b)),
+        Value::Object(o) => write_len(
+            &mut buf,
+            5,
+            &encode_struct(o.iter().map(|(k, v)| (k.as_str(), v))),
+        ),
+        Value::Array(a) => {
+            let mut list = Vec::new();
+            for v in a {
+                write_len(&mut list, 1, &encode_value(v));
+            }
+            write_len(&mut buf, 6, &list);
+        }
+    }
+    buf
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn write_tag(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    write_varint(buf, u64::from(field) << 3 | u64::from(wire_type));
+}
+
+fn write_varint_field(buf: &mut Vec<u8>, field: u32, n: u64) {
+    write_tag(buf, field, WIRE_VARINT);
+    write_varint(buf, n);
+}
+
+fn write_len(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_tag(buf, field, WIRE_LEN);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_struct_values() {
+        let data = serde_json::json!({ "retry": true });
+        let encoded = encode_struct(
+            data.as_object()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.as_str(), v)),
+        );
+        assert_eq!(encoded, b"\x0a\x0b\x0a\x05retry\x12\x02\x20\x01".to_vec());
+
+        assert_eq!(encode_value(&serde_json::Value::Null), b"\x08\x00");
+        assert_eq!(encode_value(&"a".into()), b"\x1a\x01a");
+        assert_eq!(
+            encode_value(&1.5.into()),
+            [b"\x11".as_slice(), &1.5f64.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            encode_value(&serde_json::json!([false])),
+            b"\x32\x04\x0a\x02\x20\x00"
+        );
+    }
+
+    #[test]
+    fn encode_bad_request_violations() {
+        let violations = [
+            Violation::new("email").with_message("invalid email"),
+            Violation::new("name").with_code("required"),
+        ];
+        assert_eq!(
+            encode_bad_request(&violations),
+            b"\x0a\x16\x0a\x05email\x12\x0dinvalid email\x0a\x10\x0a\x04name\x12\x08required"
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn encode_debug_info_entries() {
+        assert_eq!(
+            encode_debug_info(&["a".into(), "bc".into()]),
+            b"\x0a\x01a\x0a\x02bc".to_vec()
+        );
+    }
+
+    #[test]
+    fn write_varint_multi_byte() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300);
+        assert_eq!(buf, [0xac, 0x02]);
+    }
+}
@@ -0,0 +1,140 @@
+//! Sampling of error reports, so high-volume errors don't overwhelm tracing or OpenTelemetry.
+use http::StatusCode;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    RwLock,
+};
+
+use crate::HttpError;
+
+/// A policy that decides which errors are reported by the built-in integrations, i.e. the
+/// `tracing` events of the derive macro's `log` option and the exception events of feature
+/// `otel`. Errors are sampled deterministically: with a rate of `0.1`, the first and then every
+/// tenth error is reported.
+///
+/// ```
+/// use anyhow_http::{http_error, report::ReportPolicy};
+/// use http::StatusCode;
+///
+/// let policy = ReportPolicy::sample(1.0)
+///     .per_status(StatusCode::NOT_FOUND, 0.5)
+///     .per_status(StatusCode::UNAUTHORIZED, 0.0);
+///
+/// assert!(policy.should_report(&http_error!(NOT_FOUND)));
+/// assert!(!policy.should_report(&http_error!(NOT_FOUND)));
+/// assert!(policy.should_report(&http_error!(NOT_FOUND)));
+/// assert!(!policy.should_report(&http_error!(UNAUTHORIZED)));
+/// assert!(policy.should_report(&http_error!(INTERNAL_SERVER_ERROR)));
+/// ```
+#[derive(Debug)]
+pub struct ReportPolicy {
+    default: Sampler,
+    per_status: Vec<(StatusCode, Sampler)>,
+}
+
+impl ReportPolicy {
+    /// Creates a policy that reports errors at `rate`, between `0.0` (none) and `1.0` (all).
+    pub fn sample(rate: f64) -> Self {
+        Self {
+            default: Sampler::new(rate),
+            per_status: Vec::new(),
+        }
+    }
+
+    /// Reports errors with status code `status` at `rate` instead of the default rate.
+    pub fn per_status(mut self, status: StatusCode, rate: f64) -> Self {
+        self.per_status.retain(|(s, _)| *s != status);
+        self.per_status.push((status, Sampler::new(rate)));
+        self
+    }
+
+    /// Returns whether `http_error` is reported. Every call counts towards the sampling rate.
+    pub fn should_report(&self, http_error: &HttpError) -> bool {
+        self.per_status
+            .iter()
+            .find(|(status, _)| *status == http_error.status_code())
+            .map_or(&self.default, |(_, sampler)| sampler)
+            .sample()
+    }
+}
+
+impl Default for ReportPolicy {
+    /// Reports all errors.
+    fn default() -> Self {
+        Self::sample(1.0)
+    }
+}
+
+#[derive(Debug)]
+struct Sampler {
+    rate: f64,
+    count: AtomicU64,
+}
+
+impl Sampler {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn sample(&self) -> bool {
+        let n = self.count.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * self.rate).ceil() > (n * self.rate).ceil()
+    }
+}
+
+static REPORT_POLICY: RwLock<Option<ReportPolicy>> = RwLock::new(None);
+
+/// Sets the policy the built-in integrations use to sample error reports. All errors are
+/// reported until a policy is set. This is meant to be called once at startup.
+///
+/// ```
+/// use anyhow_http::report::{set_report_policy, ReportPolicy};
+/// use http::StatusCode;
+///
+/// set_report_policy(ReportPolicy::sample(1.0).per_status(StatusCode::NOT_FOUND, 0.01));
+/// ```
+pub fn set_report_policy(policy: ReportPolicy) {
+    if let Ok(mut report_policy) = REPORT_POLICY.write() {
+        *report_policy = Some(policy);
+    }
+}
+
+/// Returns whether `http_error` is reported according to the policy configured with
+/// [`set_report_policy`].
+pub fn should_report(http_error: &HttpError) -> bool {
+    match REPORT_POLICY.read() {
+        Ok(policy) => policy
+            .as_ref()
+            .is_none_or(|policy| policy.should_report(http_error)),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+
+    #[test]
+    fn report_policy_sample() {
+        let count = |policy: &ReportPolicy, n| {
+            (0..n)
+                .filter(|_| policy.should_report(&http_error!(BAD_REQUEST)))
+                .count()
+        };
+        assert_eq!(count(&ReportPolicy::default(), 100), 100);
+        assert_eq!(count(&ReportPolicy::sample(0.0), 100), 0);
+        assert_eq!(count(&ReportPolicy::sample(0.1), 100), 10);
+        assert_eq!(count(&ReportPolicy::sample(0.25), 100), 25);
+        assert_eq!(count(&ReportPolicy::sample(2.0), 100), 100);
+
+        let policy = ReportPolicy::sample(0.0)
+            .per_status(StatusCode::BAD_REQUEST, 0.0)
+            .per_status(StatusCode::BAD_REQUEST, 0.5);
+        assert_eq!(count(&policy, 100), 50);
+        assert!(!policy.should_report(&http_error!(NOT_FOUND)));
+    }
+}
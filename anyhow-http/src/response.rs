@@ -1,6 +1,16 @@
 //! Creating responses from [`HttpError`].
 use bytes::Bytes;
-use std::marker::PhantomData;
+use core::fmt;
+use http::{HeaderName, HeaderValue, StatusCode};
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
 use crate::http_error::HttpError;
 
@@ -8,12 +18,23 @@ use crate::http_error::HttpError;
 pub type HttpResult<T, F> = core::result::Result<T, HttpErrorResponse<F>>;
 
 /// Type representing an error response.
-#[derive(Debug)]
 pub struct HttpErrorResponse<F: FormatResponse> {
     pub http_error: HttpError,
+    /// A formatter chosen at runtime that takes precedence over `F`, see
+    /// [`dynamic`](Self::dynamic).
+    dynamic: Option<Box<dyn DynFormatResponse>>,
     _formatter: PhantomData<F>,
 }
 
+impl<F: FormatResponse> fmt::Debug for HttpErrorResponse<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpErrorResponse")
+            .field("http_error", &self.http_error)
+            .field("dynamic", &self.dynamic.is_some())
+            .finish()
+    }
+}
+
 impl<E, F> From<E> for HttpErrorResponse<F>
 where
     F: FormatResponse,
@@ -22,34 +43,542 @@ where
     fn from(e: E) -> Self {
         Self {
             http_error: HttpError::from_err(e),
+            dynamic: None,
+            _formatter: PhantomData,
+        }
+    }
+}
+
+impl<F: FormatResponse> HttpErrorResponse<F> {
+    /// Converts the error response into one that is rendered with the formatter `G`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")] {
+    /// use anyhow_http::{http_error, response::{HttpErrorResponse, Json, Logfmt}};
+    ///
+    /// let resp: HttpErrorResponse<Json> = http_error!(NOT_FOUND).into();
+    /// let resp: HttpErrorResponse<Logfmt> = resp.convert();
+    /// assert_eq!(resp.http_error.status_code(), 404);
+    /// # }
+    /// ```
+    pub fn convert<G: FormatResponse>(self) -> HttpErrorResponse<G> {
+        HttpErrorResponse {
+            http_error: self.http_error,
+            dynamic: self.dynamic,
             _formatter: PhantomData,
         }
     }
+
+    /// Renders the error response with a formatter chosen at runtime instead of `F`, e.g. per
+    /// tenant or per endpoint configuration.
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "axum", feature = "json"))] {
+    /// use anyhow_http::{
+    ///     http_error,
+    ///     response::{DynFormatResponse, Formatter, HttpJsonResult, Json, Logfmt},
+    /// };
+    /// use axum::response::IntoResponse;
+    ///
+    /// fn formatter(tenant: &str) -> Box<dyn DynFormatResponse> {
+    ///     match tenant {
+    ///         "legacy" => Box::new(Formatter::of::<Logfmt>()),
+    ///         _ => Box::new(Formatter::of::<Json>()),
+    ///     }
+    /// }
+    ///
+    /// fn handler(tenant: &str) -> HttpJsonResult<()> {
+    ///     let res: HttpJsonResult<()> = Err(http_error!(NOT_FOUND, "user not found").into());
+    ///     res.map_err(|e| e.dynamic(formatter(tenant)))
+    /// }
+    ///
+    /// let resp = handler("legacy").unwrap_err().into_response();
+    /// assert_eq!(resp.headers()["content-type"], "text/plain; charset=utf-8");
+    /// # }
+    /// ```
+    pub fn dynamic(mut self, formatter: Box<dyn DynFormatResponse>) -> Self {
+        self.dynamic = Some(formatter);
+        self
+    }
+
+    /// Formats the error response with `G` and splits it into status, headers, body and the
+    /// [`HttpError`] that is otherwise stored in the response extensions. This allows to assemble
+    /// responses on frameworks without built-in support, with the same headers as the `axum`
+    /// integration.
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")] {
+    /// use anyhow_http::{http_error, response::{HttpErrorResponse, Json}};
+    ///
+    /// let resp: HttpErrorResponse<Json> = http_error!(NOT_FOUND, "user not found").into();
+    /// let (status, headers, body, http_error) = resp.into_parts_with::<Json>();
+    /// assert_eq!(status, 404);
+    /// assert_eq!(headers["content-type"], "application/json");
+    /// assert_eq!(body, r#"{"error":{"message":"user not found"}}"#);
+    /// assert_eq!(http_error.reason().as_deref(), Some("user not found"));
+    /// # }
+    /// ```
+    pub fn into_parts_with<G: FormatResponse>(
+        self,
+    ) -> (StatusCode, http::HeaderMap, Bytes, Arc<HttpError>) {
+        let (mut parts, body) = self.http_error.into_http_response::<G>().into_parts();
+        let http_error = parts
+            .extensions
+            .remove::<Arc<HttpError>>()
+            .expect("error responses carry their `HttpError`");
+        (parts.status, parts.headers, body, http_error)
+    }
+}
+
+/// Converts the formatter of a [`HttpResult`], e.g. to render errors returned by a library as
+/// [`HttpResult<T, Json>`] with another formatter at the edge of an application.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{
+///     http_error,
+///     response::{ConvertHttpResult, HttpResult, Json, Logfmt},
+/// };
+///
+/// fn find_user(id: u64) -> HttpResult<String, Json> {
+///     Err(http_error!(NOT_FOUND, "user {id} not found"))?
+/// }
+///
+/// fn handler() -> HttpResult<String, Logfmt> {
+///     find_user(1).convert()
+/// }
+///
+/// assert_eq!(handler().unwrap_err().http_error.status_code(), 404);
+/// # }
+/// ```
+pub trait ConvertHttpResult<T> {
+    /// Converts the error response into one that is rendered with the formatter `G`.
+    fn convert<G: FormatResponse>(self) -> HttpResult<T, G>;
+}
+
+impl<T, F: FormatResponse> ConvertHttpResult<T> for HttpResult<T, F> {
+    fn convert<G: FormatResponse>(self) -> HttpResult<T, G> {
+        self.map_err(HttpErrorResponse::convert)
+    }
 }
 
 #[cfg(feature = "axum")]
 #[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
 impl<F: FormatResponse> axum::response::IntoResponse for HttpErrorResponse<F> {
     fn into_response(self) -> axum::response::Response {
-        let mut resp = (
-            self.http_error.status_code,
-            [(
-                http::header::CONTENT_TYPE,
-                http::HeaderValue::from_str(F::content_type().as_ref()).unwrap(),
-            )],
-            F::format_response(&self.http_error),
-        )
-            .into_response();
-        resp.extensions_mut()
-            .insert(std::sync::Arc::new(self.http_error));
-        resp
+        match &self.dynamic {
+            Some(formatter) => into_axum_response(self.http_error, &**formatter),
+            None => into_axum_response(self.http_error, &Formatter::of::<F>()),
+        }
+    }
+}
+
+/// Renders a bare [`HttpError`] with the formatter configured by [`set_default_formatter`],
+/// which defaults to [`Json`].
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+impl axum::response::IntoResponse for HttpError {
+    fn into_response(self) -> axum::response::Response {
+        let formatter = DEFAULT_FORMATTER
+            .read()
+            .ok()
+            .and_then(|f| *f)
+            .unwrap_or(Formatter::of::<Json>());
+        into_axum_response(self, &formatter)
+    }
+}
+
+#[cfg(all(feature = "axum", feature = "json"))]
+static DEFAULT_FORMATTER: std::sync::RwLock<Option<Formatter>> = std::sync::RwLock::new(None);
+
+/// Sets the formatter used to render bare [`HttpError`]s returned from axum handlers. This is
+/// meant to be called once at startup.
+///
+/// ```
+/// use anyhow_http::response::{set_default_formatter, Json};
+///
+/// set_default_formatter::<Json>();
+/// ```
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+pub fn set_default_formatter<F: FormatResponse>() {
+    if let Ok(mut formatter) = DEFAULT_FORMATTER.write() {
+        *formatter = Some(Formatter::of::<F>());
+    }
+}
+
+static DEFAULT_REASONS: RwLock<BTreeMap<StatusCode, Cow<'static, str>>> =
+    RwLock::new(BTreeMap::new());
+
+/// Sets the client-facing reason that the built-in formatters render for errors with the given
+/// status code that have no reason of their own, in place of the canonical reason. This is meant
+/// to be called once at startup.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{default_reason, set_default_reason}};
+/// use http::StatusCode;
+///
+/// set_default_reason(
+///     StatusCode::INTERNAL_SERVER_ERROR,
+///     "something went wrong, please contact support",
+/// );
+/// assert_eq!(
+///     default_reason(&http_error!(INTERNAL_SERVER_ERROR)).as_deref(),
+///     Some("something went wrong, please contact support")
+/// );
+/// assert_eq!(
+///     default_reason(&http_error!(INTERNAL_SERVER_ERROR, "database offline")).as_deref(),
+///     Some("database offline")
+/// );
+/// ```
+pub fn set_default_reason<S: Into<Cow<'static, str>>>(status_code: StatusCode, reason: S) {
+    if let Ok(mut reasons) = DEFAULT_REASONS.write() {
+        reasons.insert(status_code, reason.into());
+    }
+}
+
+static MAX_REASON_LEN: RwLock<Option<usize>> = RwLock::new(None);
+
+/// Sets the maximum length in bytes of the message that the built-in formatters render, see
+/// [`default_reason`]. Longer messages are truncated on a char boundary and end with an ellipsis,
+/// so that pathological reasons, e.g. with interpolated user input, don't bloat responses. This
+/// is meant to be called once at startup.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{default_reason, set_max_reason_len}};
+///
+/// set_max_reason_len(20);
+///
+/// let err = http_error!(BAD_REQUEST, "invalid name {}", "a".repeat(1024));
+/// assert_eq!(default_reason(&err).as_deref(), Some("invalid name aaaa…"));
+/// ```
+pub fn set_max_reason_len(max_len: usize) {
+    if let Ok(mut max_reason_len) = MAX_REASON_LEN.write() {
+        *max_reason_len = Some(max_len);
+    }
+}
+
+/// Returns the message to render for a [`HttpError`]: its
+/// [public message](HttpError::with_public_message), its reason, the default configured with
+/// [`set_default_reason`] or the canonical reason of the status code, in this order. The reason of
+/// server errors is skipped with [`Profile::Production`](crate::Profile::Production). The message
+/// is scrubbed with the [`Sanitizer`](crate::sanitize::Sanitizer) configured with
+/// [`set_sanitizer`](crate::sanitize::set_sanitizer) and truncated to the length configured with
+/// [`set_max_reason_len`].
+pub fn default_reason(http_error: &HttpError) -> Option<Cow<'static, str>> {
+    let reason = crate::sanitize::sanitize_reason(untruncated_reason(http_error)?);
+    match MAX_REASON_LEN.read().ok().and_then(|max_len| *max_len) {
+        Some(max_len) => Some(crate::http_error::truncate_reason(reason, max_len)),
+        None => Some(reason),
+    }
+}
+
+fn untruncated_reason(http_error: &HttpError) -> Option<Cow<'static, str>> {
+    let redact = http_error.status_code().is_server_error()
+        && crate::profile().is_some_and(crate::Profile::redact_server_errors);
+    http_error
+        .extra
+        .as_ref()
+        .and_then(|e| e.public_message.clone())
+        .or_else(|| http_error.reason().filter(|_| !redact))
+        .or_else(|| {
+            DEFAULT_REASONS
+                .read()
+                .ok()?
+                .get(&http_error.status_code())
+                .cloned()
+        })
+        .or_else(|| {
+            http_error
+                .status_code()
+                .canonical_reason()
+                .map(Cow::Borrowed)
+        })
+}
+
+/// Returns the source chain of a [`HttpError`] if the [`Profile`](crate::Profile) renders source
+/// chains and the error has a source.
+fn profile_source_chain(http_error: &HttpError) -> Option<Vec<String>> {
+    if !crate::profile().is_some_and(crate::Profile::include_source_chain) {
+        return None;
+    }
+    http_error.source()?;
+    Some(http_error.source_chain().map(|e| e.to_string()).collect())
+}
+
+/// A hook that resolves the documentation link of an error from its status code and its `code`
+/// data, if any. See [`set_docs_url_resolver`].
+pub type DocsUrlResolver = fn(StatusCode, Option<&str>) -> Option<String>;
+
+static DOCS_URL_RESOLVER: RwLock<Option<DocsUrlResolver>> = RwLock::new(None);
+
+/// Sets the hook that resolves the documentation link of errors without an explicit
+/// [docs url](HttpError::with_docs_url). The built-in Json formatters emit the link as
+/// `documentation` and as the `type` of problem details. This is meant to be called once at
+/// startup.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{docs_url, set_docs_url_resolver}};
+///
+/// set_docs_url_resolver(|status, code| {
+///     Some(format!(
+///         "https://docs.example.com/errors/{}",
+///         code.map(str::to_owned).unwrap_or_else(|| status.as_u16().to_string())
+///     ))
+/// });
+/// assert_eq!(
+///     docs_url(&http_error!(NOT_FOUND).with_key_value("code", "user_not_found")).as_deref(),
+///     Some("https://docs.example.com/errors/user_not_found")
+/// );
+/// assert_eq!(
+///     docs_url(&http_error!(NOT_FOUND)).as_deref(),
+///     Some("https://docs.example.com/errors/404")
+/// );
+/// ```
+pub fn set_docs_url_resolver(resolver: DocsUrlResolver) {
+    if let Ok(mut docs_url_resolver) = DOCS_URL_RESOLVER.write() {
+        *docs_url_resolver = Some(resolver);
+    }
+}
+
+/// Returns the documentation link of a [`HttpError`]: its
+/// [docs url](HttpError::with_docs_url) or the link resolved with the hook configured with
+/// [`set_docs_url_resolver`], in this order.
+pub fn docs_url(http_error: &HttpError) -> Option<Cow<'static, str>> {
+    if let Some(url) = http_error.get::<String>(crate::DOCS_URL_DATA_KEY) {
+        return Some(url.into());
     }
+    let resolver = (*DOCS_URL_RESOLVER.read().ok()?)?;
+    let code = http_error
+        .data_map()
+        .and_then(|data| data.get("code"))
+        .map(|code| match code.to_json().as_ref() {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        });
+    resolver(http_error.status_code(), code.as_deref()).map(Cow::Owned)
 }
 
 /// Trait for formatting error responses.
 pub trait FormatResponse {
     fn format_response(http_error: &HttpError) -> Bytes;
     fn content_type() -> mime::Mime;
+
+    /// Formats the response body. Defaults to the buffered body of
+    /// [`format_response`](Self::format_response) and can be overridden to stream large bodies.
+    fn format_response_body(http_error: &HttpError) -> ResponseBody {
+        Self::format_response(http_error).into()
+    }
+
+    /// Names of the [`HttpError`]'s headers that are emitted on the response. All other headers
+    /// are dropped. Returns `None` by default, which emits all headers.
+    fn allowed_headers() -> Option<&'static [HeaderName]> {
+        None
+    }
+
+    /// The `Cache-Control` header set on every error response, unless the [`HttpError`] sets its
+    /// own. Defaults to `no-store` so that intermediaries don't cache error responses. Returning
+    /// `None` omits the header.
+    fn cache_control() -> Option<HeaderValue> {
+        Some(HeaderValue::from_static("no-store"))
+    }
+
+    /// The `Vary` header set on every error response, for formatters whose output depends on
+    /// request headers. Returns `None` by default, which omits the header.
+    fn vary() -> Option<HeaderValue> {
+        None
+    }
+}
+
+/// The body of an error response, either buffered or streaming.
+pub enum ResponseBody {
+    Full(Bytes),
+    Streaming(UnsyncBoxBody<Bytes, BoxError>),
+}
+
+impl ResponseBody {
+    /// Creates a streaming body from any [`http_body::Body`].
+    pub fn streaming<B>(body: B) -> Self
+    where
+        B: http_body::Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        Self::Streaming(body.map_err(Into::into).boxed_unsync())
+    }
+}
+
+impl From<Bytes> for ResponseBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::Full(bytes)
+    }
+}
+
+impl fmt::Debug for ResponseBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(bytes) => f.debug_tuple("Full").field(bytes).finish(),
+            Self::Streaming(_) => f.debug_tuple("Streaming").finish(),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+impl From<ResponseBody> for axum::body::Body {
+    fn from(body: ResponseBody) -> Self {
+        match body {
+            ResponseBody::Full(bytes) => bytes.into(),
+            ResponseBody::Streaming(body) => axum::body::Body::new(body),
+        }
+    }
+}
+
+/// An object-safe variant of [`FormatResponse`], to choose the formatter of an error response at
+/// runtime, see [`HttpErrorResponse::dynamic`]. [`Formatter::of`] adapts any [`FormatResponse`]
+/// implementation, formatters that carry configuration can implement it directly.
+pub trait DynFormatResponse: Send + Sync {
+    /// Formats the response body, see [`FormatResponse::format_response_body`].
+    fn format_response_body(&self, http_error: &HttpError) -> ResponseBody;
+
+    /// The content type of the response, see [`FormatResponse::content_type`].
+    fn content_type(&self) -> mime::Mime;
+
+    /// Names of the headers that are emitted, see [`FormatResponse::allowed_headers`].
+    fn allowed_headers(&self) -> Option<&'static [HeaderName]> {
+        None
+    }
+
+    /// The `Cache-Control` header, see [`FormatResponse::cache_control`].
+    fn cache_control(&self) -> Option<HeaderValue> {
+        Some(HeaderValue::from_static("no-store"))
+    }
+
+    /// The `Vary` header, see [`FormatResponse::vary`].
+    fn vary(&self) -> Option<HeaderValue> {
+        None
+    }
+}
+
+/// The functions of a [`FormatResponse`] implementation, to select a formatter at runtime. This is
+/// the [`DynFormatResponse`] of the built-in and other statically defined formatters.
+#[derive(Clone, Copy)]
+pub struct Formatter {
+    format_response_body: fn(&HttpError) -> ResponseBody,
+    content_type: fn() -> mime::Mime,
+    allowed_headers: fn() -> Option<&'static [HeaderName]>,
+    cache_control: fn() -> Option<HeaderValue>,
+    vary: fn() -> Option<HeaderValue>,
+}
+
+impl fmt::Debug for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Formatter").finish_non_exhaustive()
+    }
+}
+
+impl Formatter {
+    /// Creates the [`Formatter`] of `F`.
+    pub const fn of<F: FormatResponse>() -> Self {
+        Self {
+            format_response_body: F::format_response_body,
+            content_type: F::content_type,
+            allowed_headers: F::allowed_headers,
+            cache_control: F::cache_control,
+            vary: F::vary,
+        }
+    }
+}
+
+impl DynFormatResponse for Formatter {
+    fn format_response_body(&self, http_error: &HttpError) -> ResponseBody {
+        (self.format_response_body)(http_error)
+    }
+
+    fn content_type(&self) -> mime::Mime {
+        (self.content_type)()
+    }
+
+    fn allowed_headers(&self) -> Option<&'static [HeaderName]> {
+        (self.allowed_headers)()
+    }
+
+    fn cache_control(&self) -> Option<HeaderValue> {
+        (self.cache_control)()
+    }
+
+    fn vary(&self) -> Option<HeaderValue> {
+        (self.vary)()
+    }
+}
+
+#[cfg(feature = "axum")]
+fn into_axum_response(
+    http_error: HttpError,
+    formatter: &dyn DynFormatResponse,
+) -> axum::response::Response {
+    let http_error = crate::axum::with_request_elapsed(http_error);
+    let body = formatter.format_response_body(&http_error);
+    let content_length = match &body {
+        ResponseBody::Full(bytes) => Some(bytes.len()),
+        ResponseBody::Streaming(_) => None,
+    }
+    .filter(|_| crate::axum::current_formatter_config().is_none_or(|c| !c.omit_content_length));
+    let mut resp = build_response(http_error, formatter, axum::body::Body::from(body));
+    // an explicit length lets compression layers skip small bodies without inspecting the body,
+    // they remove it when they compress the body
+    if let Some(content_length) = content_length {
+        resp.headers_mut().insert(
+            http::header::CONTENT_LENGTH,
+            HeaderValue::from(content_length),
+        );
+    }
+    resp
+}
+
+/// Builds the error response with the given body. This is the single formatting path of all
+/// integrations, so status, headers and extensions are set consistently.
+pub(crate) fn build_response<B>(
+    http_error: HttpError,
+    formatter: &dyn DynFormatResponse,
+    body: B,
+) -> http::Response<B> {
+    #[cfg(feature = "otel")]
+    crate::otel::record_http_error(&http_error);
+    crate::signal::emit(&http_error);
+
+    let mut resp = http::Response::new(body);
+    *resp.status_mut() = http_error.status_code;
+    resp.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        HeaderValue::from_str(formatter.content_type().as_ref()).unwrap(),
+    );
+    if let Some(cache_control) = formatter.cache_control() {
+        resp.headers_mut()
+            .insert(http::header::CACHE_CONTROL, cache_control);
+    }
+    resp.headers_mut()
+        .extend(response_headers(&http_error, formatter.allowed_headers()));
+    if let Some(vary) = formatter.vary() {
+        resp.headers_mut().append(http::header::VARY, vary);
+    }
+    resp.extensions_mut().insert(Arc::new(http_error));
+    resp
+}
+
+/// Returns the headers of `http_error` that pass the allow-list.
+fn response_headers(http_error: &HttpError, allowed: Option<&[HeaderName]>) -> http::HeaderMap {
+    let Some(headers) = http_error.headers() else {
+        return http::HeaderMap::new();
+    };
+    match allowed {
+        Some(allowed) => headers
+            .iter()
+            .filter(|(name, _)| allowed.contains(name))
+            .fold(http::HeaderMap::new(), |mut acc, (name, value)| {
+                acc.append(name, value.clone());
+                acc
+            }),
+        None => headers.clone(),
+    }
 }
 
 /// A [`HttpErrorResponse`] with configured [`Json`] formatter.
@@ -62,6 +591,176 @@ pub type HttpJsonErrorResponse = HttpErrorResponse<Json>;
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
 pub type HttpJsonResult<T> = core::result::Result<T, HttpJsonErrorResponse>;
 
+/// Responds with `200 OK` and `value` as Json body. Pairs with [`HttpJsonResult`] for the error
+/// case; failing to serialize `value` results in a `500` error.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{self, HttpJsonResult}};
+/// use axum::response::Response;
+///
+/// async fn get_user(id: u64) -> HttpJsonResult<Response> {
+///     if id != 1 {
+///         Err(http_error!(NOT_FOUND, "user not found"))?;
+///     }
+///     response::ok(serde_json::json!({ "id": id }))
+/// }
+/// ```
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+pub fn ok<T: serde::Serialize>(value: T) -> HttpJsonResult<axum::response::Response> {
+    json_response(StatusCode::OK, None, &value)
+}
+
+/// Responds with `201 Created`, a `Location` header and `value` as Json body. An invalid
+/// `location` or failing to serialize `value` results in a `500` error.
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+pub fn created<L, T>(location: L, value: T) -> HttpJsonResult<axum::response::Response>
+where
+    L: TryInto<HeaderValue>,
+    L::Error: Into<anyhow::Error>,
+    T: serde::Serialize,
+{
+    let location = location.try_into().map_err(|err| {
+        HttpError::default()
+            .with_source_err(err)
+            .with_source_context("invalid location")
+    })?;
+    json_response(StatusCode::CREATED, Some(location), &value)
+}
+
+/// Responds with `204 No Content` and an empty body.
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+pub fn no_content() -> HttpJsonResult<axum::response::Response> {
+    use axum::response::IntoResponse;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[cfg(all(feature = "axum", feature = "json"))]
+fn json_response<T: serde::Serialize>(
+    status_code: StatusCode,
+    location: Option<HeaderValue>,
+    value: &T,
+) -> HttpJsonResult<axum::response::Response> {
+    use axum::response::IntoResponse;
+
+    let body =
+        serde_json::to_vec(value).map_err(|err| HttpError::default().with_source_err(err))?;
+    let mut resp = (
+        status_code,
+        [(
+            http::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        body,
+    )
+        .into_response();
+    if let Some(location) = location {
+        resp.headers_mut().insert(http::header::LOCATION, location);
+    }
+    Ok(resp)
+}
+
+/// Wraps a success payload as `{"data": ...}`, mirroring the `{"error": ...}` body of the
+/// [`Json`] formatter. Returned from handlers in a [`HttpJsonResult`], the whole API then has one
+/// top-level body structure.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{Envelope, HttpJsonResult}};
+///
+/// async fn get_user(id: u64) -> HttpJsonResult<Envelope<serde_json::Value>> {
+///     if id != 1 {
+///         // {"error":{"message":"user not found"}}
+///         Err(http_error!(NOT_FOUND, "user not found"))?;
+///     }
+///     // {"data":{"id":1}}
+///     Ok(Envelope(serde_json::json!({ "id": id })))
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Envelope<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<T: serde::Serialize> serde::Serialize for Envelope<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut envelope = serializer.serialize_struct("Envelope", 1)?;
+        envelope.serialize_field("data", &self.0)?;
+        envelope.end()
+    }
+}
+
+/// Responds with `200 OK` and the enveloped payload as Json body. Failing to serialize the
+/// payload results in a `500` error formatted with [`Json`].
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+impl<T: serde::Serialize> axum::response::IntoResponse for Envelope<T> {
+    fn into_response(self) -> axum::response::Response {
+        match json_response(StatusCode::OK, None, &self) {
+            Ok(resp) => resp,
+            Err(err) => err.into_response(),
+        }
+    }
+}
+
+/// Adds the `service` and `debug` members configured by the current
+/// [`FormatterConfig`](crate::axum::FormatterConfig) to a Json object.
+#[cfg(all(feature = "axum", feature = "json"))]
+fn apply_formatter_config(http_error: &HttpError, object: &mut serde_json::Value) {
+    let Some(config) = crate::axum::current_formatter_config() else {
+        return;
+    };
+    if let Some(service) = &config.service {
+        object["service"] = service.as_ref().into();
+    }
+    if config.include_debug && http_error.source().is_some() {
+        object["debug"] = http_error.source_chain().map(|e| e.to_string()).collect();
+    }
+}
+
+/// Returns a data value as member of a Json response object. Pre-serialized Json is replaced by
+/// a `null` placeholder, which [`WithRawData`] writes through.
+#[cfg(feature = "json")]
+fn data_member(value: &crate::DataValue) -> serde_json::Value {
+    match value {
+        #[cfg(feature = "raw-json")]
+        crate::DataValue::RawJson(_) => serde_json::Value::Null,
+        value => value.to_json().into_owned(),
+    }
+}
+
+/// A Json object of a response that serializes the placeholders of pre-serialized data (see
+/// [`data_member`]) as the raw Json, so it isn't parsed and serialized again.
+#[cfg(feature = "json")]
+struct WithRawData<'a> {
+    object: &'a serde_json::Value,
+    #[cfg_attr(not(feature = "raw-json"), allow(dead_code))]
+    data: Option<&'a crate::DataMap>,
+}
+
+#[cfg(feature = "json")]
+impl serde::Serialize for WithRawData<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[cfg(feature = "raw-json")]
+        if let (serde_json::Value::Object(object), Some(data)) = (self.object, self.data) {
+            use serde::ser::SerializeMap;
+
+            let mut map = serializer.serialize_map(Some(object.len()))?;
+            for (k, v) in object {
+                match data.get(k).and_then(crate::DataValue::as_raw_json) {
+                    Some(raw) if v.is_null() => map.serialize_entry(k, raw)?,
+                    _ => map.serialize_entry(k, v)?,
+                }
+            }
+            return map.end();
+        }
+        self.object.serialize(serializer)
+    }
+}
+
 /// A general purpose error response that formats a [`HttpError`] as Json.
 #[cfg(feature = "json")]
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
@@ -70,76 +769,1361 @@ pub struct Json;
 
 #[cfg(feature = "json")]
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
-impl FormatResponse for Json {
-    fn format_response(http_error: &HttpError) -> Bytes {
+impl Json {
+    /// Formats a [`HttpError`] like [`format_response`](FormatResponse::format_response) but
+    /// indented. Data keys are emitted in a stable order (see [`DataMap`](crate::DataMap)), so
+    /// the output can be used for snapshot tests.
+    ///
+    /// ```
+    /// use anyhow_http::{http_error, response::Json};
+    ///
+    /// let err = http_error!(BAD_REQUEST, "invalid input").with_key_value("id", 1);
+    /// # #[cfg(not(feature = "preserve-order"))]
+    /// assert_eq!(
+    ///     Json::format_response_pretty(&err),
+    ///     "{\n  \"error\": {\n    \"id\": 1,\n    \"message\": \"invalid input\"\n  }\n}"
+    /// );
+    /// # #[cfg(feature = "preserve-order")]
+    /// # assert_eq!(
+    /// #     Json::format_response_pretty(&err),
+    /// #     "{\n  \"error\": {\n    \"message\": \"invalid input\",\n    \"id\": 1\n  }\n}"
+    /// # );
+    /// ```
+    pub fn format_response_pretty(http_error: &HttpError) -> Bytes {
+        Self::write_json(http_error, true, false)
+    }
+
+    fn write_json(http_error: &HttpError, pretty: bool, source_chain: bool) -> Bytes {
+        Self::write_json_with::<Json>(http_error, pretty, source_chain)
+    }
+
+    fn write_json_with<C: JsonConfig>(
+        http_error: &HttpError,
+        pretty: bool,
+        source_chain: bool,
+    ) -> Bytes {
         use bytes::BufMut;
-        let error_reason = http_error
-            .reason()
-            .as_deref()
-            .or_else(|| http_error.status_code().canonical_reason())
-            .map(String::from);
+        let error_reason = default_reason(http_error);
 
-        let mut resp = serde_json::json!({
-            "error": {
-                "message": error_reason,
-            },
+        let data = crate::sanitize::sanitized_data(http_error);
+
+        let mut error = serde_json::json!({
+            C::MESSAGE_KEY: error_reason,
         });
-        if let Some(data) = &http_error.data {
-            for (k, v) in data {
-                resp["error"][k] = v.clone();
+        if let Some(data) = &data {
+            for (k, v) in data.iter() {
+                error[k] = data_member(v);
+            }
+        }
+        if let Some(violations) = http_error.violation_list() {
+            error[C::ERRORS_KEY] = serde_json::to_value(violations).unwrap_or_default();
+        }
+        if let Some(url) = docs_url(http_error) {
+            error[crate::DOCS_URL_DATA_KEY] = url.as_ref().into();
+        }
+        #[cfg(feature = "axum")]
+        apply_formatter_config(http_error, &mut error);
+        let source_chain =
+            source_chain || crate::profile().is_some_and(crate::Profile::include_source_chain);
+        if source_chain && http_error.source().is_some() {
+            error["source"] = http_error.source_chain().map(|e| e.to_string()).collect();
+        }
+        let error = WithRawData {
+            object: &error,
+            data: data.as_deref(),
+        };
+        fn write<T: serde::Serialize>(
+            buf: &mut impl std::io::Write,
+            value: &T,
+            pretty: bool,
+        ) -> serde_json::Result<()> {
+            if pretty {
+                serde_json::to_writer_pretty(buf, value)
+            } else {
+                serde_json::to_writer(buf, value)
             }
         }
 
         let mut buf = bytes::BytesMut::with_capacity(128).writer();
-        if let Err(err) = serde_json::to_writer(&mut buf, &resp) {
+        let res = match C::ERROR_KEY {
+            Some(key) => write(&mut buf, &BTreeMap::from([(key, &error)]), pretty),
+            None => write(&mut buf, &error, pretty),
+        };
+        if let Err(err) = res {
             return err.to_string().into();
         }
 
         buf.into_inner().freeze()
     }
+}
+
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl FormatResponse for Json {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        Self::write_json(http_error, false, false)
+    }
 
     fn content_type() -> mime::Mime {
         mime::APPLICATION_JSON
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::http_error;
-    use http::StatusCode;
+/// The names of the members of the Json body rendered by [`JsonWith`]. The defaults are those of
+/// [`Json`].
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub trait JsonConfig {
+    /// The member that wraps the error object, `error` by default. `None` renders the error
+    /// object at the top level.
+    const ERROR_KEY: Option<&'static str> = Some("error");
+    /// The member of the error message, `message` by default.
+    const MESSAGE_KEY: &'static str = "message";
+    /// The member of the field-level violations, `errors` by default.
+    const ERRORS_KEY: &'static str = "errors";
+}
 
-    #[test]
-    #[cfg(feature = "json")]
-    fn http_error_response_json() {
-        let resp: HttpErrorResponse<Json> = http_error!(BAD_REQUEST).into();
-        assert_eq!(resp.http_error.status_code, StatusCode::BAD_REQUEST);
+#[cfg(feature = "json")]
+impl JsonConfig for Json {}
+
+/// A formatter like [`Json`] with member names configured by `C`, e.g. to adhere to an existing
+/// API contract.
+///
+/// ```
+/// use anyhow_http::{
+///     http_error,
+///     response::{FormatResponse, JsonConfig, JsonWith},
+/// };
+///
+/// #[derive(Debug)]
+/// struct Legacy;
+///
+/// impl JsonConfig for Legacy {
+///     const ERROR_KEY: Option<&'static str> = Some("err");
+///     const MESSAGE_KEY: &'static str = "detail";
+/// }
+///
+/// let body = JsonWith::<Legacy>::format_response(&http_error!(NOT_FOUND, "user not found"));
+/// assert_eq!(body, r#"{"err":{"detail":"user not found"}}"#);
+/// ```
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+pub struct JsonWith<C>(PhantomData<C>);
+
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl<C: JsonConfig> FormatResponse for JsonWith<C> {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        Json::write_json_with::<C>(http_error, false, false)
     }
 
-    #[test]
-    #[cfg(all(feature = "axum", feature = "json"))]
-    fn http_error_resonse_axum_into_response() {
-        use axum::response::IntoResponse;
-        let resp: HttpErrorResponse<Json> = http_error!(BAD_REQUEST).into();
-        let resp = resp.into_response();
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    fn content_type() -> mime::Mime {
+        mime::APPLICATION_JSON
     }
+}
 
-    #[test]
-    #[cfg(feature = "json")]
-    fn http_error_json_response() {
-        let e: HttpError = http_error!(BAD_REQUEST, "invalid param",);
-        let e = e.with_key_value("ctx", "some context");
-        let e = e.with_key_value("code", 1234);
-        let body = Json::format_response(&e);
-        let content_type = Json::content_type();
-        assert_eq!(
-            body,
-            Bytes::from_static(
-                b"{\"error\":{\"code\":1234,\"ctx\":\"some context\",\"message\":\"invalid param\"}}"
-            )
-        );
-        assert_eq!(content_type, mime::APPLICATION_JSON);
+/// The environment variable that enables the source chain in [`DebugJson`] responses.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub const DEBUG_ENV_VAR: &str = "ANYHOW_HTTP_DEBUG";
+
+/// A formatter like [`Json`] that also renders the source chain of a [`HttpError`] as `source`,
+/// e.g. `["failed to load user", "connection refused"]`, if the environment variable
+/// [`ANYHOW_HTTP_DEBUG`](DEBUG_ENV_VAR) is set to `1` or `true`. The variable is read once, so
+/// the same binary can be used in development and production.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+pub struct DebugJson;
+
+#[cfg(feature = "json")]
+impl DebugJson {
+    fn enabled() -> bool {
+        static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+        *ENABLED.get_or_init(|| Self::is_enabled(std::env::var(DEBUG_ENV_VAR).ok().as_deref()))
+    }
+
+    fn is_enabled(value: Option<&str>) -> bool {
+        matches!(value.map(str::trim), Some("1") | Some("true"))
+    }
+}
+
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl FormatResponse for DebugJson {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        Json::write_json(http_error, false, Self::enabled())
+    }
+
+    fn content_type() -> mime::Mime {
+        mime::APPLICATION_JSON
+    }
+}
+
+/// A [`HttpErrorResponse`] with configured [`Connect`] formatter.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub type HttpConnectErrorResponse = HttpErrorResponse<Connect>;
+
+/// A formatter that renders a [`HttpError`] as a [Connect protocol] error.
+///
+/// The Connect error code is derived from the status code. Violations are emitted as a detail of
+/// type `google.rpc.BadRequest` and the data of the [`HttpError`] as a detail of type
+/// `google.protobuf.Struct`. Each detail carries the base64-encoded protobuf message as `value`
+/// and its Json form as `debug`.
+///
+/// [Connect protocol]: https://connectrpc.com/docs/protocol#error-end-stream
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+pub struct Connect;
+
+#[cfg(feature = "json")]
+impl Connect {
+    fn code(status_code: http::StatusCode) -> &'static str {
+        match status_code.as_u16() {
+            400 => "invalid_argument",
+            401 => "unauthenticated",
+            403 => "permission_denied",
+            404 => "not_found",
+            408 | 504 => "deadline_exceeded",
+            409 => "already_exists",
+            412 => "failed_precondition",
+            416 => "out_of_range",
+            429 => "resource_exhausted",
+            499 => "canceled",
+            501 => "unimplemented",
+            503 => "unavailable",
+            500..=599 => "internal",
+            _ => "unknown",
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+impl FormatResponse for Connect {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        use base64::{engine::general_purpose::STANDARD_NO_PAD as BASE64_NO_PAD, Engine};
+
+        let message = default_reason(http_error);
+
+        let mut resp = serde_json::json!({
+            "code": Self::code(http_error.status_code()),
+            "message": message,
+        });
+        let mut details = Vec::new();
+        if let Some(violations) = http_error.violation_list() {
+            let field_violations: Vec<_> = violations
+                .iter()
+                .map(|v| {
+                    serde_json::json!({
+                        "field": v.field(),
+                        "description": v.message().or(v.code()),
+                    })
+                })
+                .collect();
+            details.push(serde_json::json!({
+                "type": "google.rpc.BadRequest",
+                "value": BASE64_NO_PAD.encode(crate::proto::encode_bad_request(violations)),
+                "debug": { "fieldViolations": field_violations },
+            }));
+        }
+        if let Some(data) = crate::sanitize::sanitized_data(http_error) {
+            let data = data.to_json_map();
+            details.push(serde_json::json!({
+                "type": "google.protobuf.Struct",
+                "value": BASE64_NO_PAD.encode(crate::proto::encode_struct(
+                    data.iter().map(|(k, v)| (k.as_str(), v))
+                )),
+                "debug": data,
+            }));
+        }
+        if let Some(chain) = profile_source_chain(http_error) {
+            details.push(serde_json::json!({
+                "type": "google.rpc.DebugInfo",
+                "value": BASE64_NO_PAD.encode(crate::proto::encode_debug_info(&chain)),
+                "debug": { "stackEntries": chain },
+            }));
+        }
+        if !details.is_empty() {
+            resp["details"] = details.into();
+        }
+
+        serde_json::to_vec(&resp)
+            .map(Bytes::from)
+            .unwrap_or_else(|err| err.to_string().into())
+    }
+
+    fn content_type() -> mime::Mime {
+        mime::APPLICATION_JSON
+    }
+}
+
+/// A formatter that renders a [`HttpError`] as a single [logfmt] line, e.g.
+/// `status=404 reason="user not found" id=42`, for clients that are log shippers or internal
+/// tooling.
+///
+/// The reason is resolved like in [`Json`]. String data is emitted as is,
+/// [binary data](HttpError::with_binary_data) as `base64:..` and other data as Json. Values are
+/// quoted if they contain spaces, `=` or quotes.
+///
+/// ```
+/// use anyhow_http::{http_error, response::{FormatResponse, Logfmt}};
+///
+/// let err = http_error!(NOT_FOUND, "user not found").with_key_value("id", 42);
+/// assert_eq!(
+///     Logfmt::format_response(&err),
+///     r#"status=404 reason="user not found" id=42"#
+/// );
+/// ```
+///
+/// [logfmt]: https://brandur.org/logfmt
+#[derive(Debug)]
+pub struct Logfmt;
+
+impl Logfmt {
+    fn write_pair(line: &mut String, key: &str, value: &str) {
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(key.chars().map(|c| {
+            if c.is_whitespace() || c == '=' || c == '"' {
+                '_'
+            } else {
+                c
+            }
+        }));
+        line.push('=');
+        let quote = value.is_empty()
+            || value
+                .chars()
+                .any(|c| c.is_whitespace() || c == '=' || c == '"' || c == '\\');
+        if !quote {
+            line.push_str(value);
+            return;
+        }
+        line.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => line.push_str("\\\""),
+                '\\' => line.push_str("\\\\"),
+                '\n' => line.push_str("\\n"),
+                c => line.push(c),
+            }
+        }
+        line.push('"');
+    }
+}
+
+impl FormatResponse for Logfmt {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        let mut line = String::new();
+        Self::write_pair(&mut line, "status", http_error.status_code().as_str());
+        if let Some(reason) = default_reason(http_error) {
+            Self::write_pair(&mut line, "reason", &reason);
+        }
+        if let Some(data) = crate::sanitize::sanitized_data(http_error) {
+            for (k, v) in data.iter() {
+                match v {
+                    crate::DataValue::Json(serde_json::Value::String(s)) => {
+                        Self::write_pair(&mut line, k, s)
+                    }
+                    crate::DataValue::Json(v) => Self::write_pair(&mut line, k, &v.to_string()),
+                    crate::DataValue::Binary(b) => {
+                        use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+                        Self::write_pair(&mut line, k, &format!("base64:{}", BASE64.encode(b)))
+                    }
+                    #[cfg(feature = "raw-json")]
+                    crate::DataValue::RawJson(raw) => Self::write_pair(&mut line, k, raw.get()),
+                }
+            }
+        }
+        if let Some(violations) = http_error.violation_list() {
+            let violations = serde_json::to_string(violations).unwrap_or_default();
+            Self::write_pair(&mut line, "errors", &violations);
+        }
+        if let Some(chain) = profile_source_chain(http_error) {
+            Self::write_pair(&mut line, "source", &chain.join(": "));
+        }
+        line.into()
+    }
+
+    fn content_type() -> mime::Mime {
+        mime::TEXT_PLAIN_UTF_8
+    }
+}
+
+/// Post-processing of formatted error responses, applied with [`Transformed`].
+pub trait ResponseTransform {
+    /// Transforms the `body` formatted for `http_error`.
+    fn transform(http_error: &HttpError, body: Bytes) -> Bytes;
+
+    /// The content type of the transformed body. Defaults to the content type of the formatter.
+    fn content_type(content_type: mime::Mime) -> mime::Mime {
+        content_type
+    }
+}
+
+/// A formatter that formats a [`HttpError`] with `F` and post-processes the body with the
+/// [`ResponseTransform`] `T`. Headers and `Cache-Control` are those of `F`. `T` is applied to the
+/// buffered body of `F`.
+///
+/// ```
+/// use anyhow_http::{
+///     http_error,
+///     response::{FormatResponse, Logfmt, ResponseTransform, Transformed},
+///     HttpError,
+/// };
+/// use bytes::Bytes;
+///
+/// #[derive(Debug)]
+/// struct TrailingNewline;
+///
+/// impl ResponseTransform for TrailingNewline {
+///     fn transform(_: &HttpError, body: Bytes) -> Bytes {
+///         [body.as_ref(), b"\n"].concat().into()
+///     }
+/// }
+///
+/// type LogfmtLine = Transformed<Logfmt, TrailingNewline>;
+///
+/// assert_eq!(
+///     LogfmtLine::format_response(&http_error!(NOT_FOUND)),
+///     "status=404 reason=\"Not Found\"\n"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Transformed<F, T>(PhantomData<(F, T)>);
+
+impl<F: FormatResponse, T: ResponseTransform> FormatResponse for Transformed<F, T> {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        T::transform(http_error, F::format_response(http_error))
+    }
+
+    fn content_type() -> mime::Mime {
+        T::content_type(F::content_type())
+    }
+
+    fn allowed_headers() -> Option<&'static [HeaderName]> {
+        F::allowed_headers()
+    }
+
+    fn cache_control() -> Option<HeaderValue> {
+        F::cache_control()
+    }
+
+    fn vary() -> Option<HeaderValue> {
+        F::vary()
+    }
+}
+
+/// A formatter that renders a [`HttpError`] according to the request's `Accept` header as Json,
+/// Problem Details, HTML or plain text.
+///
+/// The `Accept` header is captured by [`NegotiateLayer`](crate::axum::NegotiateLayer). Each media
+/// type is weighted by the most specific range that matches it, and a quality of `0` excludes it.
+/// Without the layer or an acceptable media type the error is rendered as [`Json`]. Responses
+/// carry `Vary: accept`, so caches keep the representations apart. In Problem Details,
+/// data is rendered as extension members; data with the name of a standard member such as
+/// `status` is omitted.
+///
+/// ```
+/// use anyhow_http::{axum::NegotiateLayer, http_error, response::{HttpResult, Negotiate}};
+/// use axum::{routing::get, Router};
+///
+/// async fn handler() -> HttpResult<(), Negotiate> {
+///     Err(http_error!(NOT_FOUND))?
+/// }
+///
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .layer(NegotiateLayer::new());
+/// ```
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+#[derive(Debug)]
+pub struct Negotiate;
+
+#[cfg(all(feature = "axum", feature = "json"))]
+impl Negotiate {
+    fn negotiate() -> mime::Mime {
+        const SUPPORTED: [&str; 4] = [
+            "application/json",
+            "application/problem+json",
+            "text/html",
+            "text/plain",
+        ];
+
+        let Some(accept) = crate::axum::current_accept() else {
+            return mime::APPLICATION_JSON;
+        };
+        let ranges: Vec<(mime::Mime, f32)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let range = range.trim().parse::<mime::Mime>().ok()?;
+                let q = range
+                    .get_param("q")
+                    .and_then(|q| q.as_str().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((range, q))
+            })
+            .collect();
+
+        // Each supported type gets the quality of its most specific matching range, so
+        // `text/*;q=0.5, text/html;q=0` excludes HTML. Ties prefer the more specific and then the
+        // earlier range.
+        let mut best: Option<((f32, u8, usize), mime::Mime)> = None;
+        for supported in SUPPORTED {
+            let supported: mime::Mime = supported.parse().unwrap();
+            let Some(rank) = ranges
+                .iter()
+                .enumerate()
+                .filter_map(|(pos, (range, q))| {
+                    let specificity = Self::specificity(range, &supported)?;
+                    Some((*q, specificity, pos))
+                })
+                .reduce(|a, b| if b.1 > a.1 { b } else { a })
+            else {
+                continue;
+            };
+            let is_better = |(best, _): &((f32, u8, usize), mime::Mime)| {
+                rank.0
+                    .total_cmp(&best.0)
+                    .then(rank.1.cmp(&best.1))
+                    .then(best.2.cmp(&rank.2))
+                    .is_gt()
+            };
+            if rank.0 > 0.0 && best.as_ref().is_none_or(is_better) {
+                best = Some((rank, supported));
+            }
+        }
+        best.map(|(_, m)| m).unwrap_or(mime::APPLICATION_JSON)
+    }
+
+    /// Returns how specifically the media `range` matches `supported`, from `0` for `*/*` to `2`
+    /// for the exact type, or `None` if it does not match.
+    fn specificity(range: &mime::Mime, supported: &mime::Mime) -> Option<u8> {
+        if range.type_() == mime::STAR && range.subtype() == mime::STAR {
+            Some(0)
+        } else if range.type_() == supported.type_() && range.subtype() == mime::STAR {
+            Some(1)
+        } else if range.essence_str() == supported.essence_str() {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    fn message(http_error: &HttpError) -> String {
+        default_reason(http_error).unwrap_or_default().into_owned()
+    }
+
+    /// The members defined by RFC 9457, which data of the error must not overwrite.
+    const PROBLEM_DETAILS_MEMBERS: [&'static str; 5] =
+        ["type", "title", "status", "detail", "instance"];
+
+    fn format_problem_details(http_error: &HttpError) -> Bytes {
+        let mut resp = serde_json::json!({
+            "type": "about:blank",
+            "title": http_error.status_code().canonical_reason(),
+            "status": http_error.status_code().as_u16(),
+            "detail": Self::message(http_error),
+        });
+        let data = crate::sanitize::sanitized_data(http_error);
+        if let Some(data) = &data {
+            for (k, v) in data.iter() {
+                if !Self::PROBLEM_DETAILS_MEMBERS.contains(&k) {
+                    resp[k] = data_member(v);
+                }
+            }
+        }
+        if let Some(violations) = http_error.violation_list() {
+            resp["errors"] = serde_json::to_value(violations).unwrap_or_default();
+        }
+        if let Some(base) = crate::axum::current_formatter_config()
+            .and_then(|config| config.problem_type_base.clone())
+        {
+            resp["type"] = format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                http_error.status_code().as_u16()
+            )
+            .into();
+        }
+        if let Some(url) = docs_url(http_error) {
+            resp["type"] = url.as_ref().into();
+            if let Some(object) = resp.as_object_mut() {
+                object.remove(crate::DOCS_URL_DATA_KEY);
+            }
+        }
+        apply_formatter_config(http_error, &mut resp);
+        if let Some(chain) = profile_source_chain(http_error) {
+            resp["source"] = chain.into();
+        }
+        let resp = WithRawData {
+            object: &resp,
+            data: data.as_deref(),
+        };
+        serde_json::to_vec(&resp)
+            .map(Bytes::from)
+            .unwrap_or_else(|err| err.to_string().into())
+    }
+
+    fn format_html(http_error: &HttpError) -> Bytes {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+        let status = escape(&http_error.status_code().to_string());
+        let message = escape(&Self::message(http_error));
+        format!(
+            "<!DOCTYPE html><html><head><title>{status}</title></head>\
+             <body><h1>{status}</h1><p>{message}</p></body></html>"
+        )
+        .into()
+    }
+
+    fn format_plain(http_error: &HttpError) -> Bytes {
+        format!(
+            "{}: {}",
+            http_error.status_code(),
+            Self::message(http_error)
+        )
+        .into()
+    }
+}
+
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+impl FormatResponse for Negotiate {
+    fn format_response(http_error: &HttpError) -> Bytes {
+        let content_type = Self::negotiate();
+        match content_type.essence_str() {
+            "application/problem+json" => Self::format_problem_details(http_error),
+            "text/html" => Self::format_html(http_error),
+            "text/plain" => Self::format_plain(http_error),
+            _ => Json::format_response(http_error),
+        }
+    }
+
+    fn content_type() -> mime::Mime {
+        Self::negotiate()
+    }
+
+    fn vary() -> Option<HeaderValue> {
+        Some(HeaderValue::from_static("accept"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+    use http::StatusCode;
+
+    /// Parses a rendered body, so that comparisons do not depend on the order of object keys.
+    #[cfg(feature = "json")]
+    fn json_value(body: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(body).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_response_json() {
+        let resp: HttpErrorResponse<Json> = http_error!(BAD_REQUEST).into();
+        assert_eq!(resp.http_error.status_code, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn http_error_response_from_infallible() {
+        fn convert<E: From<std::convert::Infallible>>() -> Result<u8, E> {
+            let value: Result<u8, std::convert::Infallible> = Ok(1);
+            Ok(value?)
+        }
+        let res: HttpResult<u8, Logfmt> = convert();
+        assert_eq!(res.unwrap(), 1);
+        let res: Result<u8, HttpError> = convert();
+        assert_eq!(res.unwrap(), 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    fn http_error_resonse_axum_into_response() {
+        use axum::response::IntoResponse;
+        let resp: HttpErrorResponse<Json> = http_error!(BAD_REQUEST).into();
+        let resp = resp.into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    fn http_error_response_convert() {
+        use axum::response::IntoResponse;
+        let res: HttpResult<(), Json> = Err(http_error!(NOT_FOUND, "user not found").into());
+        let res: HttpResult<(), Logfmt> = res.convert();
+        let resp = res.unwrap_err().into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.headers()[http::header::CONTENT_TYPE],
+            mime::TEXT_PLAIN_UTF_8.as_ref()
+        );
+
+        let res: HttpResult<u8, Json> = Ok(1);
+        assert_eq!(res.convert::<Logfmt>().unwrap(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "axum")]
+    fn http_error_response_axum_headers() {
+        use axum::response::IntoResponse;
+
+        struct Restricted;
+        impl FormatResponse for Restricted {
+            fn format_response(_: &HttpError) -> Bytes {
+                Bytes::new()
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+            fn allowed_headers() -> Option<&'static [HeaderName]> {
+                Some(&[http::header::RETRY_AFTER, http::header::WWW_AUTHENTICATE])
+            }
+        }
+
+        let e = http_error!(SERVICE_UNAVAILABLE)
+            .with_header(http::header::RETRY_AFTER, "120")
+            .with_header("x-internal-trace", "abc");
+
+        let resp = HttpErrorResponse::<Restricted>::from(e).into_response();
+        assert_eq!(resp.headers()[http::header::RETRY_AFTER], "120");
+        assert!(resp.headers().get("x-internal-trace").is_none());
+        assert_eq!(resp.headers()[http::header::CONTENT_TYPE], "text/plain");
+
+        // repeated headers keep all values, with and without an allow-list
+        let e = http_error!(UNAUTHORIZED)
+            .append_header(http::header::WWW_AUTHENTICATE, "Bearer")
+            .append_header(http::header::WWW_AUTHENTICATE, "Basic")
+            .append_header(http::header::SET_COOKIE, "a=1")
+            .append_header(http::header::SET_COOKIE, "b=2");
+        let values = |resp: &axum::response::Response, name| {
+            resp.headers()
+                .get_all(name)
+                .iter()
+                .map(|v| v.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let resp = HttpErrorResponse::<Restricted>::from(e.clone()).into_response();
+        assert_eq!(
+            values(&resp, http::header::WWW_AUTHENTICATE),
+            ["Bearer", "Basic"]
+        );
+        assert!(values(&resp, http::header::SET_COOKIE).is_empty());
+        let resp = HttpErrorResponse::<Logfmt>::from(e).into_response();
+        assert_eq!(values(&resp, http::header::SET_COOKIE), ["a=1", "b=2"]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    fn http_error_axum_into_response_default_formatter() {
+        use axum::response::IntoResponse;
+
+        let resp = http_error!(BAD_REQUEST).into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+
+        struct Plain;
+        impl FormatResponse for Plain {
+            fn format_response(_: &HttpError) -> Bytes {
+                Bytes::new()
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+        }
+
+        // restores the previous formatter even if an assertion fails
+        struct ResetFormatter(Option<Formatter>);
+        impl Drop for ResetFormatter {
+            fn drop(&mut self) {
+                if let Ok(mut formatter) = DEFAULT_FORMATTER.write() {
+                    *formatter = self.0;
+                }
+            }
+        }
+
+        let _reset = ResetFormatter(*DEFAULT_FORMATTER.read().unwrap());
+        set_default_formatter::<Plain>();
+        let resp = http_error!(BAD_REQUEST).into_response();
+        assert_eq!(resp.headers()[http::header::CONTENT_TYPE], "text/plain");
+    }
+
+    #[test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    fn http_error_response_axum_cache_control() {
+        use axum::response::IntoResponse;
+
+        let resp = HttpJsonErrorResponse::from(http_error!(BAD_REQUEST)).into_response();
+        assert_eq!(resp.headers()[http::header::CACHE_CONTROL], "no-store");
+
+        let e = http_error!(BAD_REQUEST).with_header(http::header::CACHE_CONTROL, "max-age=60");
+        let resp = HttpJsonErrorResponse::from(e).into_response();
+        assert_eq!(resp.headers()[http::header::CACHE_CONTROL], "max-age=60");
+
+        struct Cacheable;
+        impl FormatResponse for Cacheable {
+            fn format_response(_: &HttpError) -> Bytes {
+                Bytes::new()
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+            fn cache_control() -> Option<HeaderValue> {
+                None
+            }
+        }
+        let resp = HttpErrorResponse::<Cacheable>::from(http_error!(BAD_REQUEST)).into_response();
+        assert!(resp.headers().get(http::header::CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "axum")]
+    async fn http_error_response_axum_streaming_body() {
+        use axum::response::IntoResponse;
+
+        struct Streaming;
+        impl FormatResponse for Streaming {
+            fn format_response(_: &HttpError) -> Bytes {
+                unreachable!()
+            }
+            fn format_response_body(http_error: &HttpError) -> ResponseBody {
+                let body = http_body_util::Full::new(Bytes::from(http_error.to_string()));
+                ResponseBody::streaming(body)
+            }
+            fn content_type() -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+        }
+
+        let resp = HttpErrorResponse::<Streaming>::from(http_error!(BAD_REQUEST, "streamed"))
+            .into_response();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "HttpError(400 Bad Request): streamed");
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    async fn http_json_result_success_helpers() {
+        let resp = ok(serde_json::json!({ "id": 1 })).unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, r#"{"id":1}"#);
+
+        let resp = created("/users/1", serde_json::json!({ "id": 1 })).unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers()[http::header::LOCATION], "/users/1");
+
+        let err = created("/users/\n", ()).unwrap_err();
+        assert_eq!(
+            err.http_error.status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let resp = no_content().unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_with_response() {
+        use crate::Violation;
+
+        struct Flat;
+
+        impl JsonConfig for Flat {
+            const ERROR_KEY: Option<&'static str> = None;
+            const MESSAGE_KEY: &'static str = "detail";
+            const ERRORS_KEY: &'static str = "violations";
+        }
+
+        let e = HttpError::from_violations([Violation::new("name").with_code("required")])
+            .with_key_value("code", 1234);
+        assert_eq!(
+            json_value(&JsonWith::<Flat>::format_response(&e)),
+            serde_json::json!({
+                "code": 1234,
+                "detail": "Unprocessable Entity",
+                "violations": [{ "code": "required", "field": "name" }],
+            })
+        );
+        assert_eq!(
+            JsonWith::<Json>::format_response(&e),
+            Json::format_response(&e)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_debug_json_response() {
+        let e = http_error!(BAD_GATEWAY, "upstream failed")
+            .with_source_err(anyhow::anyhow!("connection refused"))
+            .with_source_context("failed to load user");
+        assert_eq!(
+            json_value(&Json::write_json(&e, false, true)),
+            serde_json::json!({
+                "error": {
+                    "message": "upstream failed",
+                    "source": ["failed to load user", "connection refused"],
+                }
+            })
+        );
+        assert_eq!(
+            json_value(&Json::write_json(&http_error!(NOT_FOUND), false, true)),
+            serde_json::json!({ "error": { "message": "Not Found" } })
+        );
+
+        assert!(DebugJson::is_enabled(Some("1")));
+        assert!(DebugJson::is_enabled(Some("true")));
+        assert!(!DebugJson::is_enabled(Some("0")));
+        assert!(!DebugJson::is_enabled(None));
+        assert_eq!(DebugJson::content_type(), mime::APPLICATION_JSON);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_transformed_response() {
+        struct VendorEnvelope;
+
+        impl ResponseTransform for VendorEnvelope {
+            fn transform(http_error: &HttpError, body: Bytes) -> Bytes {
+                let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                serde_json::to_vec(&serde_json::json!({
+                    "status": http_error.status_code().as_u16(),
+                    "payload": error,
+                }))
+                .unwrap()
+                .into()
+            }
+
+            fn content_type(_: mime::Mime) -> mime::Mime {
+                "application/vnd.example+json".parse().unwrap()
+            }
+        }
+
+        type Vendor = Transformed<Json, VendorEnvelope>;
+        let body = Vendor::format_response(&http_error!(NOT_FOUND, "not found"));
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "payload": { "error": { "message": "not found" } },
+                "status": 404,
+            })
+        );
+        assert_eq!(Vendor::content_type(), "application/vnd.example+json");
+        assert_eq!(Vendor::cache_control(), Json::cache_control());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_response_into_parts_with() {
+        let resp: HttpErrorResponse<Json> = http_error!(TOO_MANY_REQUESTS, "slow down")
+            .with_header(http::header::RETRY_AFTER, "30")
+            .into();
+        let (status, headers, body, http_error) = resp.into_parts_with::<Logfmt>();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            headers[http::header::CONTENT_TYPE],
+            Logfmt::content_type().as_ref()
+        );
+        assert_eq!(headers[http::header::CACHE_CONTROL], "no-store");
+        assert_eq!(headers[http::header::RETRY_AFTER], "30");
+        assert_eq!(body, Logfmt::format_response(&http_error));
+        assert_eq!(http_error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    async fn http_error_response_dynamic() {
+        use axum::response::IntoResponse;
+
+        struct Tenant {
+            name: &'static str,
+        }
+
+        impl DynFormatResponse for Tenant {
+            fn format_response_body(&self, http_error: &HttpError) -> ResponseBody {
+                Bytes::from(format!("{}: {}", self.name, http_error.status_code())).into()
+            }
+
+            fn content_type(&self) -> mime::Mime {
+                mime::TEXT_PLAIN
+            }
+
+            fn cache_control(&self) -> Option<HeaderValue> {
+                None
+            }
+        }
+
+        let resp: HttpErrorResponse<Json> = http_error!(NOT_FOUND).into();
+        let resp = resp
+            .dynamic(Box::new(Tenant { name: "acme" }))
+            .into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()[http::header::CONTENT_TYPE], "text/plain");
+        assert!(resp.headers().get(http::header::CACHE_CONTROL).is_none());
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, "acme: 404 Not Found");
+
+        let resp: HttpErrorResponse<Json> = http_error!(NOT_FOUND).into();
+        let resp = resp
+            .dynamic(Box::new(Formatter::of::<Logfmt>()))
+            .convert::<Json>()
+            .into_response();
+        assert_eq!(
+            resp.headers()[http::header::CONTENT_TYPE],
+            Logfmt::content_type().as_ref()
+        );
+    }
+
+    #[test]
+    fn http_error_logfmt_response() {
+        let e = http_error!(BAD_REQUEST, "invalid \"name\"\nfield")
+            .with_key_value("field", "name")
+            .with_key_value("empty", "")
+            .with_key_value("limits", serde_json::json!({ "max": 10 }))
+            .with_key_value("weird key", "a=b")
+            .with_binary_data("body", Bytes::from_static(b"\xff\x00"));
+        let line = Logfmt::format_response(&e);
+        let line = std::str::from_utf8(&line).unwrap();
+        let data = line
+            .strip_prefix(r#"status=400 reason="invalid \"name\"\nfield" "#)
+            .unwrap_or_else(|| panic!("unexpected line: {line}"));
+        // The order of data pairs follows the `DataMap`, which depends on `preserve-order`.
+        let mut pairs: Vec<_> = data.split(' ').collect();
+        pairs.sort_unstable();
+        assert_eq!(
+            pairs,
+            [
+                r#"body="base64:/wA=""#,
+                r#"empty="""#,
+                "field=name",
+                r#"limits="{\"max\":10}""#,
+                r#"weird_key="a=b""#,
+            ]
+        );
+
+        let e = HttpError::from_status_code(StatusCode::from_u16(599).unwrap());
+        assert_eq!(Logfmt::format_response(&e), "status=599");
+        assert_eq!(Logfmt::content_type(), mime::TEXT_PLAIN_UTF_8);
+    }
+
+    #[tokio::test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    async fn envelope_response() {
+        use axum::response::IntoResponse;
+
+        let resp = Envelope(serde_json::json!({ "id": 1 })).into_response();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, r#"{"data":{"id":1}}"#);
+
+        let result: HttpJsonResult<Envelope<()>> = Err(http_error!(NOT_FOUND, "not found").into());
+        let resp = result.into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, r#"{"error":{"message":"not found"}}"#);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_response_default_reason() {
+        // restores the previous reason even if an assertion fails
+        struct ResetReason(StatusCode, Option<Cow<'static, str>>);
+        impl Drop for ResetReason {
+            fn drop(&mut self) {
+                if let Ok(mut reasons) = DEFAULT_REASONS.write() {
+                    match self.1.take() {
+                        Some(reason) => reasons.insert(self.0, reason),
+                        None => reasons.remove(&self.0),
+                    };
+                }
+            }
+        }
+
+        let status = StatusCode::INSUFFICIENT_STORAGE;
+        let _reset = ResetReason(
+            status,
+            DEFAULT_REASONS.read().unwrap().get(&status).cloned(),
+        );
+        set_default_reason(status, "storage is full");
+        let body = Json::format_response(&http_error!(INSUFFICIENT_STORAGE));
+        assert_eq!(body, r#"{"error":{"message":"storage is full"}}"#);
+        let body = Json::format_response(&http_error!(INSUFFICIENT_STORAGE, "quota exceeded"));
+        assert_eq!(body, r#"{"error":{"message":"quota exceeded"}}"#);
+        let body = Connect::format_response(&http_error!(INSUFFICIENT_STORAGE));
+        assert!(String::from_utf8_lossy(&body).contains(r#""message":"storage is full""#));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_response_public_message() {
+        let e = http_error!(INTERNAL_SERVER_ERROR, "connection refused")
+            .with_public_message("try again later");
+        let body = Json::format_response(&e);
+        assert_eq!(body, r#"{"error":{"message":"try again later"}}"#);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_response_docs_url() {
+        let e = http_error!(CONFLICT).with_docs_url("https://docs.example.com/conflict");
+        assert_eq!(
+            docs_url(&e).as_deref(),
+            Some("https://docs.example.com/conflict")
+        );
+        let body: serde_json::Value = serde_json::from_slice(&Json::format_response(&e)).unwrap();
+        assert_eq!(
+            body["error"]["documentation"],
+            "https://docs.example.com/conflict"
+        );
+        #[cfg(feature = "axum")]
+        {
+            let body: serde_json::Value =
+                serde_json::from_slice(&Negotiate::format_problem_details(&e)).unwrap();
+            assert_eq!(body["type"], "https://docs.example.com/conflict");
+            assert!(body.get("documentation").is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "raw-json"))]
+    fn http_error_json_response_raw_json() {
+        let raw = serde_json::value::RawValue::from_string(r#"{"b": 1, "a": [1, 2]}"#.into());
+        let e = http_error!(BAD_GATEWAY)
+            .with_raw_json("upstream", raw.unwrap())
+            .with_key_value("id", 1);
+        // the raw Json is written through as is instead of being parsed and serialized again
+        let body = Json::format_response(&e);
+        assert!(std::str::from_utf8(&body)
+            .unwrap()
+            .contains(r#""upstream":{"b": 1, "a": [1, 2]}"#));
+        assert_eq!(
+            json_value(&body),
+            serde_json::json!({
+                "error": { "id": 1, "message": "Bad Gateway", "upstream": { "b": 1, "a": [1, 2] } }
+            })
+        );
+        let line = Logfmt::format_response(&e);
+        let line = std::str::from_utf8(&line).unwrap();
+        assert!(line.starts_with(r#"status=502 reason="Bad Gateway" "#));
+        assert!(line.contains(" id=1"));
+        assert!(line.contains(r#" upstream="{\"b\": 1, \"a\": [1, 2]}""#));
+        #[cfg(feature = "axum")]
+        {
+            let body = Negotiate::format_problem_details(&e);
+            assert!(std::str::from_utf8(&body)
+                .unwrap()
+                .contains(r#""upstream":{"b": 1, "a": [1, 2]}"#));
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "axum", feature = "json"))]
+    fn http_error_problem_details_reserved_members() {
+        let e = http_error!(NOT_FOUND, "user not found")
+            .with_key_value("type", "user")
+            .with_key_value("title", "Title")
+            .with_key_value("status", 200)
+            .with_key_value("detail", "detail")
+            .with_key_value("instance", "/users/1")
+            .with_key_value("id", 1);
+        let body: serde_json::Value =
+            serde_json::from_slice(&Negotiate::format_problem_details(&e)).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "user not found",
+                "id": 1,
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", not(feature = "preserve-order")))]
+    fn http_error_json_response_deterministic() {
+        let keys = ["b", "d", "a", "e", "c"];
+        let e1 = keys
+            .iter()
+            .fold(http_error!(BAD_REQUEST), |e, k| e.with_key_value(*k, 1));
+        let e2 = keys
+            .iter()
+            .rev()
+            .fold(http_error!(BAD_REQUEST), |e, k| e.with_key_value(*k, 1));
+        assert_eq!(Json::format_response(&e1), Json::format_response(&e2));
+        assert_eq!(
+            Json::format_response(&e1),
+            r#"{"error":{"a":1,"b":1,"c":1,"d":1,"e":1,"message":"Bad Request"}}"#
+        );
+        assert_eq!(
+            Json::format_response_pretty(&e1),
+            Json::format_response_pretty(&e2)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "json", feature = "preserve-order"))]
+    fn http_error_json_response_preserve_order() {
+        let e = http_error!(BAD_REQUEST)
+            .with_key_value("b", 1)
+            .with_key_value("a", 1);
+        assert_eq!(
+            Json::format_response(&e),
+            r#"{"error":{"message":"Bad Request","b":1,"a":1}}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_response() {
+        let e: HttpError = http_error!(BAD_REQUEST, "invalid param",);
+        let e = e.with_key_value("ctx", "some context");
+        let e = e.with_key_value("code", 1234);
+        let body = Json::format_response(&e);
+        let content_type = Json::content_type();
+        assert_eq!(
+            json_value(&body),
+            serde_json::json!({
+                "error": { "code": 1234, "ctx": "some context", "message": "invalid param" }
+            })
+        );
+        assert_eq!(content_type, mime::APPLICATION_JSON);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_json_response_violations() {
+        use crate::Violation;
+        let e = HttpError::from_violations([
+            Violation::new("name").with_code("required"),
+            Violation::new("age").with_message("must be positive"),
+        ]);
+        let body = Json::format_response(&e);
+        assert_eq!(
+            json_value(&body),
+            serde_json::json!({
+                "error": {
+                    "errors": [
+                        { "code": "required", "field": "name" },
+                        { "field": "age", "message": "must be positive" },
+                    ],
+                    "message": "Unprocessable Entity",
+                }
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_connect_response() {
+        let e: HttpError = http_error!(NOT_FOUND, "user not found");
+        let body = Connect::format_response(&e);
+        assert_eq!(
+            json_value(&body),
+            serde_json::json!({ "code": "not_found", "message": "user not found" })
+        );
+
+        let e: HttpError = http_error!(SERVICE_UNAVAILABLE).with_key_value("retry", true);
+        let body = Connect::format_response(&e);
+        assert_eq!(
+            json_value(&body),
+            serde_json::json!({
+                "code": "unavailable",
+                "details": [{
+                    "debug": { "retry": true },
+                    "type": "google.protobuf.Struct",
+                    "value": "CgsKBXJldHJ5EgIgAQ",
+                }],
+                "message": "Service Unavailable",
+            })
+        );
+        assert_eq!(Connect::content_type(), mime::APPLICATION_JSON);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_connect_response_details() {
+        use crate::Violation;
+        use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+
+        let e: HttpError = http_error!(BAD_REQUEST, "invalid user")
+            .with_violation(Violation::new("email").with_message("invalid email"))
+            .with_key_value("retry", true);
+        let body: serde_json::Value =
+            serde_json::from_slice(&Connect::format_response(&e)).unwrap();
+        let details = body["details"].as_array().unwrap();
+        assert_eq!(details.len(), 2);
+
+        assert_eq!(details[0]["type"], "google.rpc.BadRequest");
+        assert_eq!(
+            details[0]["debug"],
+            serde_json::json!({
+                "fieldViolations": [{ "field": "email", "description": "invalid email" }]
+            })
+        );
+        let value = STANDARD_NO_PAD
+            .decode(details[0]["value"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(
+            value,
+            b"\x0a\x16\x0a\x05email\x12\x0dinvalid email".to_vec()
+        );
+
+        assert_eq!(details[1]["type"], "google.protobuf.Struct");
+        assert_eq!(details[1]["debug"], serde_json::json!({ "retry": true }));
+        let value = STANDARD_NO_PAD
+            .decode(details[1]["value"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(value, b"\x0a\x0b\x0a\x05retry\x12\x02\x20\x01".to_vec());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn http_error_connect_code() {
+        assert_eq!(Connect::code(StatusCode::BAD_REQUEST), "invalid_argument");
+        assert_eq!(Connect::code(StatusCode::CONFLICT), "already_exists");
+        assert_eq!(
+            Connect::code(StatusCode::GATEWAY_TIMEOUT),
+            "deadline_exceeded"
+        );
+        assert_eq!(Connect::code(StatusCode::BAD_GATEWAY), "internal");
+        assert_eq!(Connect::code(StatusCode::IM_A_TEAPOT), "unknown");
     }
 
     #[test]
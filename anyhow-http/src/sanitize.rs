@@ -0,0 +1,255 @@
+//! Scrubbing of secrets and personal data, e.g. tokens and emails, from client-visible error
+//! bodies.
+use core::fmt;
+use std::{
+    borrow::Cow,
+    sync::{Arc, RwLock},
+};
+
+use crate::{DataMap, DataValue, HttpError};
+
+type SanitizeFn = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+#[derive(Clone)]
+enum Rule {
+    Fn(SanitizeFn),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex, Cow<'static, str>),
+}
+
+/// A set of rules that scrub strings, applied in order. Once configured with [`set_sanitizer`],
+/// the built-in formatters apply it to the rendered message and the string values of the data of
+/// errors. Pre-serialized Json data is written as is.
+///
+/// ```
+/// use anyhow_http::sanitize::Sanitizer;
+///
+/// let sanitizer = Sanitizer::new().with_fn(|s| {
+///     s.contains("password").then(|| "[redacted]".to_owned())
+/// });
+/// assert_eq!(sanitizer.sanitize("invalid password hunter2"), "[redacted]");
+/// assert_eq!(sanitizer.sanitize("user not found"), "user not found");
+/// ```
+#[derive(Clone, Default)]
+pub struct Sanitizer {
+    rules: Vec<Rule>,
+}
+
+impl Sanitizer {
+    /// Creates a [`Sanitizer`] without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule that returns the scrubbed string, or `None` if there is nothing to scrub.
+    pub fn with_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.rules.push(Rule::Fn(Arc::new(f)));
+        self
+    }
+
+    /// Adds a rule that replaces all matches of `regex` with `replacement`, which may refer to
+    /// capture groups like [`Regex::replace_all`](regex::Regex::replace_all).
+    ///
+    /// ```
+    /// use anyhow_http::sanitize::Sanitizer;
+    /// use regex::Regex;
+    ///
+    /// let sanitizer = Sanitizer::new()
+    ///     .with_regex(Regex::new(r"\bacct_[0-9]+").unwrap(), "acct_[redacted]");
+    /// assert_eq!(
+    ///     sanitizer.sanitize("account acct_123 is locked"),
+    ///     "account acct_[redacted] is locked"
+    /// );
+    /// ```
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    pub fn with_regex<S>(mut self, regex: regex::Regex, replacement: S) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        self.rules.push(Rule::Regex(regex, replacement.into()));
+        self
+    }
+
+    /// Adds rules for common secrets and personal data:
+    ///
+    /// - email addresses are replaced with `[email]`,
+    /// - JSON Web Tokens with `[jwt]`,
+    /// - bearer tokens with `Bearer [redacted]`,
+    /// - values of query-style parameters named like `token`, `secret`, `password` or `api_key`
+    ///   with `[redacted]`.
+    ///
+    /// ```
+    /// use anyhow_http::sanitize::Sanitizer;
+    ///
+    /// let sanitizer = Sanitizer::new().with_builtin_patterns();
+    /// assert_eq!(
+    ///     sanitizer.sanitize("no account for jane@example.com"),
+    ///     "no account for [email]"
+    /// );
+    /// assert_eq!(
+    ///     sanitizer.sanitize("upstream rejected ?api_key=abc123&page=2"),
+    ///     "upstream rejected ?api_key=[redacted]&page=2"
+    /// );
+    /// ```
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    pub fn with_builtin_patterns(self) -> Self {
+        const PATTERNS: [(&str, &str); 4] = [
+            (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[email]"),
+            (
+                r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+                "[jwt]",
+            ),
+            (r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]+=*", "Bearer [redacted]"),
+            (
+                r"(?i)\b(token|secret|password|api[_-]?key)=[^&\s]+",
+                "${1}=[redacted]",
+            ),
+        ];
+        PATTERNS
+            .into_iter()
+            .fold(self, |sanitizer, (pattern, replacement)| {
+                let regex = regex::Regex::new(pattern).expect("built-in pattern is valid");
+                sanitizer.with_regex(regex, replacement)
+            })
+    }
+
+    /// Applies the rules to `s`.
+    pub fn sanitize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut s = Cow::Borrowed(s);
+        for rule in &self.rules {
+            match rule {
+                Rule::Fn(f) => {
+                    if let Some(scrubbed) = f(&s) {
+                        s = Cow::Owned(scrubbed);
+                    }
+                }
+                #[cfg(feature = "regex")]
+                Rule::Regex(regex, replacement) => {
+                    if let Cow::Owned(scrubbed) = regex.replace_all(&s, replacement.as_ref()) {
+                        s = Cow::Owned(scrubbed);
+                    }
+                }
+            }
+        }
+        s
+    }
+
+    /// Applies the rules to all strings of a Json value.
+    fn sanitize_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Cow::Owned(scrubbed) = self.sanitize(s) {
+                    *s = scrubbed;
+                }
+            }
+            serde_json::Value::Array(values) => {
+                values.iter_mut().for_each(|v| self.sanitize_json(v));
+            }
+            serde_json::Value::Object(object) => {
+                object.values_mut().for_each(|v| self.sanitize_json(v));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Debug for Sanitizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sanitizer")
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+static SANITIZER: RwLock<Option<Arc<Sanitizer>>> = RwLock::new(None);
+
+/// Sets the [`Sanitizer`] that the built-in formatters apply to errors before rendering them.
+/// This is meant to be called once at startup.
+pub fn set_sanitizer(sanitizer: Sanitizer) {
+    if let Ok(mut current) = SANITIZER.write() {
+        *current = Some(Arc::new(sanitizer));
+    }
+}
+
+fn current_sanitizer() -> Option<Arc<Sanitizer>> {
+    SANITIZER.read().ok()?.clone()
+}
+
+/// Applies the [`Sanitizer`] configured with [`set_sanitizer`] to a rendered message.
+pub(crate) fn sanitize_reason(reason: Cow<'static, str>) -> Cow<'static, str> {
+    let Some(sanitizer) = current_sanitizer() else {
+        return reason;
+    };
+    match sanitizer.sanitize(&reason) {
+        Cow::Borrowed(_) => reason,
+        Cow::Owned(scrubbed) => Cow::Owned(scrubbed),
+    }
+}
+
+/// Returns the data of `http_error` with the [`Sanitizer`] configured with [`set_sanitizer`]
+/// applied to its Json values.
+pub(crate) fn sanitized_data(http_error: &HttpError) -> Option<Cow<'_, DataMap>> {
+    let data = http_error.data_map()?;
+    let Some(sanitizer) = current_sanitizer() else {
+        return Some(Cow::Borrowed(data));
+    };
+    let data = data
+        .iter()
+        .map(|(k, v)| match v {
+            DataValue::Json(value) => {
+                let mut value = value.clone();
+                sanitizer.sanitize_json(&mut value);
+                (k, DataValue::Json(value))
+            }
+            v => (k, v.clone()),
+        })
+        .collect();
+    Some(Cow::Owned(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizer_rules_in_order() {
+        let sanitizer = Sanitizer::new()
+            .with_fn(|s| Some(s.replace("secret", "[redacted]")))
+            .with_fn(|s| Some(s.to_uppercase()));
+        assert_eq!(sanitizer.sanitize("a secret"), "A [REDACTED]");
+
+        let mut value = serde_json::json!({ "a": ["secret", 1], "b": { "c": "secret" } });
+        sanitizer.sanitize_json(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({ "a": ["[REDACTED]", 1], "b": { "c": "[REDACTED]" } })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn sanitizer_builtin_patterns() {
+        let sanitizer = Sanitizer::new().with_builtin_patterns();
+        assert_eq!(
+            sanitizer.sanitize("token eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.sig expired"),
+            "token [jwt] expired"
+        );
+        assert_eq!(
+            sanitizer.sanitize("header was bearer abc.DEF-123"),
+            "header was Bearer [redacted]"
+        );
+        assert_eq!(
+            sanitizer.sanitize("url /cb?password=hunter2 failed"),
+            "url /cb?password=[redacted] failed"
+        );
+        assert!(matches!(
+            sanitizer.sanitize("user not found"),
+            Cow::Borrowed("user not found")
+        ));
+    }
+}
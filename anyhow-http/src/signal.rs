@@ -0,0 +1,134 @@
+//! Export of error signals, e.g. to feed circuit breakers or SLO error budgets without a
+//! specific metrics backend.
+use http::StatusCode;
+use std::{
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use crate::HttpError;
+
+/// The class of a status code, i.e. its first digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusClass {
+    /// `1xx`
+    Informational,
+    /// `2xx`
+    Success,
+    /// `3xx`
+    Redirection,
+    /// `4xx`
+    ClientError,
+    /// `5xx`
+    ServerError,
+}
+
+impl StatusClass {
+    /// Returns the class of `status`.
+    pub fn of(status: StatusCode) -> Self {
+        match status.as_u16() / 100 {
+            1 => Self::Informational,
+            2 => Self::Success,
+            3 => Self::Redirection,
+            4 => Self::ClientError,
+            _ => Self::ServerError,
+        }
+    }
+}
+
+/// A formatted error, as reported to the sink configured with [`set_error_sink`].
+#[derive(Debug, Clone)]
+pub struct ErrorSignal {
+    status_class: StatusClass,
+    route: Option<String>,
+    timestamp: SystemTime,
+}
+
+impl ErrorSignal {
+    /// Returns the class of the status code of the error.
+    pub fn status_class(&self) -> StatusClass {
+        self.status_class
+    }
+
+    /// Returns the matched route of the request, if the error carries it, see
+    /// [`HttpError::with_request_context`](crate::HttpError::with_request_context).
+    pub fn route(&self) -> Option<&str> {
+        self.route.as_deref()
+    }
+
+    /// Returns the time the error was formatted.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+}
+
+type ErrorSink = Arc<dyn Fn(&ErrorSignal) + Send + Sync>;
+
+static ERROR_SINK: RwLock<Option<ErrorSink>> = RwLock::new(None);
+
+/// Sets the sink that is called with an [`ErrorSignal`] for every error that is formatted into a
+/// response. The sink is called on the request path, so it should only record the signal, e.g.
+/// into a sliding window. This is meant to be called once at startup.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use anyhow_http::{http_error, response::Json, signal::{set_error_sink, StatusClass}};
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// static SERVER_ERRORS: AtomicU64 = AtomicU64::new(0);
+///
+/// set_error_sink(|signal| {
+///     if signal.status_class() == StatusClass::ServerError {
+///         SERVER_ERRORS.fetch_add(1, Ordering::Relaxed);
+///     }
+/// });
+///
+/// http_error!(BAD_GATEWAY).into_http_response::<Json>();
+/// assert_eq!(SERVER_ERRORS.load(Ordering::Relaxed), 1);
+/// # }
+/// ```
+pub fn set_error_sink<F>(sink: F)
+where
+    F: Fn(&ErrorSignal) + Send + Sync + 'static,
+{
+    if let Ok(mut error_sink) = ERROR_SINK.write() {
+        *error_sink = Some(Arc::new(sink));
+    }
+}
+
+/// Reports `http_error` to the sink configured with [`set_error_sink`], if any.
+pub(crate) fn emit(http_error: &HttpError) {
+    let Some(sink) = ERROR_SINK.read().ok().and_then(|sink| sink.clone()) else {
+        return;
+    };
+    sink(&ErrorSignal {
+        status_class: StatusClass::of(http_error.status_code()),
+        route: http_error
+            .get::<serde_json::Value>("request")
+            .and_then(|request| Some(request.get("route")?.as_str()?.to_owned())),
+        timestamp: SystemTime::now(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_class_of() {
+        assert_eq!(
+            StatusClass::of(StatusCode::CONTINUE),
+            StatusClass::Informational
+        );
+        assert_eq!(StatusClass::of(StatusCode::OK), StatusClass::Success);
+        assert_eq!(StatusClass::of(StatusCode::FOUND), StatusClass::Redirection);
+        assert_eq!(
+            StatusClass::of(StatusCode::NOT_FOUND),
+            StatusClass::ClientError
+        );
+        assert_eq!(
+            StatusClass::of(StatusCode::GATEWAY_TIMEOUT),
+            StatusClass::ServerError
+        );
+    }
+}
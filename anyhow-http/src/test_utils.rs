@@ -0,0 +1,50 @@
+//! Helpers to test how errors are rendered, e.g. enums that derive `FromHttpError`.
+//!
+//! ```
+//! use anyhow_http::{http_error, response::Json, test_utils::render};
+//!
+//! let rendered = render::<Json, _>(http_error!(NOT_FOUND, "user not found"));
+//! assert_eq!(rendered.status, 404);
+//! assert_eq!(rendered.body, r#"{"error":{"message":"user not found"}}"#);
+//! assert_eq!(rendered.body_json()["error"]["message"], "user not found");
+//! ```
+use http::{HeaderMap, StatusCode};
+
+use crate::{response::FormatResponse, HttpError};
+
+/// An error response rendered by [`render`].
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The headers of the response, including `Content-Type`.
+    pub headers: HeaderMap,
+    /// The body of the response. Invalid UTF-8 is replaced.
+    pub body: String,
+}
+
+impl Rendered {
+    /// Parses the body as Json.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the body is not valid Json.
+    pub fn body_json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).expect("response body is not valid Json")
+    }
+}
+
+/// Converts `err` into a [`HttpError`] and renders it with the formatter `F`, the same way it is
+/// rendered in handlers.
+pub fn render<F, E>(err: E) -> Rendered
+where
+    F: FormatResponse,
+    E: Into<HttpError>,
+{
+    let (parts, body) = err.into().into_http_response::<F>().into_parts();
+    Rendered {
+        status: parts.status,
+        headers: parts.headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }
+}
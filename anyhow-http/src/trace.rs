@@ -0,0 +1,180 @@
+use tracing::Span;
+
+use crate::HttpError;
+
+/// The data key under which the current span is recorded, see
+/// [`HttpError::with_current_span`].
+pub const SPAN_DATA_KEY: &str = "span";
+
+impl HttpError {
+    /// Records the name of the current [`tracing`] span as `span` data, so error responses and
+    /// logs can be correlated. With feature `otel`, the `trace_id` and `span_id` of the active
+    /// OpenTelemetry span are recorded as well. Errors created with
+    /// [`from_err`](HttpError::from_err), e.g. by the `?` operator, record the span automatically.
+    /// Nothing is recorded outside of a span or if a span was recorded already.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+    pub fn with_current_span(self) -> Self {
+        if self
+            .data_map()
+            .is_some_and(|d| d.contains_key(SPAN_DATA_KEY))
+        {
+            return self;
+        }
+        let mut span = serde_json::Map::new();
+        if let Some(metadata) = Span::current().metadata() {
+            span.insert("name".to_owned(), metadata.name().into());
+        }
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::trace::TraceContextExt;
+
+            let context = opentelemetry::Context::current();
+            let span_context = context.span().span_context().clone();
+            if span_context.is_valid() {
+                span.insert(
+                    "trace_id".to_owned(),
+                    span_context.trace_id().to_string().into(),
+                );
+                span.insert(
+                    "span_id".to_owned(),
+                    span_context.span_id().to_string().into(),
+                );
+            }
+        }
+        if span.is_empty() {
+            return self;
+        }
+        self.with_key_value(SPAN_DATA_KEY, span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+    use tracing::{
+        span::{Attributes, Id, Record},
+        subscriber::with_default,
+        Event, Metadata, Subscriber,
+    };
+    use tracing_core::span::Current;
+
+    use super::*;
+    use crate::http_error;
+
+    /// A minimal subscriber that only keeps track of the entered spans.
+    #[derive(Default)]
+    struct SpanStack {
+        next_id: AtomicU64,
+        metadata: std::sync::Mutex<Vec<&'static Metadata<'static>>>,
+    }
+
+    thread_local! {
+        static STACK: RefCell<Vec<Id>> = const { RefCell::new(Vec::new()) };
+    }
+
+    impl Subscriber for SpanStack {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.metadata.lock().unwrap().push(span.metadata());
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, span: &Id) {
+            STACK.with(|s| s.borrow_mut().push(span.clone()));
+        }
+        fn exit(&self, _: &Id) {
+            STACK.with(|s| s.borrow_mut().pop());
+        }
+        fn current_span(&self) -> Current {
+            match STACK.with(|s| s.borrow().last().cloned()) {
+                Some(id) => {
+                    let metadata = self.metadata.lock().unwrap()[id.into_u64() as usize - 1];
+                    Current::new(id, metadata)
+                }
+                None => Current::none(),
+            }
+        }
+    }
+
+    #[test]
+    fn http_error_with_current_span() {
+        with_default(SpanStack::default(), || {
+            let e = http_error!(BAD_REQUEST).with_current_span();
+            assert!(e.get::<serde_json::Value>(SPAN_DATA_KEY).is_none());
+
+            let span = tracing::info_span!("handler");
+            let _guard = span.enter();
+
+            let e = http_error!(BAD_REQUEST).with_current_span();
+            let recorded: serde_json::Value = e.get(SPAN_DATA_KEY).unwrap();
+            assert_eq!(recorded, serde_json::json!({ "name": "handler" }));
+
+            let e = HttpError::from_err(anyhow::anyhow!("error"));
+            assert_eq!(
+                e.get::<serde_json::Value>(SPAN_DATA_KEY),
+                Some(recorded.clone())
+            );
+
+            // errors that already are a `HttpError` record the span, too
+            let e = HttpError::from_err(http_error!(NOT_FOUND));
+            assert_eq!(e.get::<serde_json::Value>(SPAN_DATA_KEY), Some(recorded));
+        });
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "axum")]
+    async fn http_error_from_rejection_with_current_span() {
+        use axum::{extract::FromRequestParts, Extension};
+
+        let _subscriber = tracing::subscriber::set_default(SpanStack::default());
+        let span = tracing::info_span!("extract");
+        let _guard = span.enter();
+
+        let (mut parts, _) = http::Request::new(()).into_parts();
+        let rejection = Extension::<u32>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        let e = HttpError::from_err(rejection);
+        assert_eq!(e.status_code(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            e.get::<serde_json::Value>(SPAN_DATA_KEY),
+            Some(serde_json::json!({ "name": "extract" }))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "otel")]
+    fn http_error_with_current_otel_span() {
+        use opentelemetry::{
+            trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+            Context,
+        };
+
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let _context = Context::current()
+            .with_remote_span_context(span_context)
+            .attach();
+
+        let e = http_error!(BAD_GATEWAY).with_current_span();
+        assert_eq!(
+            e.get::<serde_json::Value>(SPAN_DATA_KEY),
+            Some(serde_json::json!({
+                "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736",
+                "span_id": "00f067aa0ba902b7",
+            }))
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use core::{fmt, ops::Deref};
+use http::StatusCode;
+use std::borrow::Cow;
+
+use crate::HttpError;
+
+/// A [`HttpError`] whose status code is part of its type, so signatures can express exactly which
+/// statuses a function may produce, e.g. `Result<T, HttpErrorOf<404>>`. It converts into
+/// [`HttpError`], [`anyhow::Error`] and error responses, and dereferences to [`HttpError`] for
+/// inspection. `STATUS` must be a `4xx` or `5xx` status code, which is checked at compile time.
+///
+/// ```
+/// use anyhow_http::{HttpError, HttpErrorOf};
+///
+/// fn find_user(id: u64) -> Result<String, HttpErrorOf<404>> {
+///     Err(HttpErrorOf::new().with_reason(format!("user {id} not found")))
+/// }
+///
+/// let err: HttpError = find_user(1).unwrap_err().into();
+/// assert_eq!(err.status_code(), 404);
+/// assert_eq!(err.reason().as_deref(), Some("user 1 not found"));
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct HttpErrorOf<const STATUS: u16>(HttpError);
+
+impl<const STATUS: u16> HttpErrorOf<STATUS> {
+    /// Creates a [`HttpErrorOf`] with status code `STATUS`.
+    pub fn new() -> Self {
+        const {
+            assert!(
+                STATUS >= 400 && STATUS <= 599,
+                "`HttpErrorOf` requires a 4xx or 5xx status code"
+            )
+        };
+        Self(HttpError::from_status_code(Self::status_code()))
+    }
+
+    /// Returns the status code `STATUS`.
+    pub fn status_code() -> StatusCode {
+        StatusCode::from_u16(STATUS).expect("status code is validated in `new`")
+    }
+
+    /// Sets the error reason, see [`HttpError::with_reason`].
+    pub fn with_reason<S: Into<Cow<'static, str>>>(self, reason: S) -> Self {
+        self.map(|e| e.with_reason(reason))
+    }
+
+    /// Adds a key-value pair to the data, see [`HttpError::with_key_value`].
+    pub fn with_key_value<K, V>(self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: serde::Serialize + Sync + Send + 'static,
+    {
+        self.map(|e| e.with_key_value(key, value))
+    }
+
+    /// Sets the source error, see [`HttpError::with_source_err`].
+    pub fn with_source_err<E>(self, err: E) -> Self
+    where
+        E: Into<anyhow::Error>,
+    {
+        self.map(|e| e.with_source_err(err))
+    }
+
+    /// Applies any builder of [`HttpError`]. The status code is restored afterwards, so the error
+    /// keeps the status of its type.
+    ///
+    /// ```
+    /// use anyhow_http::HttpErrorOf;
+    ///
+    /// let err = HttpErrorOf::<409>::new().map(|e| e.with_public_message("order exists"));
+    /// assert_eq!(err.public_message(), Some("order exists"));
+    /// ```
+    pub fn map<F>(self, f: F) -> Self
+    where
+        F: FnOnce(HttpError) -> HttpError,
+    {
+        let mut http_error = f(self.0);
+        http_error.status_code = Self::status_code();
+        Self(http_error)
+    }
+
+    /// Returns the inner [`HttpError`].
+    pub fn into_inner(self) -> HttpError {
+        self.0
+    }
+}
+
+impl<const STATUS: u16> Default for HttpErrorOf<STATUS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const STATUS: u16> Deref for HttpErrorOf<STATUS> {
+    type Target = HttpError;
+
+    fn deref(&self) -> &HttpError {
+        &self.0
+    }
+}
+
+impl<const STATUS: u16> fmt::Debug for HttpErrorOf<STATUS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<const STATUS: u16> fmt::Display for HttpErrorOf<STATUS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<const STATUS: u16> From<HttpErrorOf<STATUS>> for HttpError {
+    fn from(err: HttpErrorOf<STATUS>) -> Self {
+        err.0
+    }
+}
+
+impl<const STATUS: u16> From<HttpErrorOf<STATUS>> for anyhow::Error {
+    fn from(err: HttpErrorOf<STATUS>) -> Self {
+        err.0.into()
+    }
+}
+
+/// Narrows a [`HttpError`] to its typed status, returning the error unchanged if its status code
+/// is not `STATUS`.
+impl<const STATUS: u16> TryFrom<HttpError> for HttpErrorOf<STATUS> {
+    type Error = HttpError;
+
+    fn try_from(http_error: HttpError) -> Result<Self, HttpError> {
+        if http_error.status_code() == STATUS {
+            Ok(Self(http_error))
+        } else {
+            Err(http_error)
+        }
+    }
+}
+
+/// Renders the error like a bare [`HttpError`].
+#[cfg(all(feature = "axum", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "axum", feature = "json"))))]
+impl<const STATUS: u16> axum::response::IntoResponse for HttpErrorOf<STATUS> {
+    fn into_response(self) -> axum::response::Response {
+        self.0.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_error;
+
+    #[test]
+    fn http_error_of_status() {
+        let err = HttpErrorOf::<404>::new()
+            .with_reason("user not found")
+            .with_key_value("id", 1)
+            .map(|e| e.with_status_code(StatusCode::BAD_REQUEST));
+        assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(err.get::<u64>("id"), Some(1));
+
+        let http_error: HttpError = err.clone().into();
+        assert_eq!(
+            http_error,
+            http_error!(NOT_FOUND, "user not found").with_key_value("id", 1)
+        );
+        assert_eq!(HttpErrorOf::<404>::try_from(http_error), Ok(err));
+        assert!(HttpErrorOf::<409>::try_from(http_error!(NOT_FOUND)).is_err());
+    }
+
+    #[test]
+    fn http_error_of_conversions() {
+        fn lookup() -> Result<(), HttpErrorOf<503>> {
+            Err(HttpErrorOf::new().with_source_err(anyhow::anyhow!("db down")))
+        }
+
+        fn handler() -> anyhow::Result<()> {
+            lookup()?;
+            Ok(())
+        }
+
+        let err = HttpError::from(handler().unwrap_err());
+        assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(err.source().unwrap().to_string(), "db down");
+    }
+}
@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// A field-level entry of a grouped error, e.g. a single failed validation rule.
+///
+/// Violations are attached to a [`HttpError`](crate::HttpError) with
+/// [`with_violations`](crate::HttpError::with_violations) and are rendered by formatters as a
+/// list of errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    field: Cow<'static, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Cow<'static, str>>,
+}
+
+impl Violation {
+    /// Creates a [`Violation`] for a field name or pointer (e.g. `/items/0/name`).
+    pub fn new<S: Into<Cow<'static, str>>>(field: S) -> Self {
+        Self {
+            field: field.into(),
+            code: None,
+            message: None,
+        }
+    }
+
+    /// Sets a machine-readable code.
+    pub fn with_code<S: Into<Cow<'static, str>>>(mut self, code: S) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Sets a human-readable message.
+    pub fn with_message<S: Into<Cow<'static, str>>>(mut self, message: S) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Returns the field name or pointer.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the code if any.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Returns the message if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn violation_builder() {
+        let v = Violation::new("email")
+            .with_code("invalid_format")
+            .with_message("not an email");
+        assert_eq!(v.field(), "email");
+        assert_eq!(v.code(), Some("invalid_format"));
+        assert_eq!(v.message(), Some("not an email"));
+    }
+
+    #[test]
+    fn violation_serialize() {
+        let v = Violation::new("email").with_code("required");
+        assert_eq!(
+            serde_json::to_string(&v).unwrap(),
+            r#"{"field":"email","code":"required"}"#
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! The profile is set process-wide, so its effect on the built-in formatters is tested in a test
+//! binary of its own.
+use anyhow_http::{
+    http_error,
+    response::{FormatResponse, Logfmt},
+    set_profile, HttpError, Profile,
+};
+
+fn http_error() -> HttpError {
+    http_error!(INTERNAL_SERVER_ERROR, "query failed")
+        .with_source_err(anyhow::anyhow!("connection refused"))
+}
+
+#[cfg(all(feature = "axum", feature = "json"))]
+async fn problem_details(http_error: HttpError) -> serde_json::Value {
+    use anyhow_http::{
+        axum::NegotiateLayer,
+        response::{HttpResult, Negotiate},
+    };
+    use axum::{body::Body, routing::get, Router};
+    use http::{header::ACCEPT, Request};
+    use tower::ServiceExt;
+
+    let handler = move || {
+        let result: HttpResult<(), Negotiate> = Err(http_error.clone().into());
+        async move { result }
+    };
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(NegotiateLayer::new());
+    let resp = app
+        .oneshot(
+            Request::get("/")
+                .header(ACCEPT, "application/problem+json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn formatters_per_profile() {
+    set_profile(Profile::Development);
+    assert_eq!(
+        Logfmt::format_response(&http_error()),
+        r#"status=500 reason="query failed" source="connection refused""#
+    );
+    #[cfg(feature = "json")]
+    {
+        use anyhow_http::response::{Connect, Json};
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&Json::format_response(&http_error())).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": { "message": "query failed", "source": ["connection refused"] }
+            })
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&Connect::format_response(&http_error())).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "code": "internal",
+                "message": "query failed",
+                "details": [{
+                    "type": "google.rpc.DebugInfo",
+                    "value": "ChJjb25uZWN0aW9uIHJlZnVzZWQ",
+                    "debug": { "stackEntries": ["connection refused"] },
+                }],
+            })
+        );
+    }
+    #[cfg(all(feature = "axum", feature = "json"))]
+    {
+        let body = problem_details(http_error()).await;
+        assert_eq!(body["detail"], "query failed");
+        assert_eq!(body["source"], serde_json::json!(["connection refused"]));
+    }
+
+    set_profile(Profile::Staging);
+    assert_eq!(
+        Logfmt::format_response(&http_error()),
+        r#"status=500 reason="query failed""#
+    );
+    #[cfg(feature = "json")]
+    {
+        use anyhow_http::response::{Connect, Json};
+
+        assert_eq!(
+            Json::format_response(&http_error()),
+            r#"{"error":{"message":"query failed"}}"#
+        );
+        assert_eq!(
+            Connect::format_response(&http_error()),
+            r#"{"code":"internal","message":"query failed"}"#
+        );
+    }
+    #[cfg(all(feature = "axum", feature = "json"))]
+    {
+        let body = problem_details(http_error()).await;
+        assert_eq!(body["detail"], "query failed");
+        assert!(body.get("source").is_none());
+    }
+
+    set_profile(Profile::Production);
+    assert_eq!(
+        Logfmt::format_response(&http_error()),
+        r#"status=500 reason="Internal Server Error""#
+    );
+    #[cfg(feature = "json")]
+    {
+        use anyhow_http::response::{Connect, Json};
+
+        assert_eq!(
+            Json::format_response(&http_error()),
+            r#"{"error":{"message":"Internal Server Error"}}"#
+        );
+        assert_eq!(
+            Connect::format_response(&http_error()),
+            r#"{"code":"internal","message":"Internal Server Error"}"#
+        );
+    }
+    #[cfg(all(feature = "axum", feature = "json"))]
+    {
+        let body = problem_details(http_error()).await;
+        assert_eq!(body["detail"], "Internal Server Error");
+        assert!(body.get("source").is_none());
+    }
+}
@@ -0,0 +1,25 @@
+//! The maximum reason length is set process-wide, so its effect on the built-in formatters is
+//! tested in a test binary of its own.
+#![cfg(feature = "json")]
+use anyhow_http::{
+    http_error,
+    response::{set_max_reason_len, FormatResponse, Json, Logfmt},
+};
+
+#[test]
+fn max_reason_len() {
+    set_max_reason_len(12);
+
+    let err = http_error!(BAD_REQUEST, "unknown user {}", "x".repeat(4096));
+    assert_eq!(
+        Json::format_response(&err),
+        r#"{"error":{"message":"unknown u…"}}"#
+    );
+    assert!(String::from_utf8_lossy(&Logfmt::format_response(&err)).contains("unknown u…"));
+
+    let err = http_error!(NOT_FOUND, "not found");
+    assert_eq!(
+        Json::format_response(&err),
+        r#"{"error":{"message":"not found"}}"#
+    );
+}
@@ -0,0 +1,34 @@
+//! The sanitizer is set process-wide, so its effect on the built-in formatters is tested in a test
+//! binary of its own.
+#![cfg(feature = "json")]
+use anyhow_http::{
+    http_error,
+    response::{FormatResponse, Json, Logfmt},
+    sanitize::{set_sanitizer, Sanitizer},
+};
+
+#[test]
+fn sanitizer_scrubs_responses() {
+    set_sanitizer(Sanitizer::new().with_fn(|s| {
+        s.contains("jane@example.com")
+            .then(|| s.replace("jane@example.com", "[email]"))
+    }));
+
+    let err = http_error!(CONFLICT, "jane@example.com is taken")
+        .with_key_value("email", "jane@example.com")
+        .with_key_value("attempts", 3);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&Json::format_response(&err)).unwrap(),
+        serde_json::json!({
+            "error": { "attempts": 3, "email": "[email]", "message": "[email] is taken" }
+        })
+    );
+    let line = String::from_utf8(Logfmt::format_response(&err).to_vec()).unwrap();
+    assert!(!line.contains("jane@example.com"));
+
+    // the error itself is unchanged, e.g. for logs
+    assert_eq!(
+        err.get::<String>("email").as_deref(),
+        Some("jane@example.com")
+    );
+}
@@ -0,0 +1,36 @@
+//! The error sink is set process-wide, so it is tested in a test binary of its own.
+use anyhow_http::{
+    http_error,
+    response::Logfmt,
+    signal::{set_error_sink, ErrorSignal, StatusClass},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+#[test]
+fn error_sink() {
+    let signals: Arc<Mutex<Vec<ErrorSignal>>> = Default::default();
+    let start = SystemTime::now();
+    set_error_sink({
+        let signals = signals.clone();
+        move |signal| signals.lock().unwrap().push(signal.clone())
+    });
+
+    http_error!(NOT_FOUND).into_http_response::<Logfmt>();
+    http_error!(SERVICE_UNAVAILABLE)
+        .with_key_value(
+            "request",
+            serde_json::json!({ "method": "GET", "path": "/users/1", "route": "/users/:id" }),
+        )
+        .into_http_response::<Logfmt>();
+
+    let signals = signals.lock().unwrap();
+    assert_eq!(signals.len(), 2);
+    assert_eq!(signals[0].status_class(), StatusClass::ClientError);
+    assert_eq!(signals[0].route(), None);
+    assert_eq!(signals[1].status_class(), StatusClass::ServerError);
+    assert_eq!(signals[1].route(), Some("/users/:id"));
+    assert!(signals.iter().all(|s| s.timestamp() >= start));
+}